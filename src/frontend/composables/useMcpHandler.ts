@@ -42,32 +42,11 @@ export function useMcpHandler() {
    * 显示MCP弹窗
    */
   async function showMcpDialog(request: any) {
-    // 获取Telegram配置，检查是否需要隐藏前端弹窗
-    let shouldShowFrontendPopup = true
-    try {
-      const telegramConfig = await invoke('get_telegram_config')
-      // 如果Telegram启用且配置了隐藏前端弹窗，则不显示前端弹窗
-      if (telegramConfig && (telegramConfig as any).enabled && (telegramConfig as any).hide_frontend_popup) {
-        shouldShowFrontendPopup = false
-        console.log('🔕 根据Telegram配置，隐藏前端弹窗')
-      }
-    }
-    catch (error) {
-      console.error('获取Telegram配置失败:', error)
-      // 配置获取失败时，保持默认行为（显示弹窗）
-    }
+    // 是否使用弹窗已由交互后端路由决定，这里直接显示
+    mcpRequest.value = request
+    showMcpPopup.value = true
 
-    // 根据配置决定是否显示前端弹窗
-    if (shouldShowFrontendPopup) {
-      // 设置请求数据和显示状态
-      mcpRequest.value = request
-      showMcpPopup.value = true
-    }
-    else {
-      console.log('🔕 跳过前端弹窗显示，仅使用Telegram交互')
-    }
-
-    // 播放音频通知（无论是否显示弹窗都播放）
+    // 播放音频通知
     try {
       await invoke('play_notification_sound')
     }
@@ -75,9 +54,9 @@ export function useMcpHandler() {
       console.error('播放音频通知失败:', error)
     }
 
-    // 启动Telegram同步（无论是否显示弹窗都启动）
+    // 启动Telegram同步（广播模式下Telegram独立询问，不再同步）
     try {
-      if (request?.message) {
+      if (request?.message && !request.skip_telegram_sync) {
         await invoke('start_telegram_sync', {
           message: request.message,
           predefinedOptions: request.predefined_options || [],
@@ -122,6 +101,16 @@ export function useMcpHandler() {
       await listen('mcp-request', (event) => {
         showMcpDialog(event.payload)
       })
+      // 常驻模式下弹窗超时后由后端返回默认答复，这里只需关闭弹窗
+      await listen('mcp-request-timeout', () => {
+        showMcpPopup.value = false
+        mcpRequest.value = null
+      })
+      // 广播模式下其他渠道已答复，关闭弹窗
+      await listen('mcp-request-cancelled', () => {
+        showMcpPopup.value = false
+        mcpRequest.value = null
+      })
     }
     catch (error) {
       console.error('设置MCP事件监听器失败:', error)
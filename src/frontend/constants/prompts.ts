@@ -37,9 +37,14 @@ export const PROMPT_SECTIONS = {
   memory: {
     base: ``,
     detail: `记忆管理使用细节：
-- 对话开始时查询 \`回忆\` 参数 \`project_path\` 为 git 的根目录
+- 对话开始时查询 \`回忆\` 参数 \`project_path\` 为 git 的根目录，可用 \`query\` 描述当前任务以只获取相关记忆
 - 当发现用户输入"请记住："时，要对用户的消息进行总结后调用 \`记忆\` 的 add 功能添加记忆
 - 使用 \`记忆\` 的 add 功能添加新记忆（content + category: rule/preference/pattern/context）
+- 与具体项目无关的个人偏好（如回复语言）使用 \`scope: user\` 存为全局记忆，所有项目共享
+- 记忆过时或有误时，先用 \`列表\` 查看记忆ID，再用 \`更新\` 或 \`删除\` 按ID修改，不要重复添加
+- 添加记忆时如提示存在相似情报，判断两者是否表达同一意思，重复时用 \`删除\` 去掉其一
+- 记忆较多或内容重复时，使用 \`整理\` 合并完全相同的记忆
+- 用户要求共享或同步记忆时，使用 \`导出\`/\`导入\`（\`file_path\` 指向 .json 或 .md 情报包）
 - 仅在重要变更时更新记忆，保持简洁`,
   } as PromptSection,
 
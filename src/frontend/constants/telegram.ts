@@ -9,11 +9,19 @@ export const API_EXAMPLES = {
   proxy_example: 'https://your-proxy.com/bot',
 } as const
 
+/** 投递模式 */
+export const DELIVERY_MODE = {
+  sync: 'sync',
+  telegramOnly: 'telegram_only',
+  broadcast: 'broadcast',
+} as const
+
 /** 默认 Telegram 配置 */
 export const DEFAULT_CONFIG = {
   enabled: false,
   bot_token: '',
   chat_id: '',
   hide_frontend_popup: false,
+  delivery_mode: DELIVERY_MODE.sync,
   api_base_url: API_BASE_URL,
 } as const
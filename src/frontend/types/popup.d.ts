@@ -5,6 +5,10 @@ export interface McpRequest {
   message: string
   predefined_options?: string[]
   is_markdown?: boolean
+  timeout_secs?: number
+  timeout_default_option?: string
+  project_path?: string
+  skip_telegram_sync?: boolean
 }
 
 // 自定义prompt类型定义
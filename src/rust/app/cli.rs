@@ -1,85 +1,485 @@
-use crate::config::load_standalone_telegram_config;
-use crate::telegram::handle_telegram_only_mcp_request;
+use crate::config::{get_standalone_config_path, load_standalone_config, save_standalone_config, AppConfig};
+use crate::constants::mcp::BACKEND_POPUP;
+use crate::constants::telegram as telegram_constants;
+use crate::constants::themes::{get_theme, get_theme_config_path, user_theme_file, McpTheme};
+use crate::interaction::{select_backends, CancelSignal};
+use crate::mcp::handlers::is_headless_environment;
+use crate::mcp::types::PopupRequest;
 use crate::log_important;
 use crate::app::builder::run_tauri_app;
+use crate::daemon::{ping_daemon, DAEMON_ARG};
 use anyhow::Result;
+use clap::{ArgAction, ArgGroup, Args, CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+/// 处理单个 MCP 请求的参数，寸止通过它启动弹窗进程
+pub const MCP_REQUEST_ARG: &str = "--mcp-request";
+
+/// 当前进程处理的 MCP 请求文件，由 `--mcp-request` 或 `popup <文件>` 设置
+static MCP_REQUEST_FILE: OnceLock<PathBuf> = OnceLock::new();
+
+/// 当前进程处理的 MCP 请求，弹窗界面从这里读取请求，并在超时后返回默认答复
+static MCP_REQUEST: OnceLock<PopupRequest> = OnceLock::new();
+
+/// 是否已输出答复，用户答复与超时答复只输出先到的一个
+static MCP_RESPONDED: AtomicBool = AtomicBool::new(false);
+
+/// 寸止 - 智能代码审查工具
+#[derive(Parser, Debug)]
+#[command(name = "等一下", version, disable_version_flag = true, args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    /// 处理 MCP 请求文件（供寸止调用）
+    #[arg(long = "mcp-request", value_name = "文件")]
+    mcp_request: Option<PathBuf>,
+
+    /// 以常驻模式运行，供寸止复用弹窗
+    #[arg(long, conflicts_with = "mcp_request")]
+    daemon: bool,
+
+    /// 显示版本信息
+    #[arg(short = 'v', long = "version", action = ArgAction::Version)]
+    version: Option<bool>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// 显示弹窗，把用户的答复输出到标准输出
+    Popup(PopupArgs),
+    /// 查看或修改配置
+    #[command(subcommand)]
+    Config(ConfigCommand),
+    /// 查看或切换 MCP 工具主题
+    #[command(subcommand)]
+    Theme(ThemeCommand),
+    /// Telegram Bot 相关操作
+    #[command(subcommand)]
+    Telegram(TelegramCommand),
+    /// 检查配置、常驻弹窗服务和相关命令是否正常
+    Doctor,
+    /// 显示版本信息
+    Version,
+    /// 输出 shell 补全脚本
+    Completions {
+        /// shell 类型
+        shell: Shell,
+    },
+}
+
+#[derive(Args, Debug)]
+#[command(group(ArgGroup::new("source").required(true).args(["file", "message"])))]
+struct PopupArgs {
+    /// 请求文件（与 MCP 请求格式相同），`-` 表示从标准输入读取
+    #[arg(value_name = "文件")]
+    file: Option<String>,
+
+    /// 弹窗消息
+    #[arg(short, long)]
+    message: Option<String>,
+
+    /// 预定义选项，可重复
+    #[arg(short = 'o', long = "option", value_name = "选项", requires = "message")]
+    options: Vec<String>,
+
+    /// 按 Markdown 渲染消息
+    #[arg(long, requires = "message")]
+    markdown: bool,
+
+    /// 等待答复的时长（秒），超时后返回默认答复
+    #[arg(long, value_name = "秒", requires = "message")]
+    timeout: Option<u64>,
+
+    /// 发起请求的项目路径，用于选择交互后端
+    #[arg(long, value_name = "路径", requires = "message")]
+    project: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigCommand {
+    /// 输出配置项的值，不指定时输出全部配置
+    Get {
+        /// 以点分隔的配置项，如 telegram_config.chat_id
+        key: Option<String>,
+    },
+    /// 修改配置项，值按 JSON 解析，解析失败时作为字符串
+    Set {
+        /// 以点分隔的配置项，如 ui_config.theme
+        key: String,
+        value: String,
+    },
+    /// 输出配置文件路径
+    Path,
+}
+
+#[derive(Subcommand, Debug)]
+enum ThemeCommand {
+    /// 列出可用的主题
+    List,
+    /// 切换主题，重新启动 MCP 服务器后生效
+    Use {
+        /// 主题名称
+        name: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum TelegramCommand {
+    /// 使用已保存的 Bot Token 和 Chat ID 发送一条测试消息
+    Test,
+}
 
 /// 处理命令行参数
 pub fn handle_cli_args() -> Result<()> {
-    let args: Vec<String> = std::env::args().collect();
+    let cli = Cli::parse();
+
+    if let Some(request_file) = cli.mcp_request {
+        return handle_mcp_request(&request_file);
+    }
+    if cli.daemon {
+        handle_daemon_mode();
+        return Ok(());
+    }
 
-    match args.len() {
-        // 无参数：正常启动GUI
-        1 => {
+    match cli.command {
+        // 无子命令：正常启动GUI
+        None => run_tauri_app(),
+        Some(Command::Popup(args)) => handle_popup(args)?,
+        Some(Command::Config(command)) => handle_config(command)?,
+        Some(Command::Theme(command)) => handle_theme(command)?,
+        Some(Command::Telegram(TelegramCommand::Test)) => handle_telegram_test()?,
+        Some(Command::Doctor) => handle_doctor(),
+        Some(Command::Version) => print_version(),
+        Some(Command::Completions { shell }) => {
+            clap_complete::generate(shell, &mut Cli::command(), "等一下", &mut std::io::stdout());
+        }
+    }
+
+    Ok(())
+}
+
+/// 当前进程处理的 MCP 请求文件，请求来自标准输入或命令行参数时为 None
+pub fn mcp_request_file() -> Option<&'static Path> {
+    MCP_REQUEST_FILE.get().map(PathBuf::as_path)
+}
+
+/// 当前进程处理的 MCP 请求；设置后弹窗界面显示该请求，并把答复输出到标准输出
+pub fn mcp_request() -> Option<&'static PopupRequest> {
+    MCP_REQUEST.get()
+}
+
+/// 把答复输出到标准输出，已输出过答复时忽略并返回 false
+pub fn respond_mcp_request(response: &str) -> bool {
+    if MCP_RESPONDED.swap(true, Ordering::SeqCst) {
+        return false;
+    }
+    println!("{}", response);
+    let _ = std::io::Write::flush(&mut std::io::stdout());
+    true
+}
+
+/// 是否以处理单个请求的方式启动，此时标准输出只用于输出答复
+///
+/// 日志系统在解析参数之前初始化，因此直接检查原始参数
+pub fn is_request_invocation(args: &[String]) -> bool {
+    matches!(args.get(1).map(String::as_str), Some(MCP_REQUEST_ARG) | Some("popup"))
+}
+
+/// 处理MCP请求
+fn handle_mcp_request(request_file: &Path) -> Result<()> {
+    let request_json = std::fs::read_to_string(request_file)?;
+    let request: PopupRequest = serde_json::from_str(&request_json)?;
+    let _ = MCP_REQUEST_FILE.set(request_file.to_path_buf());
+    dispatch_request(MCP_REQUEST.get_or_init(|| request))
+}
+
+/// 按路由规则依次尝试交互后端；轮到弹窗时在当前进程中启动GUI
+fn dispatch_request(request: &PopupRequest) -> Result<()> {
+    let config = load_standalone_config().unwrap_or_else(|e| {
+        log_important!(warn, "加载配置失败: {}，使用默认配置", e);
+        Default::default()
+    });
+
+    for backend in select_backends(&config, request) {
+        if backend.name() == BACKEND_POPUP {
+            // GUI 会自行输出响应并退出，设置了超时时到期返回默认答复
             run_tauri_app();
+            return Ok(());
+        }
+
+        match backend.interact(request, &CancelSignal::new()) {
+            Ok(response) => {
+                // 输出JSON响应到stdout（MCP协议要求）
+                println!("{}", response);
+                return Ok(());
+            }
+            Err(e) => {
+                log_important!(warn, "交互后端 {} 失败，尝试下一个: {}", backend.name(), e);
+            }
+        }
+    }
+
+    log_important!(error, "没有可用的交互后端处理请求");
+    std::process::exit(1);
+}
+
+/// 从文件、标准输入或命令行参数构造请求，按 MCP 请求处理
+fn handle_popup(args: PopupArgs) -> Result<()> {
+    let request = match (args.file, args.message) {
+        (Some(file), _) if file == "-" => {
+            let mut content = String::new();
+            std::io::stdin().read_to_string(&mut content)?;
+            serde_json::from_str(&content)?
         }
-        // 单参数：帮助或版本
-        2 => {
-            match args[1].as_str() {
-                "--help" | "-h" => print_help(),
-                "--version" | "-v" => print_version(),
-                _ => {
-                    eprintln!("未知参数: {}", args[1]);
-                    print_help();
-                    std::process::exit(1);
-                }
+        (Some(file), _) => return handle_mcp_request(Path::new(&file)),
+        (None, message) => PopupRequest {
+            id: uuid::Uuid::new_v4().to_string(),
+            message: message.unwrap_or_default(),
+            predefined_options: (!args.options.is_empty()).then_some(args.options),
+            is_markdown: args.markdown,
+            timeout_secs: args.timeout,
+            timeout_default_option: None,
+            project_path: args.project,
+            skip_telegram_sync: false,
+        },
+    };
+
+    dispatch_request(MCP_REQUEST.get_or_init(|| request))
+}
+
+fn handle_config(command: ConfigCommand) -> Result<()> {
+    match command {
+        ConfigCommand::Path => println!("{}", get_standalone_config_path()?.display()),
+        ConfigCommand::Get { key } => {
+            let config = serde_json::to_value(load_standalone_config()?)?;
+            let value = match key.as_deref() {
+                Some(key) => config_value(&config, key).ok_or_else(|| anyhow::anyhow!("未知配置项: {}", key))?,
+                None => &config,
+            };
+            match value {
+                serde_json::Value::String(text) => println!("{}", text),
+                other => println!("{}", serde_json::to_string_pretty(other)?),
             }
         }
-        // 多参数：MCP请求模式
-        _ => {
-            if args[1] == "--mcp-request" && args.len() >= 3 {
-                handle_mcp_request(&args[2])?;
-            } else {
-                eprintln!("无效的命令行参数");
-                print_help();
-                std::process::exit(1);
+        ConfigCommand::Set { key, value } => {
+            let config = updated_config(&load_standalone_config()?, &key, &value)?;
+            save_standalone_config(&config)?;
+            println!("已更新 {}，设置界面正在运行时需重新打开后生效", key);
+        }
+    }
+    Ok(())
+}
+
+/// 按点分隔的路径读取配置项
+fn config_value<'a>(config: &'a serde_json::Value, key: &str) -> Option<&'a serde_json::Value> {
+    key.split('.').try_fold(config, |value, part| value.get(part))
+}
+
+/// 按点分隔的路径修改已有的配置项，不存在的配置项视为拼写错误
+fn set_config_value(config: &mut serde_json::Value, key: &str, new_value: serde_json::Value) -> Result<()> {
+    let mut value = config;
+    for part in key.split('.') {
+        value = value
+            .as_object_mut()
+            .and_then(|object| object.get_mut(part))
+            .ok_or_else(|| anyhow::anyhow!("未知配置项: {}", key))?;
+    }
+    *value = new_value;
+    Ok(())
+}
+
+/// 修改配置项并校验类型
+///
+/// 值先按 JSON 解析，解析失败或类型不符时作为字符串，如 Chat ID 这类由数字组成的字符串
+fn updated_config(config: &AppConfig, key: &str, raw: &str) -> Result<AppConfig> {
+    let apply = |new_value: serde_json::Value| -> Result<AppConfig> {
+        let mut value = serde_json::to_value(config)?;
+        set_config_value(&mut value, key, new_value)?;
+        serde_json::from_value(value).map_err(|e| anyhow::anyhow!("配置项 {} 的值无效: {}", key, e))
+    };
+    match serde_json::from_str::<serde_json::Value>(raw) {
+        Ok(parsed) if !parsed.is_string() => apply(parsed).or_else(|e| apply(raw.into()).map_err(|_| e)),
+        _ => apply(raw.into()),
+    }
+}
+
+fn handle_theme(command: ThemeCommand) -> Result<()> {
+    let available = McpTheme::available_themes();
+    match command {
+        ThemeCommand::List => {
+            let current = &get_theme().name;
+            for (name, description) in available {
+                let marker = if name == current { "*" } else { " " };
+                println!("{} {:<14}{}", marker, name, description);
+            }
+        }
+        ThemeCommand::Use { name } => {
+            if !available.iter().any(|(theme, _)| *theme == name) {
+                let names: Vec<&str> = available.iter().map(|(theme, _)| *theme).collect();
+                anyhow::bail!("未知主题: {}，可用主题: {}", name, names.join(", "));
+            }
+            let path = user_theme_file().ok_or_else(|| anyhow::anyhow!("无法获取用户目录"))?;
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&path, serde_json::to_string_pretty(&McpTheme::from_name(&name))?)?;
+            println!("已切换主题为 {}，重新启动 MCP 服务器后生效", name);
+
+            // 可执行文件同目录的 theme.json 优先级更高
+            if let Some(active) = get_theme_config_path().filter(|active| *active != path) {
+                println!("注意: {} 优先于用户主题配置，需删除后切换才会生效", active.display());
             }
         }
     }
+    Ok(())
+}
 
+fn handle_telegram_test() -> Result<()> {
+    let telegram = load_standalone_config()?.telegram_config;
+    // 使用默认API URL时传递None，否则传递自定义URL
+    let api_url = (telegram.api_base_url != telegram_constants::API_BASE_URL).then_some(telegram.api_base_url.as_str());
+    let result = tokio::runtime::Runtime::new()?.block_on(crate::telegram::core::test_telegram_connection_with_api_url(
+        &telegram.bot_token,
+        &telegram.chat_id,
+        api_url,
+    ))?;
+    println!("{}", result);
     Ok(())
 }
 
-/// 处理MCP请求
-fn handle_mcp_request(request_file: &str) -> Result<()> {
-    // 检查Telegram配置，决定是否启用纯Telegram模式
-    match load_standalone_telegram_config() {
-        Ok(telegram_config) => {
-            if telegram_config.enabled && telegram_config.hide_frontend_popup {
-                // 纯Telegram模式：不启动GUI，直接处理
-                if let Err(e) = tokio::runtime::Runtime::new()
-                    .unwrap()
-                    .block_on(handle_telegram_only_mcp_request(request_file))
-                {
-                    log_important!(error, "处理Telegram请求失败: {}", e);
-                    std::process::exit(1);
-                }
-            } else {
-                // 正常模式：启动GUI处理弹窗
-                run_tauri_app();
+/// 逐项检查运行环境，有错误时以非零状态退出
+fn handle_doctor() {
+    let mut errors = 0;
+    let mut report = |ok: Option<bool>, item: &str, detail: String| {
+        let label = match ok {
+            Some(true) => "[正常]",
+            Some(false) => {
+                errors += 1;
+                "[错误]"
             }
+            None => "[提示]",
+        };
+        println!("{} {}: {}", label, item, detail);
+    };
+
+    let config = match get_standalone_config_path().and_then(|path| load_standalone_config().map(|config| (path, config))) {
+        Ok((path, config)) => {
+            let state = if path.exists() { "已加载" } else { "不存在，使用默认配置" };
+            report(Some(true), "配置文件", format!("{} ({})", path.display(), state));
+            config
         }
         Err(e) => {
-            log_important!(warn, "加载Telegram配置失败: {}，使用默认GUI模式", e);
-            // 配置加载失败时，使用默认行为（启动GUI）
-            run_tauri_app();
+            report(Some(false), "配置文件", format!("读取失败: {}", e));
+            AppConfig::default()
         }
+    };
+
+    match sibling_command("寸止") {
+        Some(path) => report(Some(true), "MCP 服务器", path.display().to_string()),
+        None => report(Some(false), "MCP 服务器", "未找到寸止命令，请确认与等一下安装在同一目录或位于 PATH 中".to_string()),
+    }
+
+    if ping_daemon() {
+        report(Some(true), "常驻弹窗服务", "正在运行".to_string());
+    } else {
+        report(None, "常驻弹窗服务", format!("未运行，可通过 等一下 {} 启动", DAEMON_ARG));
+    }
+    if is_headless_environment() {
+        report(None, "图形环境", "未检测到图形环境，弹窗需要常驻弹窗服务或改用其他交互后端".to_string());
+    } else {
+        report(Some(true), "图形环境", "可用".to_string());
+    }
+
+    let telegram = &config.telegram_config;
+    if !telegram.enabled {
+        report(None, "Telegram", "未启用".to_string());
+    } else if telegram.bot_token.trim().is_empty() || telegram.chat_id.trim().is_empty() {
+        report(Some(false), "Telegram", "已启用但缺少 Bot Token 或 Chat ID".to_string());
+    } else {
+        report(Some(true), "Telegram", "已配置，可运行 等一下 telegram test 发送测试消息".to_string());
+    }
+
+    let theme_source = get_theme_config_path().map_or("内置".to_string(), |path| path.display().to_string());
+    report(Some(true), "MCP 主题", format!("{} ({})", get_theme().name, theme_source));
+
+    if errors > 0 {
+        std::process::exit(1);
     }
-    Ok(())
 }
 
-/// 显示帮助信息
-fn print_help() {
-    println!("寸止 - 智能代码审查工具");
-    println!();
-    println!("用法:");
-    println!("  等一下                    启动设置界面");
-    println!("  等一下 --mcp-request <文件>  处理 MCP 请求");
-    println!("  等一下 --help             显示此帮助信息");
-    println!("  等一下 --version          显示版本信息");
+/// 查找与当前程序同目录或位于 PATH 中的命令
+fn sibling_command(name: &str) -> Option<PathBuf> {
+    let file_name = format!("{}{}", name, std::env::consts::EXE_SUFFIX);
+    let sibling = std::env::current_exe().ok()?.parent().map(|dir| dir.join(&file_name));
+    let in_path = std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).map(|dir| dir.join(&file_name)).collect::<Vec<_>>())
+        .unwrap_or_default();
+    sibling.into_iter().chain(in_path).find(|path| path.is_file())
+}
+
+/// 以常驻模式启动UI
+fn handle_daemon_mode() {
+    if ping_daemon() {
+        eprintln!("常驻弹窗服务已在运行");
+        std::process::exit(1);
+    }
+
+    log_important!(info, "以常驻模式启动弹窗服务");
+    run_tauri_app();
 }
 
 /// 显示版本信息
 fn print_version() {
     println!("寸止 v{}", env!("CARGO_PKG_VERSION"));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Cli, clap::Error> {
+        Cli::try_parse_from(std::iter::once("等一下").chain(args.iter().copied()))
+    }
+
+    #[test]
+    fn test_cli_parsing() {
+        Cli::command().debug_assert();
+
+        // 兼容寸止的调用方式，多余参数报错
+        assert_eq!(parse(&["--mcp-request", "req.json"]).unwrap().mcp_request, Some(PathBuf::from("req.json")));
+        assert!(parse(&["--mcp-request", "req.json", "--foo"]).is_err());
+        assert!(parse(&["--mcp-request", "req.json", "doctor"]).is_err());
+        assert!(parse(&["--daemon"]).unwrap().daemon);
+        assert_eq!(parse(&["-v"]).unwrap_err().kind(), clap::error::ErrorKind::DisplayVersion);
+
+        // 弹窗需要请求文件或消息之一
+        assert!(parse(&["popup"]).is_err());
+        assert!(parse(&["popup", "-", "--message", "hi"]).is_err());
+        let cli = parse(&["popup", "--message", "继续吗", "-o", "是", "-o", "否"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Popup(PopupArgs { ref options, .. })) if options.len() == 2));
+    }
+
+    #[test]
+    fn test_updated_config() {
+        let config = AppConfig::default();
+        let value = serde_json::to_value(&config).unwrap();
+        assert!(config_value(&value, "telegram_config.chat_id").is_some());
+        assert!(config_value(&value, "telegram_config.missing").is_none());
+
+        let config = updated_config(&config, "telegram_config.enabled", "true").unwrap();
+        assert!(config.telegram_config.enabled);
+        // 数字形式的字符串按字符串保存
+        let config = updated_config(&config, "telegram_config.chat_id", "123456").unwrap();
+        assert_eq!(config.telegram_config.chat_id, "123456");
+        let config = updated_config(&config, "mcp_config.acemcp_batch_size", "20").unwrap();
+        assert_eq!(config.mcp_config.acemcp_batch_size, Some(20));
+
+        assert!(updated_config(&config, "telegram_config.chatid", "1").is_err());
+        assert!(updated_config(&config, "telegram_config.enabled", "maybe").is_err());
+    }
+}
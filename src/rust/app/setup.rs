@@ -1,6 +1,9 @@
 use crate::config::{AppState, load_config_and_apply_window_settings};
 use crate::ui::{initialize_audio_asset_manager, setup_window_event_listeners};
 use crate::ui::exit_handler::setup_exit_handlers;
+use crate::daemon::{is_daemon_mode, start_popup_daemon};
+use crate::app::{mcp_request, respond_mcp_request};
+use crate::mcp::types::build_timeout_response;
 use crate::log_important;
 use tauri::{AppHandle, Manager};
 
@@ -26,5 +29,36 @@ pub async fn setup_application(app_handle: &AppHandle) -> Result<(), String> {
         log_important!(warn, "设置退出处理器失败: {}", e);
     }
 
+    // 处理单个请求且设置了超时：到期后返回默认答复并退出
+    start_request_timeout(app_handle);
+
+    // 常驻模式：隐藏窗口，等待寸止投递弹窗请求
+    if is_daemon_mode() {
+        if let Some(window) = app_handle.get_webview_window("main") {
+            let _ = window.hide();
+        }
+
+        start_popup_daemon(app_handle).map_err(|e| format!("启动常驻弹窗服务失败: {}", e))?;
+    }
+
     Ok(())
 }
+
+/// 弹窗超时后输出默认答复并退出，用户已答复时不做处理
+fn start_request_timeout(app_handle: &AppHandle) {
+    let Some(request) = mcp_request() else {
+        return;
+    };
+    let Some(timeout) = request.timeout() else {
+        return;
+    };
+
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(timeout).await;
+        if respond_mcp_request(&build_timeout_response(request)) {
+            log_important!(warn, "弹窗请求 {} 等待超时，返回默认答复", request.id);
+            let _ = crate::ui::exit::force_exit_app(app_handle).await;
+        }
+    });
+}
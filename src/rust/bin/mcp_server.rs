@@ -1,11 +1,17 @@
 // MCP 服务器入口点
-use cunzhi::{mcp::run_server, utils::auto_init_logger, log_important};
+// 第一个参数是子命令时直接执行代码搜索的索引、检索等命令，否则启动 MCP 服务器（客户端可能附带 --stdio 等参数）
+use cunzhi::{mcp::run_server, mcp::tools::acemcp::cli::{run_cli, Command}, utils::auto_init_logger, log_important};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // 自动初始化日志系统
     auto_init_logger()?;
 
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().is_some_and(|name| Command::parse(name).is_some()) {
+        std::process::exit(run_cli(&args).await);
+    }
+
     log_important!(info, "启动 MCP 服务器");
     run_server().await
 }
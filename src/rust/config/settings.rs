@@ -19,6 +19,8 @@ pub struct AppConfig {
     pub custom_prompt_config: CustomPromptConfig, // 自定义prompt配置
     #[serde(default = "default_shortcut_config")]
     pub shortcut_config: ShortcutConfig, // 自定义快捷键配置
+    #[serde(default = "default_interaction_config")]
+    pub interaction_config: InteractionConfig, // 交互后端路由配置
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -102,6 +104,10 @@ pub struct ReplyConfig {
     pub auto_continue_threshold: u32, // 字符数阈值
     #[serde(default = "default_continue_prompt")]
     pub continue_prompt: String, // 继续回复的提示词
+    #[serde(default = "default_popup_timeout_secs")]
+    pub popup_timeout_secs: u64, // 弹窗默认超时时间（秒），0 表示一直等待
+    #[serde(default = "default_timeout_action")]
+    pub timeout_action: String, // 超时后的默认答复："continue", "option", "no_answer"
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -112,8 +118,26 @@ pub struct McpConfig {
     pub acemcp_token: Option<String>, // acemcp认证令牌
     pub acemcp_batch_size: Option<u32>, // acemcp批处理大小
     pub acemcp_max_lines_per_blob: Option<u32>, // acemcp最大行数/块
+    #[serde(default)]
+    pub acemcp_chunk_overlap_lines: Option<u32>, // acemcp相邻代码块重叠的行数
     pub acemcp_text_extensions: Option<Vec<String>>, // acemcp文件扩展名
     pub acemcp_exclude_patterns: Option<Vec<String>>, // acemcp排除模式
+    #[serde(default)]
+    pub acemcp_backend: Option<String>, // acemcp检索后端："remote"、"local"、"embedding"，未设置时有远程配置则用远程
+    #[serde(default)]
+    pub acemcp_watch: Option<bool>, // acemcp是否在后台监听项目变化并维护索引，未设置时启用
+    #[serde(default)]
+    pub acemcp_upload_concurrency: Option<u32>, // acemcp同时上传的批次数
+    #[serde(default)]
+    pub acemcp_max_batch_bytes: Option<u64>, // acemcp每批上传的最大字节数
+    #[serde(default)]
+    pub acemcp_embedding_base_url: Option<String>, // acemcp向量检索的 OpenAI 兼容端点URL
+    #[serde(default)]
+    pub acemcp_embedding_token: Option<String>, // acemcp向量检索的API密钥
+    #[serde(default)]
+    pub acemcp_embedding_model: Option<String>, // acemcp向量检索使用的模型
+    #[serde(default)]
+    pub acemcp_secret_mode: Option<String>, // acemcp检测到密钥时的处理方式："redact"、"skip"、"off"，项目中的 .acemcpsecrets 可覆盖
 }
 
 // 自定义prompt结构
@@ -185,11 +209,51 @@ pub struct TelegramConfig {
     #[serde(default = "default_telegram_chat_id")]
     pub chat_id: String, // Chat ID
     #[serde(default = "default_telegram_hide_frontend_popup")]
-    pub hide_frontend_popup: bool, // 是否隐藏前端弹窗，仅使用Telegram交互
+    pub hide_frontend_popup: bool, // 旧版开关：是否隐藏前端弹窗，仅使用Telegram交互
+    #[serde(default = "default_telegram_delivery_mode")]
+    pub delivery_mode: String, // 投递模式："sync", "telegram_only", "broadcast"
     #[serde(default = "default_telegram_api_base_url")]
     pub api_base_url: String, // Telegram API基础URL
 }
 
+// 交互后端路由配置
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InteractionConfig {
+    #[serde(default = "default_interaction_rules")]
+    pub rules: Vec<InteractionRule>, // 路由规则，按顺序匹配第一条
+    #[serde(default = "default_fallback_chain")]
+    pub fallback_chain: Vec<String>, // 后端失败或没有规则命中时依次尝试，为空时沿用Telegram配置的行为
+    #[serde(default = "default_idle_threshold_secs")]
+    pub idle_threshold_secs: u64, // 无键鼠操作超过该时长视为离开桌面
+    #[serde(default = "default_broadcast_backends")]
+    pub broadcast_backends: Vec<String>, // 广播模式下同时询问的渠道
+}
+
+// 交互后端路由规则，所有已设置的条件都满足时命中
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InteractionRule {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub project_paths: Vec<String>, // 项目路径前缀或glob，为空表示不限
+    #[serde(default)]
+    pub time_range: Option<String>, // 本地时间段 "HH:MM-HH:MM"，可跨零点
+    #[serde(default)]
+    pub desktop_idle: Option<bool>, // true：离开桌面时；false：在桌面前时
+    pub backends: Vec<String>, // 命中后依次尝试的后端："popup", "telegram", "terminal", "broadcast"
+}
+
+impl TelegramConfig {
+    /// 实际生效的投递模式，兼容只设置了旧版隐藏弹窗开关的配置
+    pub fn effective_delivery_mode(&self) -> &str {
+        if self.delivery_mode == telegram::DELIVERY_MODE_SYNC && self.hide_frontend_popup {
+            telegram::DELIVERY_MODE_TELEGRAM_ONLY
+        } else {
+            &self.delivery_mode
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct AppState {
     pub config: Mutex<AppConfig>,
@@ -209,6 +273,7 @@ impl Default for AppConfig {
             telegram_config: default_telegram_config(),
             custom_prompt_config: default_custom_prompt_config(),
             shortcut_config: default_shortcut_config(),
+            interaction_config: default_interaction_config(),
         }
     }
 }
@@ -248,8 +313,17 @@ pub fn default_mcp_config() -> McpConfig {
         acemcp_token: None,
         acemcp_batch_size: None,
         acemcp_max_lines_per_blob: None,
+        acemcp_chunk_overlap_lines: None,
         acemcp_text_extensions: None,
         acemcp_exclude_patterns: None,
+        acemcp_backend: None,
+        acemcp_watch: None,
+        acemcp_upload_concurrency: None,
+        acemcp_max_batch_bytes: None,
+        acemcp_embedding_base_url: None,
+        acemcp_embedding_token: None,
+        acemcp_embedding_model: None,
+        acemcp_secret_mode: None,
     }
 }
 
@@ -259,6 +333,7 @@ pub fn default_telegram_config() -> TelegramConfig {
         bot_token: default_telegram_bot_token(),
         chat_id: default_telegram_chat_id(),
         hide_frontend_popup: default_telegram_hide_frontend_popup(),
+        delivery_mode: default_telegram_delivery_mode(),
         api_base_url: default_telegram_api_base_url(),
     }
 }
@@ -271,6 +346,31 @@ pub fn default_custom_prompt_config() -> CustomPromptConfig {
     }
 }
 
+pub fn default_interaction_config() -> InteractionConfig {
+    InteractionConfig {
+        rules: default_interaction_rules(),
+        fallback_chain: default_fallback_chain(),
+        idle_threshold_secs: default_idle_threshold_secs(),
+        broadcast_backends: default_broadcast_backends(),
+    }
+}
+
+pub fn default_interaction_rules() -> Vec<InteractionRule> {
+    Vec::new()
+}
+
+pub fn default_fallback_chain() -> Vec<String> {
+    Vec::new()
+}
+
+pub fn default_idle_threshold_secs() -> u64 {
+    mcp::DEFAULT_IDLE_THRESHOLD_SECS
+}
+
+pub fn default_broadcast_backends() -> Vec<String> {
+    mcp::DEFAULT_BROADCAST_BACKENDS.iter().map(|s| s.to_string()).collect()
+}
+
 pub fn default_always_on_top() -> bool {
     window::DEFAULT_ALWAYS_ON_TOP
 }
@@ -307,6 +407,8 @@ pub fn default_reply_config() -> ReplyConfig {
         enable_continue_reply: mcp::DEFAULT_CONTINUE_REPLY_ENABLED,
         auto_continue_threshold: mcp::DEFAULT_AUTO_CONTINUE_THRESHOLD,
         continue_prompt: mcp::DEFAULT_CONTINUE_PROMPT.to_string(),
+        popup_timeout_secs: mcp::DEFAULT_POPUP_TIMEOUT_SECS,
+        timeout_action: mcp::DEFAULT_TIMEOUT_ACTION.to_string(),
     }
 }
 
@@ -342,6 +444,14 @@ pub fn default_continue_prompt() -> String {
     mcp::DEFAULT_CONTINUE_PROMPT.to_string()
 }
 
+pub fn default_popup_timeout_secs() -> u64 {
+    mcp::DEFAULT_POPUP_TIMEOUT_SECS
+}
+
+pub fn default_timeout_action() -> String {
+    mcp::DEFAULT_TIMEOUT_ACTION.to_string()
+}
+
 pub fn default_mcp_tools() -> HashMap<String, bool> {
     use crate::constants::themes::get_theme;
     let theme = get_theme();
@@ -397,6 +507,10 @@ pub fn default_telegram_hide_frontend_popup() -> bool {
     telegram::DEFAULT_HIDE_FRONTEND_POPUP
 }
 
+pub fn default_telegram_delivery_mode() -> String {
+    telegram::DEFAULT_DELIVERY_MODE.to_string()
+}
+
 pub fn default_telegram_api_base_url() -> String {
     telegram::API_BASE_URL.to_string()
 }
@@ -143,8 +143,16 @@ pub fn load_standalone_telegram_config() -> Result<super::settings::TelegramConf
     Ok(config.telegram_config)
 }
 
+/// 独立保存配置文件（用于命令行等不依赖Tauri的场景）
+pub fn save_standalone_config(config: &AppConfig) -> Result<()> {
+    let config_path = get_standalone_config_path()?;
+    fs::write(&config_path, serde_json::to_string_pretty(config)?)?;
+    log::debug!("配置已保存到: {:?}", config_path);
+    Ok(())
+}
+
 /// 获取独立配置文件路径（不依赖Tauri）
-fn get_standalone_config_path() -> Result<PathBuf> {
+pub fn get_standalone_config_path() -> Result<PathBuf> {
     // 使用标准的配置目录
     let config_dir = dirs::config_dir()
         .ok_or_else(|| anyhow::anyhow!("无法获取配置目录"))?
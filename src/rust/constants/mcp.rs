@@ -21,6 +21,127 @@ pub const DEFAULT_AUTO_CONTINUE_THRESHOLD: u32 = 1000;
 /// 默认继续提示词
 pub const DEFAULT_CONTINUE_PROMPT: &str = "汽车人，变形出发！继续执行任务";
 
+/// 默认弹窗超时时间（秒），0 表示一直等待
+pub const DEFAULT_POPUP_TIMEOUT_SECS: u64 = 0;
+
+/// 默认超时动作："continue"（继续提示词）、"option"（预定义选项）、"no_answer"（明确未答复）
+pub const DEFAULT_TIMEOUT_ACTION: &str = "continue";
+
+/// 外层等待弹窗进程时额外给出的宽限时间（秒），让弹窗自身先完成超时处理
+pub const POPUP_TIMEOUT_GRACE_SECS: u64 = 5;
+
+/// 交互后端：桌面弹窗
+pub const BACKEND_POPUP: &str = "popup";
+
+/// 交互后端：Telegram Bot
+pub const BACKEND_TELEGRAM: &str = "telegram";
+
+/// 交互后端：控制终端
+pub const BACKEND_TERMINAL: &str = "terminal";
+
+/// 交互后端：同时向多个渠道发起请求，第一个答复的渠道胜出
+pub const BACKEND_BROADCAST: &str = "broadcast";
+
+/// 所有内置交互后端
+pub const INTERACTION_BACKENDS: &[&str] = &[BACKEND_POPUP, BACKEND_TELEGRAM, BACKEND_TERMINAL, BACKEND_BROADCAST];
+
+/// 广播模式默认参与的渠道
+pub const DEFAULT_BROADCAST_BACKENDS: &[&str] = &[BACKEND_POPUP, BACKEND_TELEGRAM];
+
+/// 强制指定交互后端的环境变量，值为后端名称
+pub const INTERACTION_ENV: &str = "CUNZHI_INTERACTION";
+
+/// 默认无操作多久视为离开桌面（秒）
+pub const DEFAULT_IDLE_THRESHOLD_SECS: u64 = 300;
+
+/// 回忆记忆时默认返回的最大条数
+pub const DEFAULT_MEMORY_RECALL_LIMIT: usize = 20;
+
+/// 同一分类中记忆内容相似度达到该值时视为相近，提示调用方但不自动合并
+pub const MEMORY_SIMILARITY_THRESHOLD: f64 = 0.8;
+
+/// 记忆存储和导出包的格式版本，主版本号变化表示不兼容
+pub const MEMORY_FORMAT_VERSION: &str = "2.0.0";
+
+/// 记忆导出包的格式标识
+pub const MEMORY_BUNDLE_FORMAT: &str = "cunzhi-memory-bundle";
+
+/// 代码搜索后端：远程 ACE 服务
+pub const ACEMCP_BACKEND_REMOTE: &str = "remote";
+
+/// 代码搜索后端：本地离线索引
+pub const ACEMCP_BACKEND_LOCAL: &str = "local";
+
+/// 代码搜索后端：OpenAI 兼容的向量接口加本地向量索引
+pub const ACEMCP_BACKEND_EMBEDDING: &str = "embedding";
+
+/// 代码搜索向量检索默认使用的模型
+pub const ACEMCP_DEFAULT_EMBEDDING_MODEL: &str = "text-embedding-3-small";
+
+/// 代码搜索默认索引的文件扩展名
+pub const ACEMCP_DEFAULT_TEXT_EXTENSIONS: &[&str] = &[
+    ".py", ".js", ".ts", ".jsx", ".tsx", ".java", ".go", ".rs", ".cpp", ".c", ".h", ".hpp", ".cs", ".rb", ".php",
+    ".md", ".txt", ".json", ".yaml", ".yml", ".toml", ".xml", ".html", ".css", ".scss", ".sql", ".sh", ".bash",
+];
+
+/// 代码搜索默认排除的目录和文件
+pub const ACEMCP_DEFAULT_EXCLUDE_PATTERNS: &[&str] = &[
+    ".venv", "venv", ".env", "env", "node_modules", ".next", ".nuxt", ".output", "out", ".cache", ".turbo",
+    ".vercel", ".netlify", ".swc", ".vite", ".parcel-cache", ".sass-cache", ".eslintcache", ".stylelintcache",
+    "coverage", ".nyc_output", "tmp", "temp", ".tmp", ".temp", ".git", ".svn", ".hg", "__pycache__",
+    ".pytest_cache", ".mypy_cache", ".tox", ".eggs", "*.egg-info", "dist", "build", ".idea", ".vscode",
+    ".DS_Store", "*.pyc", "*.pyo", "*.pyd", ".Python", "pip-log.txt", "pip-delete-this-directory.txt",
+    ".coverage", "htmlcov", ".gradle", "target", "bin", "obj",
+];
+
+/// 代码搜索后台索引合并文件变化事件的等待时间 (ms)
+pub const ACEMCP_WATCH_DEBOUNCE_MS: u64 = 300;
+
+/// 代码搜索后台索引在持续有文件变化时的最长处理间隔 (ms)
+pub const ACEMCP_WATCH_MAX_DELAY_MS: u64 = 3000;
+
+/// 代码搜索后台同时监听的最大项目数
+pub const ACEMCP_WATCH_MAX_PROJECTS: usize = 8;
+
+/// 代码搜索项目级忽略文件名，语法与 .gitignore 相同
+pub const ACEMCP_IGNORE_FILENAME: &str = ".acemcpignore";
+
+/// 代码搜索相对检查点的变化超过该数量时建立新检查点
+pub const ACEMCP_CHECKPOINT_DELTA_THRESHOLD: usize = 1000;
+
+/// 代码搜索大文件分块时相邻代码块默认重叠的行数
+pub const ACEMCP_DEFAULT_CHUNK_OVERLAP_LINES: u32 = 20;
+
+/// 代码搜索默认同时上传的批次数
+pub const ACEMCP_DEFAULT_UPLOAD_CONCURRENCY: u32 = 4;
+
+/// 代码搜索默认每批上传的最大字节数
+pub const ACEMCP_DEFAULT_MAX_BATCH_BYTES: u64 = 1024 * 1024;
+
+/// 代码搜索一次检索最多包含的根目录数
+pub const ACEMCP_MAX_INDEX_ROOTS: usize = 16;
+
+/// 代码搜索发现嵌套 git 仓库和 Cargo 工作区成员时遍历的最大目录深度
+pub const ACEMCP_WORKSPACE_DISCOVERY_DEPTH: usize = 4;
+
+/// 代码搜索检测到密钥时替换为占位符
+pub const ACEMCP_SECRET_MODE_REDACT: &str = "redact";
+
+/// 代码搜索检测到密钥时跳过整个文件
+pub const ACEMCP_SECRET_MODE_SKIP: &str = "skip";
+
+/// 代码搜索不检测密钥
+pub const ACEMCP_SECRET_MODE_OFF: &str = "off";
+
+/// 代码搜索项目级密钥检测配置文件名
+pub const ACEMCP_SECRETS_FILENAME: &str = ".acemcpsecrets";
+
+/// 代码搜索检测密钥时始终跳过的文件，匹配文件名或相对路径
+pub const ACEMCP_SECRET_FILE_PATTERNS: &[&str] = &[
+    ".env", ".env.*", "*.pem", "*.key", "*.p12", "*.pfx", "*.keystore", "*.jks", "id_rsa", "id_dsa", "id_ecdsa",
+    "id_ed25519", ".npmrc", ".pypirc", ".netrc", "credentials.json",
+];
+
 /// MCP 请求超时时间 (ms)
 pub const REQUEST_TIMEOUT_MS: u64 = 30000;
 
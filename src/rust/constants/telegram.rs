@@ -12,6 +12,18 @@ pub const DEFAULT_CHAT_ID: &str = "";
 /// 默认隐藏前端弹窗状态
 pub const DEFAULT_HIDE_FRONTEND_POPUP: bool = false;
 
+/// 投递模式：弹窗为主，Telegram 同步编辑同一份答复
+pub const DELIVERY_MODE_SYNC: &str = "sync";
+
+/// 投递模式：仅通过 Telegram 交互
+pub const DELIVERY_MODE_TELEGRAM_ONLY: &str = "telegram_only";
+
+/// 投递模式：弹窗和 Telegram 同时询问，先答复的一方生效
+pub const DELIVERY_MODE_BROADCAST: &str = "broadcast";
+
+/// 默认投递模式
+pub const DEFAULT_DELIVERY_MODE: &str = DELIVERY_MODE_SYNC;
+
 /// Telegram API 基础 URL
 pub const API_BASE_URL: &str = "https://api.telegram.org/bot";
 
@@ -34,6 +46,7 @@ pub struct TelegramConfig {
     pub bot_token: String,
     pub chat_id: String,
     pub hide_frontend_popup: bool,
+    pub delivery_mode: String,
     pub api_base_url: String,
     pub max_message_length: usize,
     pub request_timeout_ms: u64,
@@ -48,6 +61,7 @@ impl Default for TelegramConfig {
             bot_token: DEFAULT_BOT_TOKEN.to_string(),
             chat_id: DEFAULT_CHAT_ID.to_string(),
             hide_frontend_popup: DEFAULT_HIDE_FRONTEND_POPUP,
+            delivery_mode: DEFAULT_DELIVERY_MODE.to_string(),
             api_base_url: API_BASE_URL.to_string(),
             max_message_length: MAX_MESSAGE_LENGTH,
             request_timeout_ms: REQUEST_TIMEOUT_MS,
@@ -110,6 +124,7 @@ impl TelegramConfig {
             "bot_token": self.bot_token,
             "chat_id": self.chat_id,
             "hide_frontend_popup": self.hide_frontend_popup,
+            "delivery_mode": self.delivery_mode,
             "api_base_url": self.api_base_url,
             "max_message_length": self.max_message_length,
             "request_timeout_ms": self.request_timeout_ms,
@@ -256,8 +256,13 @@ impl McpTheme {
     }
 }
 
+/// 用户主题配置文件 ~/.cunzhi/theme.json
+pub fn user_theme_file() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".cunzhi").join("theme.json"))
+}
+
 /// 获取主题配置文件路径
-fn get_theme_config_path() -> Option<PathBuf> {
+pub fn get_theme_config_path() -> Option<PathBuf> {
     // 优先级：
     // 1. 与可执行文件同目录的 theme.json
     // 2. 用户配置目录 ~/.cunzhi/theme.json
@@ -274,8 +279,7 @@ fn get_theme_config_path() -> Option<PathBuf> {
     }
 
     // 检查用户配置目录
-    if let Some(home) = dirs::home_dir() {
-        let user_theme = home.join(".cunzhi").join("theme.json");
+    if let Some(user_theme) = user_theme_file() {
         if user_theme.exists() {
             return Some(user_theme);
         }
@@ -0,0 +1,128 @@
+use anyhow::Result;
+use std::io::{BufRead, BufReader, Write};
+use std::time::Duration;
+
+use super::protocol::{DaemonReply, DaemonRequest};
+use crate::constants::mcp::POPUP_TIMEOUT_GRACE_SECS;
+use crate::interaction::CancelSignal;
+use crate::mcp::types::{build_timeout_response, PopupRequest};
+use crate::log_debug;
+
+/// 通过常驻服务显示弹窗，阻塞直到用户响应
+///
+/// 连接失败时返回错误，调用方应回退到进程模式
+pub fn request_popup_via_daemon(request: &PopupRequest, cancel: &CancelSignal) -> Result<String> {
+    let message = DaemonRequest::Popup {
+        request: request.clone(),
+    };
+
+    // 常驻服务自己会按时返回超时答复，这里的读超时只是兜底
+    let read_timeout = request
+        .timeout()
+        .map(|timeout| timeout + Duration::from_secs(POPUP_TIMEOUT_GRACE_SECS));
+
+    // 被取消时通知常驻服务撤销弹窗，阻塞中的读取会随之收到答复
+    let finished = CancelSignal::new();
+    spawn_cancel_watcher(request.id.clone(), cancel.clone(), finished.clone());
+
+    let result = exchange(&message, read_timeout);
+    finished.cancel();
+
+    let reply = match result {
+        Ok(reply) => reply,
+        Err(e) if is_timeout_error(&e) => return Ok(build_timeout_response(request)),
+        Err(e) => return Err(e),
+    };
+
+    match reply {
+        DaemonReply::Response { response } => Ok(response),
+        DaemonReply::Error { message } => anyhow::bail!("常驻弹窗服务返回错误: {}", message),
+        DaemonReply::Pong | DaemonReply::Ack => anyhow::bail!("常驻弹窗服务返回了意外的响应"),
+    }
+}
+
+/// 在请求结束前监听取消信号，触发时向常驻服务发送撤销消息
+fn spawn_cancel_watcher(id: String, cancel: CancelSignal, finished: CancelSignal) {
+    std::thread::spawn(move || {
+        while !finished.is_cancelled() {
+            if cancel.is_cancelled() {
+                if let Err(e) = exchange(&DaemonRequest::Cancel { id }, Some(Duration::from_secs(5))) {
+                    log_debug!("撤销常驻弹窗请求失败: {}", e);
+                }
+                return;
+            }
+            std::thread::sleep(CancelSignal::poll_interval());
+        }
+    });
+}
+
+/// 检查常驻服务是否在运行
+///
+/// 常驻服务卡住未及时回复时同样视为未运行，调用方回退到进程模式
+pub fn ping_daemon() -> bool {
+    match exchange(&DaemonRequest::Ping, Some(Duration::from_secs(2))) {
+        Ok(DaemonReply::Pong) => true,
+        Ok(_) => false,
+        Err(e) => {
+            if is_timeout_error(&e) {
+                log_debug!("常驻弹窗服务未及时响应探测，视为未运行");
+            }
+            false
+        }
+    }
+}
+
+/// 发送一条消息并读取一条回复
+fn exchange(message: &DaemonRequest, read_timeout: Option<Duration>) -> Result<DaemonReply> {
+    let mut stream = connect()?;
+    set_read_timeout(&stream, read_timeout)?;
+
+    let mut line = serde_json::to_string(message)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())?;
+    stream.flush()?;
+
+    let mut reader = BufReader::new(stream);
+    let mut reply_line = String::new();
+    if reader.read_line(&mut reply_line)? == 0 {
+        anyhow::bail!("常驻弹窗服务提前关闭了连接");
+    }
+
+    Ok(serde_json::from_str(reply_line.trim())?)
+}
+
+/// 判断错误是否由读超时引起
+fn is_timeout_error(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<std::io::Error>()
+        .map(|e| matches!(e.kind(), std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock))
+        .unwrap_or(false)
+}
+
+#[cfg(unix)]
+fn set_read_timeout(stream: &std::os::unix::net::UnixStream, timeout: Option<Duration>) -> Result<()> {
+    stream.set_read_timeout(timeout)?;
+    Ok(())
+}
+
+/// 命名管道不支持读超时，完全依赖常驻服务自身的超时处理
+#[cfg(windows)]
+fn set_read_timeout(_stream: &std::fs::File, _timeout: Option<Duration>) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(unix)]
+fn connect() -> Result<std::os::unix::net::UnixStream> {
+    let path = super::protocol::socket_path();
+    std::os::unix::net::UnixStream::connect(&path)
+        .map_err(|e| anyhow::anyhow!("无法连接常驻弹窗服务 {}: {}", path.display(), e))
+}
+
+#[cfg(windows)]
+fn connect() -> Result<std::fs::File> {
+    std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(super::protocol::PIPE_NAME)
+        .map_err(|e| anyhow::anyhow!("无法连接常驻弹窗服务 {}: {}", super::protocol::PIPE_NAME, e))
+}
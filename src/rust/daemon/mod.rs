@@ -0,0 +1,12 @@
+//! 常驻弹窗服务
+//!
+//! `等一下 --daemon` 以常驻进程运行 UI，`寸止` MCP 服务器通过本地 socket 投递弹窗请求，
+//! 避免每次交互都冷启动一个 Tauri 进程。服务不可用时调用方回退到进程模式。
+
+pub mod protocol;
+pub mod client;
+pub mod server;
+
+pub use protocol::{DaemonRequest, DaemonReply, is_daemon_mode, DAEMON_ARG};
+pub use client::{request_popup_via_daemon, ping_daemon};
+pub use server::start_popup_daemon;
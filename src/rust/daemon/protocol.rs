@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+
+use crate::mcp::types::PopupRequest;
+
+/// 以常驻模式启动 UI 的命令行参数
+pub const DAEMON_ARG: &str = "--daemon";
+
+/// Unix socket 文件名
+#[cfg(unix)]
+pub const SOCKET_FILE_NAME: &str = "cunzhi-popup.sock";
+
+/// Windows 命名管道名称
+#[cfg(windows)]
+pub const PIPE_NAME: &str = r"\\.\pipe\cunzhi-popup";
+
+/// 客户端发送给常驻服务的消息（一行一个 JSON）
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DaemonRequest {
+    /// 探测服务是否存活
+    Ping,
+    /// 弹窗请求
+    Popup { request: PopupRequest },
+    /// 撤销弹窗请求（如已在其他渠道答复）
+    Cancel { id: String },
+}
+
+/// 常驻服务返回给客户端的消息（一行一个 JSON）
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DaemonReply {
+    Pong,
+    /// 弹窗响应，内容与进程模式下 stdout 输出的内容一致
+    Response { response: String },
+    /// 撤销请求已受理
+    Ack,
+    Error { message: String },
+}
+
+/// 当前进程是否以常驻模式运行
+pub fn is_daemon_mode() -> bool {
+    std::env::args().nth(1).as_deref() == Some(DAEMON_ARG)
+}
+
+/// 获取 Unix socket 路径
+///
+/// 优先使用用户私有的运行时目录，避免与其他用户共享 /tmp
+#[cfg(unix)]
+pub fn socket_path() -> std::path::PathBuf {
+    let dir = dirs::runtime_dir()
+        .or_else(|| dirs::cache_dir().map(|d| d.join("cunzhi")))
+        .unwrap_or_else(std::env::temp_dir);
+    let _ = std::fs::create_dir_all(&dir);
+    dir.join(SOCKET_FILE_NAME)
+}
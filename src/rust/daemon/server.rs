@@ -0,0 +1,315 @@
+use anyhow::Result;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::{mpsc, oneshot};
+
+use super::protocol::{DaemonReply, DaemonRequest};
+use crate::config::AppState;
+use crate::mcp::types::{build_timeout_response, PopupRequest};
+use crate::{log_debug, log_important};
+
+/// 排队等待显示的弹窗
+struct PopupJob {
+    request: PopupRequest,
+    reply: oneshot::Sender<String>,
+}
+
+/// 弹窗队列状态，用于撤销请求
+#[derive(Default)]
+struct QueueState {
+    /// 正在显示的请求
+    current_id: Option<String>,
+    /// 排队中的请求
+    queued_ids: HashSet<String>,
+    /// 排队中已被撤销的请求
+    cancelled_ids: HashSet<String>,
+}
+
+type SharedQueueState = Arc<Mutex<QueueState>>;
+
+/// 启动常驻弹窗服务
+///
+/// 监听本地 socket，弹窗请求按到达顺序排队，同一时间只显示一个
+pub fn start_popup_daemon(app_handle: &AppHandle) -> Result<()> {
+    let (job_tx, job_rx) = mpsc::unbounded_channel::<PopupJob>();
+
+    let listener = bind_listener()?;
+    let queue_state = SharedQueueState::default();
+
+    let worker_handle = app_handle.clone();
+    let worker_state = queue_state.clone();
+    tauri::async_runtime::spawn(async move {
+        run_popup_worker(worker_handle, worker_state, job_rx).await;
+    });
+
+    let context = ConnectionContext {
+        app_handle: app_handle.clone(),
+        queue_state,
+        job_tx,
+    };
+    tauri::async_runtime::spawn(async move {
+        accept_loop(listener, context).await;
+    });
+
+    log_important!(info, "常驻弹窗服务已启动");
+    Ok(())
+}
+
+/// 客户端连接处理所需的上下文
+#[derive(Clone)]
+struct ConnectionContext {
+    app_handle: AppHandle,
+    queue_state: SharedQueueState,
+    job_tx: mpsc::UnboundedSender<PopupJob>,
+}
+
+/// 依次处理弹窗队列
+async fn run_popup_worker(
+    app_handle: AppHandle,
+    queue_state: SharedQueueState,
+    mut job_rx: mpsc::UnboundedReceiver<PopupJob>,
+) {
+    while let Some(job) = job_rx.recv().await {
+        let id = job.request.id.clone();
+
+        // 客户端已断开（如 MCP 调用被取消），跳过该请求
+        if job.reply.is_closed() {
+            log_debug!("弹窗请求 {} 的客户端已断开，跳过", id);
+            forget_request(&queue_state, &id);
+            continue;
+        }
+
+        // 设置响应通道和当前请求需在同一把锁内完成，避免与撤销请求交错
+        let (response_tx, response_rx) = oneshot::channel();
+        {
+            let mut queue = match queue_state.lock() {
+                Ok(queue) => queue,
+                Err(e) => {
+                    log_important!(error, "获取弹窗队列状态失败: {}", e);
+                    let _ = job.reply.send("CANCELLED".to_string());
+                    continue;
+                }
+            };
+            queue.queued_ids.remove(&id);
+
+            if queue.cancelled_ids.remove(&id) {
+                log_debug!("弹窗请求 {} 已被撤销，跳过", id);
+                let _ = job.reply.send("CANCELLED".to_string());
+                continue;
+            }
+
+            let state = app_handle.state::<AppState>();
+            match state.response_channel.lock() {
+                Ok(mut channel) => *channel = Some(response_tx),
+                Err(e) => {
+                    log_important!(error, "获取响应通道失败: {}", e);
+                    let _ = job.reply.send("CANCELLED".to_string());
+                    continue;
+                }
+            };
+            queue.current_id = Some(id.clone());
+        }
+
+        if let Some(window) = app_handle.get_webview_window("main") {
+            let _ = window.show();
+            let _ = window.set_focus();
+        }
+
+        if let Err(e) = app_handle.emit("mcp-request", &job.request) {
+            log_important!(error, "发送弹窗事件失败: {}", e);
+        }
+
+        let response = match job.request.timeout() {
+            Some(timeout) => match tokio::time::timeout(timeout, response_rx).await {
+                Ok(response) => response.unwrap_or_else(|_| "CANCELLED".to_string()),
+                Err(_) => {
+                    log_important!(warn, "弹窗请求 {} 等待超时，返回默认答复", job.request.id);
+                    clear_response_channel(&app_handle);
+                    let _ = app_handle.emit("mcp-request-timeout", &job.request.id);
+                    build_timeout_response(&job.request)
+                }
+            },
+            // 通道被丢弃视为用户取消
+            None => response_rx.await.unwrap_or_else(|_| "CANCELLED".to_string()),
+        };
+
+        forget_request(&queue_state, &id);
+
+        if let Some(window) = app_handle.get_webview_window("main") {
+            let _ = window.hide();
+        }
+
+        let _ = job.reply.send(response);
+    }
+}
+
+/// 请求处理结束后清理队列状态
+fn forget_request(queue_state: &SharedQueueState, id: &str) {
+    if let Ok(mut queue) = queue_state.lock() {
+        queue.queued_ids.remove(id);
+        queue.cancelled_ids.remove(id);
+        if queue.current_id.as_deref() == Some(id) {
+            queue.current_id = None;
+        }
+    }
+}
+
+/// 撤销弹窗请求：正在显示的直接以取消结束，排队中的在轮到时跳过
+fn cancel_request(context: &ConnectionContext, id: &str) {
+    let Ok(mut queue) = context.queue_state.lock() else {
+        return;
+    };
+
+    if queue.current_id.as_deref() == Some(id) {
+        let state = context.app_handle.state::<AppState>();
+        let sender = state.response_channel.lock().ok().and_then(|mut channel| channel.take());
+        if let Some(sender) = sender {
+            let _ = sender.send("CANCELLED".to_string());
+        }
+        let _ = context.app_handle.emit("mcp-request-cancelled", id);
+        log_debug!("弹窗请求 {} 已撤销", id);
+    } else if queue.queued_ids.contains(id) {
+        queue.cancelled_ids.insert(id.to_string());
+    }
+}
+
+/// 清除等待中的响应通道，避免超时后的迟到响应被下一个弹窗误收
+fn clear_response_channel(app_handle: &AppHandle) {
+    let state = app_handle.state::<AppState>();
+    if let Ok(mut channel) = state.response_channel.lock() {
+        channel.take();
+    }
+}
+
+/// 处理单个客户端连接
+async fn handle_connection<S>(stream: S, context: ConnectionContext)
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (read_half, mut write_half) = tokio::io::split(stream);
+    let mut reader = BufReader::new(read_half);
+    let mut line = String::new();
+
+    if let Err(e) = reader.read_line(&mut line).await {
+        log_debug!("读取客户端请求失败: {}", e);
+        return;
+    }
+
+    let reply = match serde_json::from_str::<DaemonRequest>(line.trim()) {
+        Ok(DaemonRequest::Ping) => DaemonReply::Pong,
+        Ok(DaemonRequest::Popup { request }) => {
+            let (reply_tx, reply_rx) = oneshot::channel();
+            if let Ok(mut queue) = context.queue_state.lock() {
+                queue.queued_ids.insert(request.id.clone());
+            }
+            if context.job_tx.send(PopupJob { request, reply: reply_tx }).is_err() {
+                DaemonReply::Error { message: "弹窗队列已关闭".to_string() }
+            } else {
+                match reply_rx.await {
+                    Ok(response) => DaemonReply::Response { response },
+                    Err(_) => DaemonReply::Error { message: "弹窗未返回响应".to_string() },
+                }
+            }
+        }
+        Ok(DaemonRequest::Cancel { id }) => {
+            cancel_request(&context, &id);
+            DaemonReply::Ack
+        }
+        Err(e) => DaemonReply::Error { message: format!("无效的请求: {}", e) },
+    };
+
+    let mut reply_line = match serde_json::to_string(&reply) {
+        Ok(s) => s,
+        Err(e) => {
+            log_important!(error, "序列化响应失败: {}", e);
+            return;
+        }
+    };
+    reply_line.push('\n');
+
+    if let Err(e) = write_half.write_all(reply_line.as_bytes()).await {
+        log_debug!("写入客户端响应失败: {}", e);
+        return;
+    }
+    let _ = write_half.flush().await;
+}
+
+#[cfg(unix)]
+type Listener = tokio::net::UnixListener;
+
+#[cfg(unix)]
+fn bind_listener() -> Result<Listener> {
+    let path = super::protocol::socket_path();
+
+    if path.exists() {
+        // 已有服务在运行时不重复启动，否则清理残留的 socket 文件
+        if std::os::unix::net::UnixStream::connect(&path).is_ok() {
+            anyhow::bail!("常驻弹窗服务已在运行: {}", path.display());
+        }
+        std::fs::remove_file(&path)?;
+    }
+
+    let listener = tokio::net::UnixListener::bind(&path)
+        .map_err(|e| anyhow::anyhow!("无法监听 {}: {}", path.display(), e))?;
+
+    // 仅允许当前用户连接
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600));
+    }
+
+    Ok(listener)
+}
+
+#[cfg(unix)]
+async fn accept_loop(listener: Listener, context: ConnectionContext) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                tokio::spawn(handle_connection(stream, context.clone()));
+            }
+            Err(e) => {
+                log_important!(warn, "接受客户端连接失败: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+type Listener = tokio::net::windows::named_pipe::NamedPipeServer;
+
+#[cfg(windows)]
+fn bind_listener() -> Result<Listener> {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    ServerOptions::new()
+        .first_pipe_instance(true)
+        .create(super::protocol::PIPE_NAME)
+        .map_err(|e| anyhow::anyhow!("无法创建命名管道 {}（服务可能已在运行）: {}", super::protocol::PIPE_NAME, e))
+}
+
+#[cfg(windows)]
+async fn accept_loop(mut server: Listener, context: ConnectionContext) {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    loop {
+        if let Err(e) = server.connect().await {
+            log_important!(warn, "接受客户端连接失败: {}", e);
+            continue;
+        }
+
+        // 先创建下一个管道实例，再把已连接的实例交给处理任务
+        let next = match ServerOptions::new().create(super::protocol::PIPE_NAME) {
+            Ok(next) => next,
+            Err(e) => {
+                log_important!(error, "创建命名管道实例失败: {}", e);
+                return;
+            }
+        };
+        let connected = std::mem::replace(&mut server, next);
+
+        tokio::spawn(handle_connection(connected, context.clone()));
+    }
+}
@@ -0,0 +1,130 @@
+use anyhow::Result;
+
+use super::broadcast::BroadcastBackend;
+use super::cancel::CancelSignal;
+use crate::config::AppConfig;
+use crate::constants::mcp::{BACKEND_BROADCAST, BACKEND_POPUP, BACKEND_TELEGRAM, BACKEND_TERMINAL};
+use crate::daemon::ping_daemon;
+use crate::mcp::handlers::{create_tauri_popup, create_terminal_popup, is_headless_environment, is_terminal_available};
+use crate::mcp::types::PopupRequest;
+use crate::telegram::run_telegram_interaction;
+use crate::log_important;
+
+/// 交互后端
+///
+/// 每个后端负责把 `PopupRequest` 展示给用户并等待答复，
+/// 返回与弹窗一致的 MCP 响应 JSON（或 "CANCELLED"）
+pub trait InteractionBackend: Send + Sync {
+    /// 后端名称，与配置中使用的名称一致
+    fn name(&self) -> &'static str;
+
+    /// 当前环境下是否可用，不可用的后端会被直接跳过
+    fn is_available(&self, config: &AppConfig) -> bool;
+
+    /// 展示请求并阻塞等待用户响应
+    ///
+    /// `cancel` 被触发时应尽快关闭交互并返回 "CANCELLED"
+    fn interact(&self, request: &PopupRequest, cancel: &CancelSignal) -> Result<String>;
+}
+
+/// 桌面弹窗：优先交给常驻弹窗服务，否则启动等一下进程
+pub struct PopupBackend;
+
+impl InteractionBackend for PopupBackend {
+    fn name(&self) -> &'static str {
+        BACKEND_POPUP
+    }
+
+    fn is_available(&self, _config: &AppConfig) -> bool {
+        !is_headless_environment() || ping_daemon()
+    }
+
+    fn interact(&self, request: &PopupRequest, cancel: &CancelSignal) -> Result<String> {
+        create_tauri_popup(request, cancel)
+    }
+}
+
+/// Telegram Bot
+pub struct TelegramBackend;
+
+impl InteractionBackend for TelegramBackend {
+    fn name(&self) -> &'static str {
+        BACKEND_TELEGRAM
+    }
+
+    fn is_available(&self, config: &AppConfig) -> bool {
+        let telegram_config = &config.telegram_config;
+        telegram_config.enabled
+            && !telegram_config.bot_token.trim().is_empty()
+            && !telegram_config.chat_id.trim().is_empty()
+    }
+
+    fn interact(&self, request: &PopupRequest, cancel: &CancelSignal) -> Result<String> {
+        // 调用方可能已处于异步运行时中，在独立线程里创建运行时避免嵌套阻塞
+        let request = request.clone();
+        let cancel = cancel.clone();
+        std::thread::spawn(move || -> Result<String> {
+            tokio::runtime::Runtime::new()?.block_on(run_telegram_interaction(&request, &cancel))
+        })
+        .join()
+        .map_err(|_| anyhow::anyhow!("Telegram交互线程异常退出"))?
+    }
+}
+
+/// 控制终端
+pub struct TerminalBackend;
+
+impl InteractionBackend for TerminalBackend {
+    fn name(&self) -> &'static str {
+        BACKEND_TERMINAL
+    }
+
+    fn is_available(&self, _config: &AppConfig) -> bool {
+        is_terminal_available()
+    }
+
+    fn interact(&self, request: &PopupRequest, cancel: &CancelSignal) -> Result<String> {
+        create_terminal_popup(request, cancel)
+    }
+}
+
+/// 根据名称创建后端，未知名称返回 None
+pub fn create_backend(name: &str, config: &AppConfig) -> Option<Box<dyn InteractionBackend>> {
+    match name {
+        BACKEND_POPUP => Some(Box::new(PopupBackend)),
+        BACKEND_TELEGRAM => Some(Box::new(TelegramBackend)),
+        BACKEND_TERMINAL => Some(Box::new(TerminalBackend)),
+        BACKEND_BROADCAST => {
+            let members = config
+                .interaction_config
+                .broadcast_backends
+                .iter()
+                .filter(|member| member.as_str() != BACKEND_BROADCAST)
+                .filter_map(|member| create_backend(member, config))
+                .collect();
+            Some(Box::new(BroadcastBackend::new(members, config)))
+        }
+        _ => None,
+    }
+}
+
+/// 按顺序尝试各个后端，返回第一个成功的响应
+pub fn run_with_fallback(request: &PopupRequest, backends: &[Box<dyn InteractionBackend>]) -> Result<String> {
+    let cancel = CancelSignal::new();
+    let mut errors = Vec::new();
+
+    for backend in backends {
+        match backend.interact(request, &cancel) {
+            Ok(response) => return Ok(response),
+            Err(e) => {
+                log_important!(warn, "交互后端 {} 失败，尝试下一个: {}", backend.name(), e);
+                errors.push(format!("{}: {}", backend.name(), e));
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        anyhow::bail!("没有可用的交互后端");
+    }
+    anyhow::bail!("所有交互后端均失败（{}）", errors.join("；"))
+}
@@ -0,0 +1,176 @@
+use anyhow::Result;
+use std::sync::mpsc;
+use std::sync::Arc;
+
+use super::backend::InteractionBackend;
+use super::cancel::CancelSignal;
+use crate::config::AppConfig;
+use crate::constants::mcp::{BACKEND_BROADCAST, BACKEND_TELEGRAM};
+use crate::mcp::types::PopupRequest;
+use crate::{log_debug, log_important};
+
+/// 广播：同时向多个渠道发起同一个请求，第一个答复的渠道胜出，其余渠道被取消
+pub struct BroadcastBackend {
+    members: Vec<Arc<dyn InteractionBackend>>,
+}
+
+impl BroadcastBackend {
+    /// 仅保留当前可用的成员
+    pub fn new(members: Vec<Box<dyn InteractionBackend>>, config: &AppConfig) -> Self {
+        let members = members
+            .into_iter()
+            .filter(|member| member.is_available(config))
+            .map(Arc::from)
+            .collect();
+        Self { members }
+    }
+}
+
+impl InteractionBackend for BroadcastBackend {
+    fn name(&self) -> &'static str {
+        BACKEND_BROADCAST
+    }
+
+    fn is_available(&self, _config: &AppConfig) -> bool {
+        !self.members.is_empty()
+    }
+
+    fn interact(&self, request: &PopupRequest, cancel: &CancelSignal) -> Result<String> {
+        // Telegram 作为独立渠道参与时，弹窗不再另外同步到 Telegram，避免重复消息
+        let mut request = request.clone();
+        request.skip_telegram_sync = self.members.iter().any(|member| member.name() == BACKEND_TELEGRAM);
+
+        let members_cancel = CancelSignal::new();
+        let (tx, rx) = mpsc::channel();
+
+        for member in &self.members {
+            let member = member.clone();
+            let request = request.clone();
+            let members_cancel = members_cancel.clone();
+            let tx = tx.clone();
+            std::thread::spawn(move || {
+                let result = member.interact(&request, &members_cancel);
+                let _ = tx.send((member.name(), result));
+            });
+        }
+        drop(tx);
+
+        let mut user_cancelled = false;
+        let mut errors = Vec::new();
+
+        loop {
+            let (name, result) = match rx.recv_timeout(CancelSignal::poll_interval()) {
+                Ok(received) => received,
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    // 外层取消时一并取消所有渠道
+                    if cancel.is_cancelled() {
+                        members_cancel.cancel();
+                        return Ok("CANCELLED".to_string());
+                    }
+                    continue;
+                }
+                // 所有渠道都已结束
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            };
+
+            match result {
+                Ok(response) if response.trim() == "CANCELLED" => {
+                    // 某个渠道被用户关闭时继续等待其他渠道
+                    log_debug!("广播渠道 {} 被用户取消", name);
+                    user_cancelled = true;
+                }
+                Ok(response) => {
+                    log_important!(info, "广播请求 {} 由 {} 答复", request.id, name);
+                    members_cancel.cancel();
+                    return Ok(response);
+                }
+                Err(e) => {
+                    log_important!(warn, "广播渠道 {} 失败: {}", name, e);
+                    errors.push(format!("{}: {}", name, e));
+                }
+            }
+        }
+
+        if user_cancelled {
+            return Ok("CANCELLED".to_string());
+        }
+        anyhow::bail!("所有广播渠道均失败（{}）", errors.join("；"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 一直等待直到被取消
+    struct WaitingBackend;
+
+    impl InteractionBackend for WaitingBackend {
+        fn name(&self) -> &'static str {
+            "waiting"
+        }
+
+        fn is_available(&self, _config: &AppConfig) -> bool {
+            true
+        }
+
+        fn interact(&self, _request: &PopupRequest, cancel: &CancelSignal) -> Result<String> {
+            while !cancel.is_cancelled() {
+                std::thread::sleep(CancelSignal::poll_interval());
+            }
+            Ok("CANCELLED".to_string())
+        }
+    }
+
+    /// 立即返回固定答复
+    struct AnsweringBackend(&'static str);
+
+    impl InteractionBackend for AnsweringBackend {
+        fn name(&self) -> &'static str {
+            "answering"
+        }
+
+        fn is_available(&self, _config: &AppConfig) -> bool {
+            true
+        }
+
+        fn interact(&self, _request: &PopupRequest, _cancel: &CancelSignal) -> Result<String> {
+            Ok(self.0.to_string())
+        }
+    }
+
+    fn request() -> PopupRequest {
+        PopupRequest {
+            id: "test".to_string(),
+            message: "测试".to_string(),
+            predefined_options: None,
+            is_markdown: false,
+            timeout_secs: None,
+            timeout_default_option: None,
+            project_path: None,
+            skip_telegram_sync: false,
+        }
+    }
+
+    #[test]
+    fn test_first_answer_wins() {
+        let backend = BroadcastBackend::new(
+            vec![Box::new(WaitingBackend), Box::new(AnsweringBackend("答复"))],
+            &AppConfig::default(),
+        );
+
+        let response = backend.interact(&request(), &CancelSignal::new()).unwrap();
+        assert_eq!(response, "答复");
+    }
+
+    #[test]
+    fn test_all_cancelled() {
+        let backend = BroadcastBackend::new(
+            vec![Box::new(AnsweringBackend("CANCELLED")), Box::new(AnsweringBackend("CANCELLED"))],
+            &AppConfig::default(),
+        );
+
+        let response = backend.interact(&request(), &CancelSignal::new()).unwrap();
+        assert_eq!(response, "CANCELLED");
+    }
+}
@@ -0,0 +1,42 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// 检查取消信号的间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// 交互取消信号
+///
+/// 广播模式下某个渠道先得到答复后，通过它通知其他渠道停止等待
+#[derive(Debug, Clone, Default)]
+pub struct CancelSignal {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelSignal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 发出取消信号
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// 是否已取消
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// 同步等待时每次阻塞的最长时间，调用方醒来后应检查 `is_cancelled`
+    pub fn poll_interval() -> Duration {
+        POLL_INTERVAL
+    }
+
+    /// 异步等待直到被取消
+    pub async fn cancelled(&self) {
+        while !self.is_cancelled() {
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use std::process::Command;
+
+use crate::log_debug;
+use crate::mcp::handlers::is_headless_environment;
+
+/// 获取桌面无键鼠操作的时长（秒）
+///
+/// 没有图形界面时视为一直空闲；无法检测时返回 None
+pub fn desktop_idle_secs() -> Option<u64> {
+    if is_headless_environment() {
+        return Some(u64::MAX);
+    }
+
+    let idle = query_idle_secs();
+    if idle.is_none() {
+        log_debug!("无法检测桌面空闲时间");
+    }
+    idle
+}
+
+#[cfg(target_os = "macos")]
+fn query_idle_secs() -> Option<u64> {
+    // HIDIdleTime 以纳秒为单位
+    let output = command_output("ioreg", &["-c", "IOHIDSystem", "-d", "4"])?;
+    let line = output.lines().find(|line| line.contains("\"HIDIdleTime\""))?;
+    let nanos: u64 = line.rsplit('=').next()?.trim().parse().ok()?;
+    Some(nanos / 1_000_000_000)
+}
+
+#[cfg(windows)]
+fn query_idle_secs() -> Option<u64> {
+    const SCRIPT: &str = r#"Add-Type @'
+using System; using System.Runtime.InteropServices;
+public static class CunzhiIdle {
+    [StructLayout(LayoutKind.Sequential)] struct LastInput { public uint cbSize; public uint dwTime; }
+    [DllImport("user32.dll")] static extern bool GetLastInputInfo(ref LastInput info);
+    public static uint Get() { LastInput info = new LastInput(); info.cbSize = 8; GetLastInputInfo(ref info); return (uint)Environment.TickCount - info.dwTime; }
+}
+'@; [CunzhiIdle]::Get()"#;
+
+    // 输出单位为毫秒
+    let output = command_output("powershell", &["-NoProfile", "-NonInteractive", "-Command", SCRIPT])?;
+    let millis: u64 = output.trim().parse().ok()?;
+    Some(millis / 1000)
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn query_idle_secs() -> Option<u64> {
+    // X11 下使用 xprintidle（毫秒）
+    if let Some(output) = command_output("xprintidle", &[]) {
+        if let Ok(millis) = output.trim().parse::<u64>() {
+            return Some(millis / 1000);
+        }
+    }
+
+    // GNOME（含 Wayland）通过 Mutter 的 IdleMonitor 查询，输出形如 "(uint64 12345,)"
+    let output = command_output(
+        "gdbus",
+        &[
+            "call",
+            "--session",
+            "--dest",
+            "org.gnome.Mutter.IdleMonitor",
+            "--object-path",
+            "/org/gnome/Mutter/IdleMonitor/Core",
+            "--method",
+            "org.gnome.Mutter.IdleMonitor.GetIdletime",
+        ],
+    )?;
+    let millis: u64 = output
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|part| !part.is_empty())
+        .nth(1)?
+        .parse()
+        .ok()?;
+    Some(millis / 1000)
+}
+
+/// 执行命令并返回标准输出，失败时返回 None
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
@@ -0,0 +1,14 @@
+// 交互后端模块
+// 弹窗、Telegram、终端等交互方式实现统一的后端接口，按配置的路由规则选择
+
+pub mod backend;
+pub mod broadcast;
+pub mod cancel;
+pub mod idle;
+pub mod routing;
+
+pub use backend::{create_backend, run_with_fallback, InteractionBackend, PopupBackend, TelegramBackend, TerminalBackend};
+pub use broadcast::BroadcastBackend;
+pub use cancel::CancelSignal;
+pub use idle::desktop_idle_secs;
+pub use routing::{resolve_backend_chain, select_backends};
@@ -0,0 +1,229 @@
+use chrono::{Local, NaiveTime};
+use std::path::Path;
+
+use super::backend::{create_backend, InteractionBackend};
+use super::idle::desktop_idle_secs;
+use crate::config::{AppConfig, InteractionRule};
+use crate::constants::mcp::{BACKEND_BROADCAST, BACKEND_POPUP, BACKEND_TELEGRAM, BACKEND_TERMINAL, INTERACTION_BACKENDS, INTERACTION_ENV};
+use crate::constants::telegram::{DELIVERY_MODE_BROADCAST, DELIVERY_MODE_TELEGRAM_ONLY};
+use crate::mcp::types::PopupRequest;
+use crate::{log_debug, log_important};
+
+/// 解析本次请求应依次尝试的后端名称
+///
+/// 顺序：环境变量强制指定 > 第一条命中的路由规则 > 兜底链
+pub fn resolve_backend_chain(config: &AppConfig, request: &PopupRequest) -> Vec<String> {
+    if let Ok(name) = std::env::var(INTERACTION_ENV) {
+        let name = name.trim().to_lowercase();
+        if INTERACTION_BACKENDS.contains(&name.as_str()) {
+            return vec![name];
+        }
+        log_important!(warn, "{} 指定了未知的交互后端: {}", INTERACTION_ENV, name);
+    }
+
+    let interaction_config = &config.interaction_config;
+    let threshold = interaction_config.idle_threshold_secs;
+
+    // 空闲检测需要调用外部命令，只在规则用到时检测一次
+    let mut idle_cache: Option<Option<bool>> = None;
+    let mut is_idle = || *idle_cache.get_or_insert_with(|| desktop_idle_secs().map(|secs| secs >= threshold));
+
+    let now = Local::now().time();
+    let mut chain: Vec<String> = Vec::new();
+
+    for rule in &interaction_config.rules {
+        if rule_matches(rule, request.project_path.as_deref(), now, &mut is_idle) {
+            log_debug!("命中交互路由规则: {}", rule.name);
+            chain.extend(rule.backends.iter().cloned());
+            break;
+        }
+    }
+
+    if interaction_config.fallback_chain.is_empty() {
+        chain.extend(legacy_chain(config));
+    } else {
+        chain.extend(interaction_config.fallback_chain.iter().cloned());
+    }
+
+    // 去重并丢弃未知名称，保持原有顺序
+    let mut result: Vec<String> = Vec::new();
+    for name in chain {
+        let name = name.trim().to_lowercase();
+        if !INTERACTION_BACKENDS.contains(&name.as_str()) {
+            log_important!(warn, "忽略未知的交互后端: {}", name);
+            continue;
+        }
+        if !result.contains(&name) {
+            result.push(name);
+        }
+    }
+    result
+}
+
+/// 解析后端链并创建当前环境下可用的后端
+pub fn select_backends(config: &AppConfig, request: &PopupRequest) -> Vec<Box<dyn InteractionBackend>> {
+    resolve_backend_chain(config, request)
+        .iter()
+        .filter_map(|name| create_backend(name, config))
+        .filter(|backend| {
+            let available = backend.is_available(config);
+            if !available {
+                log_debug!("交互后端 {} 当前不可用，跳过", backend.name());
+            }
+            available
+        })
+        .collect()
+}
+
+/// 未配置兜底链时按Telegram投递模式决定：纯Telegram只用Telegram，广播模式同时询问，否则弹窗优先、终端兜底
+fn legacy_chain(config: &AppConfig) -> Vec<String> {
+    let telegram_config = &config.telegram_config;
+    let default_chain = [BACKEND_POPUP, BACKEND_TERMINAL];
+
+    let chain: &[&str] = match telegram_config.effective_delivery_mode() {
+        _ if !telegram_config.enabled => &default_chain,
+        DELIVERY_MODE_TELEGRAM_ONLY => &[BACKEND_TELEGRAM],
+        DELIVERY_MODE_BROADCAST => &[BACKEND_BROADCAST, BACKEND_POPUP, BACKEND_TERMINAL],
+        _ => &default_chain,
+    };
+    chain.iter().map(|name| name.to_string()).collect()
+}
+
+/// 检查规则是否命中，所有已设置的条件都需满足
+fn rule_matches(
+    rule: &InteractionRule,
+    project_path: Option<&str>,
+    now: NaiveTime,
+    is_idle: &mut dyn FnMut() -> Option<bool>,
+) -> bool {
+    if rule.backends.is_empty() {
+        return false;
+    }
+
+    if !rule.project_paths.is_empty() {
+        let Some(project_path) = project_path else {
+            return false;
+        };
+        if !rule.project_paths.iter().any(|pattern| path_matches(pattern, project_path)) {
+            return false;
+        }
+    }
+
+    if let Some(time_range) = &rule.time_range {
+        match parse_time_range(time_range) {
+            Some((start, end)) => {
+                if !in_time_range(now, start, end) {
+                    return false;
+                }
+            }
+            None => {
+                log_important!(warn, "规则 {} 的时间段格式无效: {}", rule.name, time_range);
+                return false;
+            }
+        }
+    }
+
+    if let Some(expected) = rule.desktop_idle {
+        // 无法检测空闲状态时不命中
+        if is_idle() != Some(expected) {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// 匹配项目路径，包含通配符时按 glob 匹配，否则按路径前缀匹配
+fn path_matches(pattern: &str, path: &str) -> bool {
+    let pattern = match (pattern.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest).to_string_lossy().to_string(),
+        _ => pattern.to_string(),
+    };
+
+    if pattern.contains(['*', '?', '[', '{']) {
+        globset::Glob::new(&pattern)
+            .map(|glob| glob.compile_matcher().is_match(path))
+            .unwrap_or(false)
+    } else {
+        Path::new(path).starts_with(&pattern)
+    }
+}
+
+/// 解析 "HH:MM-HH:MM" 格式的时间段
+fn parse_time_range(range: &str) -> Option<(NaiveTime, NaiveTime)> {
+    let (start, end) = range.split_once('-')?;
+    let start = NaiveTime::parse_from_str(start.trim(), "%H:%M").ok()?;
+    let end = NaiveTime::parse_from_str(end.trim(), "%H:%M").ok()?;
+    Some((start, end))
+}
+
+/// 判断时间是否在时间段内，结束早于开始时视为跨零点
+fn in_time_range(now: NaiveTime, start: NaiveTime, end: NaiveTime) -> bool {
+    if start <= end {
+        now >= start && now < end
+    } else {
+        now >= start || now < end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    fn rule(backends: &[&str]) -> InteractionRule {
+        InteractionRule {
+            name: "test".to_string(),
+            project_paths: Vec::new(),
+            time_range: None,
+            desktop_idle: None,
+            backends: backends.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_time_range() {
+        let (start, end) = parse_time_range("09:00-18:00").unwrap();
+        assert!(in_time_range(time(9, 0), start, end));
+        assert!(!in_time_range(time(18, 0), start, end));
+
+        // 跨零点
+        let (start, end) = parse_time_range("22:00 - 07:30").unwrap();
+        assert!(in_time_range(time(23, 15), start, end));
+        assert!(in_time_range(time(6, 0), start, end));
+        assert!(!in_time_range(time(12, 0), start, end));
+
+        assert!(parse_time_range("9点-18点").is_none());
+    }
+
+    #[test]
+    fn test_path_matches() {
+        assert!(path_matches("/work/company", "/work/company/app"));
+        assert!(!path_matches("/work/company", "/work/company-other"));
+        assert!(path_matches("/work/*/secret", "/work/team/secret"));
+        assert!(path_matches("**/side-*", "/home/me/side-project"));
+    }
+
+    #[test]
+    fn test_rule_matches() {
+        let mut idle = || Some(true);
+        let mut active = || Some(false);
+        let mut unknown = || None;
+
+        let mut away = rule(&["telegram"]);
+        away.desktop_idle = Some(true);
+        assert!(rule_matches(&away, None, time(10, 0), &mut idle));
+        assert!(!rule_matches(&away, None, time(10, 0), &mut active));
+        assert!(!rule_matches(&away, None, time(10, 0), &mut unknown));
+
+        let mut project = rule(&["terminal"]);
+        project.project_paths = vec!["/srv".to_string()];
+        assert!(rule_matches(&project, Some("/srv/app"), time(10, 0), &mut unknown));
+        assert!(!rule_matches(&project, None, time(10, 0), &mut unknown));
+
+        assert!(!rule_matches(&rule(&[]), None, time(10, 0), &mut unknown));
+    }
+}
@@ -1,6 +1,8 @@
 pub mod app;
 pub mod config;
 pub mod constants;
+pub mod daemon;
+pub mod interaction;
 pub mod mcp;
 pub mod telegram;
 pub mod ui;
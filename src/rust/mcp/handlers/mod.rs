@@ -1,5 +1,7 @@
 pub mod popup;
 pub mod response;
+pub mod terminal;
 
 pub use popup::*;
 pub use response::*;
+pub use terminal::*;
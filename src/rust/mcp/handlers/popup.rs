@@ -1,14 +1,38 @@
 use anyhow::Result;
-use std::process::Command;
+use std::process::{Command, Output, Stdio};
 use std::fs;
+use std::io::Read;
 use std::path::Path;
+use std::time::{Duration, Instant};
 
-use crate::mcp::types::PopupRequest;
+use crate::mcp::types::{build_timeout_response, PopupRequest};
+use crate::constants::mcp::{BACKEND_POPUP, INTERACTION_ENV, POPUP_TIMEOUT_GRACE_SECS};
+use crate::app::MCP_REQUEST_ARG;
+use crate::daemon::request_popup_via_daemon;
+use crate::interaction::CancelSignal;
+use crate::{log_debug, log_important};
 
 /// 创建 Tauri 弹窗
 ///
-/// 优先调用与 MCP 服务器同目录的 UI 命令，找不到时使用全局版本
-pub fn create_tauri_popup(request: &PopupRequest) -> Result<String> {
+/// 优先交给常驻弹窗服务处理；服务未运行时调用与 MCP 服务器同目录的 UI 命令，找不到时使用全局版本
+pub fn create_tauri_popup(request: &PopupRequest, cancel: &CancelSignal) -> Result<String> {
+    match request_popup_via_daemon(request, cancel) {
+        Ok(response) => return Ok(normalize_popup_response(&response)),
+        Err(e) => log_debug!("常驻弹窗服务不可用，回退到进程模式: {}", e),
+    }
+
+    spawn_tauri_popup(request, cancel)
+}
+
+/// 等待弹窗进程的结果
+enum PopupWait {
+    Finished(Output),
+    TimedOut,
+    Cancelled,
+}
+
+/// 启动一个新的等一下进程显示弹窗
+fn spawn_tauri_popup(request: &PopupRequest, cancel: &CancelSignal) -> Result<String> {
     // 创建临时请求文件 - 跨平台适配
     let temp_dir = std::env::temp_dir();
     let temp_file = temp_dir.join(format!("mcp_request_{}.json", request.id));
@@ -19,28 +43,104 @@ pub fn create_tauri_popup(request: &PopupRequest) -> Result<String> {
     let command_path = find_ui_command()?;
 
     // 调用等一下命令
-    let output = Command::new(&command_path)
-        .arg("--mcp-request")
+    let mut command = Command::new(&command_path);
+    command
+        .arg(MCP_REQUEST_ARG)
         .arg(temp_file.to_string_lossy().to_string())
-        .output()?;
+        // 后端已在MCP服务器中选定，UI进程直接显示弹窗，不再重新路由
+        .env(INTERACTION_ENV, BACKEND_POPUP);
+
+    // 弹窗进程到期后自行返回默认答复并退出，这里额外留出宽限时间，只在进程无响应时结束它
+    let deadline = request
+        .timeout()
+        .map(|timeout| timeout + Duration::from_secs(POPUP_TIMEOUT_GRACE_SECS));
+    let result = wait_popup_process(&mut command, deadline, cancel);
 
     // 清理临时文件
     let _ = fs::remove_file(&temp_file);
 
+    let output = match result? {
+        PopupWait::Finished(output) => output,
+        PopupWait::TimedOut => {
+            log_important!(warn, "弹窗请求 {} 等待超时，返回默认答复", request.id);
+            return Ok(build_timeout_response(request));
+        }
+        PopupWait::Cancelled => return Ok("CANCELLED".to_string()),
+    };
+
     if output.status.success() {
         let response = String::from_utf8_lossy(&output.stdout);
-        let response = response.trim();
-        if response.is_empty() {
-            Ok("用户取消了操作".to_string())
-        } else {
-            Ok(response.to_string())
-        }
+        Ok(normalize_popup_response(&response))
     } else {
         let error = String::from_utf8_lossy(&output.stderr);
         anyhow::bail!("UI进程失败: {}", error);
     }
 }
 
+/// 运行命令并收集输出，超过期限或被取消时结束进程
+fn wait_popup_process(
+    command: &mut Command,
+    deadline: Option<Duration>,
+    cancel: &CancelSignal,
+) -> std::io::Result<PopupWait> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // 在独立线程中读取输出，避免管道写满导致子进程阻塞
+    let stdout_reader = child.stdout.take().map(spawn_pipe_reader);
+    let stderr_reader = child.stderr.take().map(spawn_pipe_reader);
+
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if deadline.is_some_and(|deadline| started.elapsed() >= deadline) {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Ok(PopupWait::TimedOut);
+        }
+        if cancel.is_cancelled() {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Ok(PopupWait::Cancelled);
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    };
+
+    let collect = |reader: Option<std::thread::JoinHandle<Vec<u8>>>| {
+        reader.and_then(|handle| handle.join().ok()).unwrap_or_default()
+    };
+
+    Ok(PopupWait::Finished(Output {
+        status,
+        stdout: collect(stdout_reader),
+        stderr: collect(stderr_reader),
+    }))
+}
+
+/// 在后台线程中读完整个管道
+fn spawn_pipe_reader<R: Read + Send + 'static>(mut pipe: R) -> std::thread::JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = pipe.read_to_end(&mut buf);
+        buf
+    })
+}
+
+/// 统一处理弹窗返回的原始内容，空响应视为用户取消
+fn normalize_popup_response(response: &str) -> String {
+    let response = response.trim();
+    if response.is_empty() {
+        "用户取消了操作".to_string()
+    } else {
+        response.to_string()
+    }
+}
+
 /// 查找等一下 UI 命令的路径
 ///
 /// 按优先级查找：同目录 -> 全局版本 -> 开发环境
@@ -0,0 +1,332 @@
+use anyhow::Result;
+use regex::Regex;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::sync::{mpsc, Mutex};
+use std::time::Instant;
+
+use crate::config::load_standalone_config;
+use crate::interaction::CancelSignal;
+use crate::mcp::types::{build_continue_response, build_send_response, build_timeout_response, PopupRequest};
+
+/// 进程内共用的终端输入，读取线程在首次交互时启动并在之后的交互中复用，
+/// 交互结束后不会遗留仍在读取终端的线程吞掉下一次交互的输入
+static TERMINAL_LINES: Mutex<Option<mpsc::Receiver<String>>> = Mutex::new(None);
+
+/// 在终端中显示交互请求
+///
+/// 直接读写控制终端（而不是 stdin/stdout，它们被 MCP 协议占用），
+/// 响应格式与弹窗、Telegram 完全一致
+pub fn create_terminal_popup(request: &PopupRequest, cancel: &CancelSignal) -> Result<String> {
+    let (input, mut output) = open_terminal()?;
+
+    // 持有输入期间其他终端交互等待，同一终端上不会同时出现两个提示
+    let mut reader = TERMINAL_LINES.lock().unwrap_or_else(|e| e.into_inner());
+    let lines = reader.get_or_insert_with(|| spawn_line_reader(input));
+    // 丢弃交互开始前输入的内容
+    while lines.try_recv().is_ok() {}
+
+    let continue_enabled = load_standalone_config()
+        .map(|config| config.reply_config.enable_continue_reply)
+        .unwrap_or(true);
+
+    let options = request.predefined_options.clone().unwrap_or_default();
+    let mut selected = vec![false; options.len()];
+    let mut user_input_lines: Vec<String> = Vec::new();
+
+    let message = if request.is_markdown {
+        render_markdown_for_terminal(&request.message)
+    } else {
+        request.message.clone()
+    };
+
+    writeln!(output, "\n\x1b[1m━━━━━━━━━━━━ 寸止 ━━━━━━━━━━━━\x1b[0m")?;
+    writeln!(output, "{}\n", message.trim_end())?;
+    print_options(&mut output, &options, &selected)?;
+    print_usage(&mut output, !options.is_empty(), continue_enabled, request)?;
+
+    let deadline = request.timeout().map(|timeout| Instant::now() + timeout);
+
+    loop {
+        write!(output, "> ")?;
+        output.flush()?;
+
+        // 分段等待输入，以便及时响应超时和取消
+        let line = loop {
+            let mut wait = CancelSignal::poll_interval();
+            if let Some(deadline) = deadline {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    writeln!(output, "\n⏰ 等待超时，已自动返回默认答复")?;
+                    return Ok(build_timeout_response(request));
+                }
+                wait = wait.min(remaining);
+            }
+
+            match lines.recv_timeout(wait) {
+                Ok(line) => break line,
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    if cancel.is_cancelled() {
+                        writeln!(output, "\n✅ 已在其他渠道答复")?;
+                        return Ok("CANCELLED".to_string());
+                    }
+                }
+                // 终端已关闭，下次交互时重新启动读取线程
+                Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    *reader = None;
+                    return Ok("CANCELLED".to_string());
+                }
+            }
+        };
+
+        let trimmed = line.trim();
+        match trimmed {
+            ":s" | ":send" => {
+                let selected_options: Vec<String> = options
+                    .iter()
+                    .zip(selected.iter())
+                    .filter(|(_, is_selected)| **is_selected)
+                    .map(|(option, _)| option.clone())
+                    .collect();
+                let user_input = user_input_lines.join("\n");
+                let user_input = if user_input.trim().is_empty() { None } else { Some(user_input) };
+
+                writeln!(output, "✅ 已发送")?;
+                return Ok(build_send_response(
+                    user_input,
+                    selected_options,
+                    vec![], // 终端模式下没有图片
+                    Some(request.id.clone()),
+                    "terminal",
+                ));
+            }
+            ":c" | ":continue" if continue_enabled => {
+                writeln!(output, "✅ 已继续")?;
+                return Ok(build_continue_response(Some(request.id.clone()), "terminal_continue"));
+            }
+            ":q" | ":quit" => {
+                writeln!(output, "已取消")?;
+                return Ok("CANCELLED".to_string());
+            }
+            ":u" | ":undo" => {
+                user_input_lines.pop();
+            }
+            "" => {}
+            _ => {
+                if let Some(indices) = parse_option_toggles(trimmed, options.len()) {
+                    for index in indices {
+                        selected[index] = !selected[index];
+                    }
+                    print_options(&mut output, &options, &selected)?;
+                } else {
+                    user_input_lines.push(line.trim_end().to_string());
+                }
+            }
+        }
+    }
+}
+
+/// 控制终端是否可用
+pub fn is_terminal_available() -> bool {
+    open_terminal().is_ok()
+}
+
+/// 检查当前环境是否没有图形界面
+pub fn is_headless_environment() -> bool {
+    if cfg!(windows) {
+        return false;
+    }
+
+    if cfg!(target_os = "macos") {
+        // macOS 本地会话总有图形界面，仅 SSH 登录时视为无界面
+        return std::env::var_os("SSH_CONNECTION").is_some();
+    }
+
+    let has_display = |name: &str| std::env::var(name).map(|v| !v.is_empty()).unwrap_or(false);
+    !has_display("DISPLAY") && !has_display("WAYLAND_DISPLAY")
+}
+
+/// 打开控制终端，返回（输入，输出）
+fn open_terminal() -> Result<(File, File)> {
+    #[cfg(unix)]
+    let (input_path, output_path) = ("/dev/tty", "/dev/tty");
+    #[cfg(windows)]
+    let (input_path, output_path) = ("CONIN$", "CONOUT$");
+
+    let input = File::open(input_path)
+        .map_err(|e| anyhow::anyhow!("无法打开终端输入 {}: {}", input_path, e))?;
+    let output = OpenOptions::new()
+        .write(true)
+        .open(output_path)
+        .map_err(|e| anyhow::anyhow!("无法打开终端输出 {}: {}", output_path, e))?;
+
+    Ok((input, output))
+}
+
+/// 在后台线程中逐行读取终端输入，便于交互时处理超时和取消
+fn spawn_line_reader(input: File) -> mpsc::Receiver<String> {
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in BufReader::new(input).lines() {
+            match line {
+                Ok(line) => {
+                    if tx.send(line).is_err() {
+                        break;
+                    }
+                }
+                Err(_) => break,
+            }
+        }
+    });
+    rx
+}
+
+/// 打印选项列表及选中状态
+fn print_options(output: &mut File, options: &[String], selected: &[bool]) -> Result<()> {
+    if options.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(output, "选项：")?;
+    for (index, (option, is_selected)) in options.iter().zip(selected.iter()).enumerate() {
+        let mark = if *is_selected { "\x1b[32m[x]\x1b[0m" } else { "[ ]" };
+        writeln!(output, "  {} {}. {}", mark, index + 1, option)?;
+    }
+    writeln!(output)?;
+    Ok(())
+}
+
+/// 打印操作说明
+fn print_usage(output: &mut File, has_options: bool, continue_enabled: bool, request: &PopupRequest) -> Result<()> {
+    if has_options {
+        writeln!(output, "输入选项编号切换选中状态（如 1 或 1,3）")?;
+    }
+    writeln!(output, "直接输入文字作为补充说明，可输入多行，:u 撤销上一行")?;
+
+    let mut actions = vec![":s 发送"];
+    if continue_enabled {
+        actions.push(":c 继续");
+    }
+    actions.push(":q 取消");
+    writeln!(output, "{}", actions.join("   "))?;
+
+    if let Some(secs) = request.timeout_secs.filter(|secs| *secs > 0) {
+        writeln!(output, "⏰ {} 秒内未操作将自动答复", secs)?;
+    }
+    writeln!(output)?;
+    Ok(())
+}
+
+/// 解析选项切换输入，全部是合法编号时返回从 0 开始的索引
+fn parse_option_toggles(input: &str, option_count: usize) -> Option<Vec<usize>> {
+    if option_count == 0 {
+        return None;
+    }
+
+    let mut indices = Vec::new();
+    for part in input.split(|c: char| c == ',' || c == '，' || c.is_whitespace()) {
+        if part.is_empty() {
+            continue;
+        }
+        let number: usize = part.parse().ok()?;
+        if number == 0 || number > option_count {
+            return None;
+        }
+        indices.push(number - 1);
+    }
+
+    if indices.is_empty() {
+        None
+    } else {
+        Some(indices)
+    }
+}
+
+/// 将 Markdown 渲染为适合终端显示的纯文本
+pub fn render_markdown_for_terminal(text: &str) -> String {
+    let header_regex = Regex::new(r"^(#{1,6})\s+(.+)$").unwrap();
+    let list_regex = Regex::new(r"^(\s*)[-*+]\s+(.*)$").unwrap();
+
+    let mut lines = Vec::new();
+    let mut in_code_block = false;
+
+    for line in text.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+
+        // 代码块原样缩进显示
+        if in_code_block {
+            lines.push(format!("    {}", line));
+            continue;
+        }
+
+        if let Some(captures) = header_regex.captures(line) {
+            let title = render_inline_markdown(captures.get(2).unwrap().as_str());
+            lines.push(format!("\x1b[1m{}\x1b[0m", title));
+        } else if is_horizontal_rule(line) {
+            lines.push("─".repeat(32));
+        } else if let Some(captures) = list_regex.captures(line) {
+            let indent = captures.get(1).unwrap().as_str();
+            let item = render_inline_markdown(captures.get(2).unwrap().as_str());
+            lines.push(format!("{}• {}", indent, item));
+        } else if let Some(quote) = line.trim_start().strip_prefix('>') {
+            lines.push(format!("│ {}", render_inline_markdown(quote.trim_start())));
+        } else {
+            lines.push(render_inline_markdown(line));
+        }
+    }
+
+    lines.join("\n")
+}
+
+/// 判断是否为分隔线（三个及以上相同的 -、* 或 _）
+fn is_horizontal_rule(line: &str) -> bool {
+    let chars: Vec<char> = line.chars().filter(|c| !c.is_whitespace()).collect();
+    chars.len() >= 3 && matches!(chars[0], '-' | '*' | '_') && chars.iter().all(|c| *c == chars[0])
+}
+
+/// 处理行内 Markdown：粗体、斜体、行内代码和链接
+fn render_inline_markdown(line: &str) -> String {
+    let bold_regex = Regex::new(r"\*\*([^*]+)\*\*|__([^_]+)__").unwrap();
+    let code_regex = Regex::new(r"`([^`]+)`").unwrap();
+    let link_regex = Regex::new(r"\[([^\]]+)\]\(([^)]+)\)").unwrap();
+
+    let result = link_regex.replace_all(line, "$1 ($2)");
+    let result = bold_regex.replace_all(&result, |captures: &regex::Captures| {
+        let content = captures.get(1).or_else(|| captures.get(2)).unwrap().as_str();
+        format!("\x1b[1m{}\x1b[0m", content)
+    });
+    let result = code_regex.replace_all(&result, "\x1b[36m$1\x1b[0m");
+
+    result.into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_markdown_for_terminal() {
+        let input = "# 标题\n\n- **粗体** 项\n> 引用\n```rust\nfn main() {}\n```\n[链接](https://example.com)";
+        let result = render_markdown_for_terminal(input);
+
+        assert!(result.contains("\x1b[1m标题\x1b[0m"));
+        assert!(result.contains("• \x1b[1m粗体\x1b[0m 项"));
+        assert!(result.contains("│ 引用"));
+        assert!(result.contains("    fn main() {}"));
+        assert!(!result.contains("```"));
+        assert!(result.contains("链接 (https://example.com)"));
+        assert!(render_markdown_for_terminal("---").contains('─'));
+    }
+
+    #[test]
+    fn test_parse_option_toggles() {
+        assert_eq!(parse_option_toggles("1", 3), Some(vec![0]));
+        assert_eq!(parse_option_toggles("1, 3", 3), Some(vec![0, 2]));
+        assert_eq!(parse_option_toggles("4", 3), None);
+        assert_eq!(parse_option_toggles("1 个问题", 3), None);
+        assert_eq!(parse_option_toggles("1", 0), None);
+    }
+}
@@ -105,6 +105,19 @@ impl ServerHandler for ZhiServer {
                 "is_markdown": {
                     "type": "boolean",
                     "description": "信息是否为Markdown格式，默认为true"
+                },
+                "timeout_secs": {
+                    "type": "integer",
+                    "minimum": 0,
+                    "description": "等待用户响应的超时时间（秒，可选），不填使用全局默认值，0 表示一直等待"
+                },
+                "timeout_default_option": {
+                    "type": "string",
+                    "description": "超时后自动选择的预定义选项（可选）"
+                },
+                "project_path": {
+                    "type": "string",
+                    "description": "当前项目的绝对路径（可选），用于按项目选择交互方式，不填使用寸止的工作目录"
                 }
             },
             "required": ["message"]
@@ -126,7 +139,7 @@ impl ServerHandler for ZhiServer {
                 "properties": {
                     "action": {
                         "type": "string",
-                        "description": "任务类型：记忆(存储), 回忆(提取)"
+                        "description": "任务类型：记忆(存储), 回忆(提取), 列表(列出情报及ID), 更新(按ID修改), 删除(按ID删除), 整理(合并完全相同的情报，列出相近情报), 导出(导出情报包), 导入(导入情报包)"
                     },
                     "project_path": {
                         "type": "string",
@@ -134,11 +147,39 @@ impl ServerHandler for ZhiServer {
                     },
                     "content": {
                         "type": "string",
-                        "description": "内容（存储时必需）"
+                        "description": "内容（存储时必需，更新时可选）"
                     },
                     "category": {
                         "type": "string",
                         "description": "分类：rule(规则), preference(偏好), pattern(模式), context(上下文)"
+                    },
+                    "scope": {
+                        "type": "string",
+                        "description": "范围：user(用户全局), workspace(工作区), repo(仓库，默认), directory(子目录)。回忆和列表不指定时合并所有范围"
+                    },
+                    "id": {
+                        "type": "string",
+                        "description": "情报ID（更新、删除时必需），可使用列表中显示的ID前缀"
+                    },
+                    "query": {
+                        "type": "string",
+                        "description": "回忆时的检索内容，按相关度返回匹配的情报；不提供时返回最近更新的情报"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "回忆时返回的最大条数，默认20"
+                    },
+                    "file_path": {
+                        "type": "string",
+                        "description": "导出、导入时的情报包文件路径，相对路径基于项目路径；导出时不提供则直接返回内容"
+                    },
+                    "format": {
+                        "type": "string",
+                        "description": "情报包格式：json 或 markdown，默认按扩展名推断"
+                    },
+                    "conflict": {
+                        "type": "string",
+                        "description": "导入时ID冲突的处理：newer(保留较新的，默认), local(保留本地), incoming(使用导入的)"
                     }
                 },
                 "required": ["action", "project_path"]
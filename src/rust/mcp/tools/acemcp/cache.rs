@@ -0,0 +1,217 @@
+// 文件哈希缓存
+// 按文件路径、修改时间、大小和 inode 记录每个文件分块后的 blob 哈希，
+// 文件未变化时无需重新读取、解码和计算哈希
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::chunker::ChunkOptions;
+use super::index::{keyed_data_file, HashedBlob};
+use crate::log_important;
+use crate::utils::write_atomic;
+
+/// 缓存格式版本，结构变化时递增以丢弃旧缓存
+const CACHE_VERSION: u32 = 2;
+
+/// 修改时间距扫描时刻小于该值的文件不写入缓存，
+/// 避免文件在同一时间精度内再次修改后被误判为未变化
+const RACY_WINDOW: Duration = Duration::from_secs(2);
+
+/// 文件状态，任一字段变化即视为文件已修改
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct FileStamp {
+    pub mtime_ns: u64,
+    pub size: u64,
+    pub inode: u64,
+}
+
+impl FileStamp {
+    pub fn of(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        let mtime_ns = metadata
+            .modified()
+            .ok()?
+            .duration_since(UNIX_EPOCH)
+            .ok()?
+            .as_nanos() as u64;
+
+        #[cfg(unix)]
+        let inode = std::os::unix::fs::MetadataExt::ino(&metadata);
+        #[cfg(not(unix))]
+        let inode = 0;
+
+        Some(Self { mtime_ns, size: metadata.len(), inode })
+    }
+
+    /// 文件是否刚刚修改过
+    fn is_racy(&self, now: SystemTime) -> bool {
+        let modified = UNIX_EPOCH + Duration::from_nanos(self.mtime_ns);
+        now.duration_since(modified).map(|age| age < RACY_WINDOW).unwrap_or(true)
+    }
+}
+
+/// 缓存的 blob
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct CachedBlob {
+    path: String,
+    hash: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct CachedFile {
+    stamp: FileStamp,
+    blobs: Vec<CachedBlob>,
+}
+
+/// 单个项目的文件哈希缓存
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FileHashCache {
+    version: u32,
+    /// 影响分块结果的配置，变化时缓存整体失效
+    settings_key: String,
+    /// 相对路径 → 文件状态和 blob 哈希
+    files: HashMap<String, CachedFile>,
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(skip)]
+    seen: HashMap<String, CachedFile>,
+    #[serde(skip)]
+    hits: usize,
+}
+
+impl FileHashCache {
+    /// 读取项目的缓存文件，不存在、损坏或配置变化时返回空缓存
+    ///
+    /// `secrets_key` 为密钥检测策略的摘要，策略变化后文件内容的处理结果不同，缓存随之失效
+    pub fn load(normalized_root: &str, text_exts: &[String], chunk_options: ChunkOptions, secrets_key: &str) -> Self {
+        Self::load_from(&cache_file(normalized_root), text_exts, chunk_options, secrets_key)
+    }
+
+    /// 从指定文件读取缓存
+    pub fn load_from(path: &Path, text_exts: &[String], chunk_options: ChunkOptions, secrets_key: &str) -> Self {
+        let settings_key = settings_key(text_exts, chunk_options, secrets_key);
+        let cached = fs::read_to_string(path)
+            .ok()
+            .and_then(|data| serde_json::from_str::<FileHashCache>(&data).ok())
+            .filter(|cache| cache.version == CACHE_VERSION && cache.settings_key == settings_key);
+
+        Self {
+            version: CACHE_VERSION,
+            settings_key,
+            files: cached.map(|cache| cache.files).unwrap_or_default(),
+            path: path.to_path_buf(),
+            seen: HashMap::new(),
+            hits: 0,
+        }
+    }
+
+    /// 文件未变化时返回缓存的 blob 哈希（不含内容）
+    pub fn lookup(&mut self, rel_path: &str, stamp: FileStamp) -> Option<Vec<HashedBlob>> {
+        let cached = self.files.get(rel_path).filter(|cached| cached.stamp == stamp)?.clone();
+        let blobs = cached
+            .blobs
+            .iter()
+            .map(|blob| HashedBlob { hash: blob.hash.clone(), path: blob.path.clone(), content: None })
+            .collect();
+        self.seen.insert(rel_path.to_string(), cached);
+        self.hits += 1;
+        Some(blobs)
+    }
+
+    /// 记录重新读取的文件
+    pub fn record(&mut self, rel_path: &str, stamp: FileStamp, blobs: &[HashedBlob], now: SystemTime) {
+        if stamp.is_racy(now) {
+            return;
+        }
+        let blobs = blobs.iter().map(|blob| CachedBlob { path: blob.path.clone(), hash: blob.hash.clone() }).collect();
+        self.seen.insert(rel_path.to_string(), CachedFile { stamp, blobs });
+    }
+
+    /// 命中缓存的文件数
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    /// 保存本次遍历到的文件，已不存在的文件随之移出缓存
+    pub fn save(mut self) {
+        self.files = std::mem::take(&mut self.seen);
+        match serde_json::to_string(&self) {
+            Ok(data) => {
+                if let Err(e) = write_atomic(&self.path, &data) {
+                    log_important!(warn, "保存文件哈希缓存失败: {}", e);
+                }
+            }
+            Err(e) => log_important!(warn, "序列化文件哈希缓存失败: {}", e),
+        }
+    }
+}
+
+fn settings_key(text_exts: &[String], chunk_options: ChunkOptions, secrets_key: &str) -> String {
+    let mut exts: Vec<String> = text_exts.iter().map(|ext| ext.to_lowercase()).collect();
+    exts.sort();
+    exts.dedup();
+    format!(
+        "max_lines={};overlap={};exts={};secrets={}",
+        chunk_options.max_lines,
+        chunk_options.overlap_lines,
+        exts.join(","),
+        secrets_key
+    )
+}
+
+/// 文件哈希缓存路径
+pub fn cache_file(normalized_root: &str) -> PathBuf {
+    keyed_data_file("cache", normalized_root, "json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_hit_and_invalidation() {
+        let dir = std::env::temp_dir().join(format!("acemcp-cache-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let cache_path = dir.join("cache.json");
+        let exts = vec![".rs".to_string()];
+        let options = ChunkOptions { max_lines: 800, overlap_lines: 20 };
+        let stamp = FileStamp { mtime_ns: 1_000_000_000, size: 10, inode: 42 };
+        let blobs = vec![HashedBlob { hash: "h1".to_string(), path: "src/a.rs".to_string(), content: None }];
+
+        let mut cache = FileHashCache::load_from(&cache_path, &exts, options, "redact");
+        assert!(cache.lookup("src/a.rs", stamp).is_none());
+        cache.record("src/a.rs", stamp, &blobs, SystemTime::now());
+        // 按策略跳过的文件记为空 blob 列表
+        cache.record("src/secret.rs", stamp, &[], SystemTime::now());
+        cache.save();
+
+        let mut cache = FileHashCache::load_from(&cache_path, &exts, options, "redact");
+        assert_eq!(cache.lookup("src/a.rs", stamp).unwrap()[0].hash, "h1");
+        assert!(cache.lookup("src/secret.rs", stamp).unwrap().is_empty());
+        assert!(cache.lookup("src/a.rs", FileStamp { size: 11, ..stamp }).is_none());
+        assert!(cache.lookup("src/a.rs", FileStamp { inode: 43, ..stamp }).is_none());
+
+        // 分块参数、扩展名或密钥检测策略变化后缓存失效
+        assert!(FileHashCache::load_from(&cache_path, &exts, options, "skip").lookup("src/a.rs", stamp).is_none());
+        let changed = ChunkOptions { max_lines: 400, ..options };
+        assert!(FileHashCache::load_from(&cache_path, &exts, changed, "redact").lookup("src/a.rs", stamp).is_none());
+        let changed = ChunkOptions { overlap_lines: 0, ..options };
+        assert!(FileHashCache::load_from(&cache_path, &exts, changed, "redact").lookup("src/a.rs", stamp).is_none());
+        let exts = vec![".rs".to_string(), ".md".to_string()];
+        assert!(FileHashCache::load_from(&cache_path, &exts, options, "redact").lookup("src/a.rs", stamp).is_none());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_recently_modified_file_not_cached() {
+        let now = SystemTime::now();
+        let mtime_ns = now.duration_since(UNIX_EPOCH).unwrap().as_nanos() as u64;
+        let stamp = FileStamp { mtime_ns, size: 1, inode: 1 };
+        assert!(stamp.is_racy(now));
+        assert!(!stamp.is_racy(now + Duration::from_secs(5)));
+    }
+}
@@ -0,0 +1,165 @@
+// 检索检查点
+// 记录服务端为每个项目签发的 checkpoint_id 及其对应的 blob 集合，
+// 检索时只发送相对检查点新增和删除的 blob，避免每次查询都重发整个项目的 blob 列表。
+// 每个检索键（单个或多个根目录）单独保存一个文件，检查点与其 blob 集合一起原子写入，
+// 多个客户端同时写入时后写入的检查点整体替换先前的检查点，不会影响其他项目
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+
+use super::index::keyed_data_file;
+use crate::log_important;
+use crate::utils::write_atomic;
+
+/// 检索键的检查点
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Checkpoint {
+    /// 检索键，读取时校验，防止哈希冲突时误用其他项目的检查点
+    #[serde(default)]
+    pub key: String,
+    pub checkpoint_id: String,
+    /// 检查点包含的 blob
+    pub blob_names: Vec<String>,
+}
+
+impl Checkpoint {
+    /// 读取检查点，不存在或损坏时返回 None
+    pub fn load(key: &str) -> Option<Self> {
+        let data = fs::read_to_string(checkpoint_file(key)).ok()?;
+        serde_json::from_str::<Self>(&data).ok().filter(|checkpoint| checkpoint.key == key)
+    }
+
+    /// 保存检查点，替换该检索键原有的检查点
+    pub fn save(key: &str, checkpoint_id: String, blob_names: &[String]) -> Self {
+        let checkpoint = Self { key: key.to_string(), checkpoint_id, blob_names: blob_names.to_vec() };
+        match serde_json::to_string_pretty(&checkpoint) {
+            Ok(data) => {
+                if let Err(e) = write_atomic(&checkpoint_file(key), &data) {
+                    log_important!(warn, "保存检查点失败: {}", e);
+                }
+            }
+            Err(e) => log_important!(warn, "序列化检查点失败: {}", e),
+        }
+        checkpoint
+    }
+
+    /// 删除检查点，返回是否存在
+    pub fn remove(key: &str) -> bool {
+        fs::remove_file(checkpoint_file(key)).is_ok()
+    }
+}
+
+/// 检查点文件路径
+pub fn checkpoint_file(key: &str) -> PathBuf {
+    keyed_data_file("projects", key, "checkpoint.json")
+}
+
+/// 相对检查点的 blob 变化
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlobDelta {
+    pub checkpoint_id: Option<String>,
+    pub added_blobs: Vec<String>,
+    pub deleted_blobs: Vec<String>,
+}
+
+impl BlobDelta {
+    /// 不使用检查点，发送全部 blob
+    pub fn full(blob_names: &[String]) -> Self {
+        Self { checkpoint_id: None, added_blobs: blob_names.to_vec(), deleted_blobs: Vec::new() }
+    }
+
+    /// 计算当前 blob 相对检查点的变化，没有检查点时发送全部 blob
+    pub fn since(checkpoint: Option<&Checkpoint>, blob_names: &[String]) -> Self {
+        let Some(checkpoint) = checkpoint else {
+            return Self::full(blob_names);
+        };
+
+        let current: HashSet<&String> = blob_names.iter().collect();
+        let previous: HashSet<&String> = checkpoint.blob_names.iter().collect();
+        let mut added_blobs: Vec<String> = current.difference(&previous).map(|name| name.to_string()).collect();
+        let mut deleted_blobs: Vec<String> = previous.difference(&current).map(|name| name.to_string()).collect();
+        added_blobs.sort();
+        deleted_blobs.sort();
+
+        Self { checkpoint_id: Some(checkpoint.checkpoint_id.clone()), added_blobs, deleted_blobs }
+    }
+
+    /// 变化的 blob 数量
+    pub fn len(&self) -> usize {
+        self.added_blobs.len() + self.deleted_blobs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 请求中的 blobs 字段
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "checkpoint_id": self.checkpoint_id,
+            "added_blobs": self.added_blobs,
+            "deleted_blobs": self.deleted_blobs,
+        })
+    }
+}
+
+/// 从响应中读取服务端签发的检查点
+pub fn checkpoint_id_from_response(value: &serde_json::Value) -> Option<String> {
+    ["new_checkpoint_id", "checkpoint_id"]
+        .iter()
+        .find_map(|key| value.get(key).and_then(|v| v.as_str()))
+        .filter(|id| !id.is_empty())
+        .map(|id| id.to_string())
+}
+
+/// 判断请求失败是否因为服务端不认可检查点
+///
+/// 认证、限流和服务端错误不属于此类，避免在这些情况下重发全部 blob
+pub fn is_checkpoint_rejected(error: &str) -> bool {
+    if error.to_lowercase().contains("checkpoint") {
+        return true;
+    }
+    let status = error
+        .strip_prefix("HTTP ")
+        .and_then(|rest| rest.split_whitespace().next())
+        .and_then(|code| code.parse::<u16>().ok());
+    matches!(status, Some(code) if (400..500).contains(&code) && !matches!(code, 401 | 403 | 429))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_delta_since_checkpoint() {
+        let current = names(&["a", "c", "d"]);
+        assert_eq!(BlobDelta::since(None, &current), BlobDelta::full(&current));
+
+        let checkpoint = Checkpoint {
+            key: "/p".to_string(),
+            checkpoint_id: "cp1".to_string(),
+            blob_names: names(&["a", "b", "c"]),
+        };
+        let delta = BlobDelta::since(Some(&checkpoint), &current);
+        assert_eq!(delta.checkpoint_id.as_deref(), Some("cp1"));
+        assert_eq!(delta.added_blobs, names(&["d"]));
+        assert_eq!(delta.deleted_blobs, names(&["b"]));
+        assert_eq!(delta.len(), 2);
+    }
+
+    #[test]
+    fn test_is_checkpoint_rejected() {
+        assert!(is_checkpoint_rejected("HTTP 400 Bad Request unknown checkpoint"));
+        assert!(is_checkpoint_rejected("HTTP 404 Not Found"));
+        assert!(is_checkpoint_rejected("invalid checkpoint_id"));
+        assert!(!is_checkpoint_rejected("HTTP 401 Unauthorized"));
+        assert!(!is_checkpoint_rejected("HTTP 500 Internal Server Error"));
+        assert!(!is_checkpoint_rejected("error sending request: connection refused"));
+    }
+}
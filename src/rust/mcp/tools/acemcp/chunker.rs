@@ -0,0 +1,263 @@
+// 语法感知分块
+// 超过最大行数的文件优先在顶层定义（函数、类、impl 块等）之前切分，相邻代码块之间保留若干行重叠；
+// 无法识别的文件类型按固定行数切分。分块路径带行号范围后缀 `path#L120-240`
+
+use regex::Regex;
+use std::sync::OnceLock;
+
+use super::index::BlobItem;
+
+/// 分块参数
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChunkOptions {
+    /// 每块最多的行数（含重叠部分）
+    pub max_lines: usize,
+    /// 每块开头重复前一块末尾的行数
+    pub overlap_lines: usize,
+}
+
+/// 按语言识别可以切分的定义
+struct Language {
+    extensions: &'static [&'static str],
+    /// 定义的首行，匹配去除缩进后的内容
+    item: &'static str,
+    /// 缩进不超过该列数的定义才作为切分点，类或 impl 中的方法通常缩进一级
+    max_indent: usize,
+    /// 紧挨在定义之前、随定义一起划分的注释、属性和装饰器
+    attached: &'static [&'static str],
+}
+
+const LANGUAGES: &[Language] = &[
+    Language {
+        extensions: &["rs"],
+        item: r"^(pub(\([^)]*\))?\s+)?((async|const|unsafe|default)\s+)*(extern\s+\S+\s+)?(fn|struct|enum|union|impl|trait|mod|type|static|const|macro_rules!)\b",
+        max_indent: 4,
+        attached: &["///", "//!", "//", "#[", "/*", "*"],
+    },
+    Language {
+        extensions: &["py", "pyi"],
+        item: r"^((async\s+)?def|class)\b",
+        max_indent: 4,
+        attached: &["@", "#"],
+    },
+    Language {
+        extensions: &["js", "jsx", "mjs", "cjs", "ts", "tsx", "mts", "cts"],
+        item: r"^(export\s+(default\s+)?)?(declare\s+)?((async\s+)?function\b|(abstract\s+)?class\b|interface\b|enum\b|namespace\b|type\s+\w+|(const|let|var)\s+\w+\s*=)",
+        max_indent: 0,
+        attached: &["//", "/*", "*", "@"],
+    },
+    Language {
+        extensions: &["go"],
+        item: r"^(func|type|var|const)\b",
+        max_indent: 0,
+        attached: &["//"],
+    },
+    Language {
+        extensions: &["java", "kt", "kts", "cs", "scala"],
+        item: r"^(((public|private|protected|internal|static|final|abstract|sealed|override|open|partial|async|virtual|data|suspend|inline)\s+)+|(class|interface|enum|record|struct|object|fun|def|namespace)\b)",
+        max_indent: 4,
+        attached: &["//", "/*", "*", "@", "["],
+    },
+    Language {
+        extensions: &["c", "h", "cc", "cpp", "cxx", "hh", "hpp", "hxx"],
+        item: r"^(template\b|namespace\b|class\b|struct\b|enum\b|union\b|typedef\b|[A-Za-z_][\w\s\*&:<>,]*\([^;]*$)",
+        max_indent: 0,
+        attached: &["//", "/*", "*"],
+    },
+    Language {
+        extensions: &["rb"],
+        item: r"^(def|class|module)\b",
+        max_indent: 2,
+        attached: &["#"],
+    },
+    Language {
+        extensions: &["php"],
+        item: r"^((public|private|protected|static|abstract|final)\s+)*(function|class|interface|trait|enum)\b",
+        max_indent: 4,
+        attached: &["//", "/*", "*", "#["],
+    },
+    Language {
+        extensions: &["sh", "bash", "zsh"],
+        item: r"^(function\s+\w+|\w+\s*\(\)\s*(\{|$))",
+        max_indent: 0,
+        attached: &["#"],
+    },
+    Language {
+        extensions: &["md", "markdown", "mdx"],
+        item: r"^#{1,6}\s",
+        max_indent: 0,
+        attached: &[],
+    },
+];
+
+fn compiled_languages() -> &'static [(&'static Language, Regex)] {
+    static COMPILED: OnceLock<Vec<(&'static Language, Regex)>> = OnceLock::new();
+    COMPILED.get_or_init(|| {
+        LANGUAGES
+            .iter()
+            .map(|language| (language, Regex::new(language.item).expect("分块规则无效")))
+            .collect()
+    })
+}
+
+/// 按文件扩展名查找语言
+fn language_for(path: &str) -> Option<&'static (&'static Language, Regex)> {
+    let ext = path.rsplit_once('.')?.1.to_lowercase();
+    compiled_languages()
+        .iter()
+        .find(|(language, _)| language.extensions.contains(&ext.as_str()))
+}
+
+/// 行首缩进的列数，制表符按 4 列计算
+fn indent_width(line: &str) -> usize {
+    line.chars()
+        .take_while(|ch| *ch == ' ' || *ch == '\t')
+        .map(|ch| if ch == '\t' { 4 } else { 1 })
+        .sum()
+}
+
+/// 可以切分的行（从 0 开始），即定义及其前置注释、属性的首行
+fn split_points(lines: &[&str], language: &Language, item: &Regex) -> Vec<usize> {
+    let mut points: Vec<usize> = Vec::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        let indent = indent_width(line);
+        if indent > language.max_indent || !item.is_match(line.trim_start()) {
+            continue;
+        }
+
+        // 块注释的续行比首行多缩进一列
+        let mut start = i;
+        while start > 0 {
+            let prev = lines[start - 1];
+            let attached = (indent..=indent + 1).contains(&indent_width(prev))
+                && language.attached.iter().any(|prefix| prev.trim_start().starts_with(prefix));
+            if !attached {
+                break;
+            }
+            start -= 1;
+        }
+
+        if start > points.last().copied().unwrap_or(0) {
+            points.push(start);
+        }
+    }
+
+    points
+}
+
+/// 分割文件内容为多个 blob（如果超过最大行数）
+///
+/// 每块在不超过最大行数的前提下尽量在最后一个定义之前结束；
+/// 找不到切分点或文件类型未知时按最大行数切分
+pub fn split_content(path: &str, content: &str, options: ChunkOptions) -> Vec<BlobItem> {
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let max_lines = options.max_lines.max(1);
+
+    // 如果文件在限制内，返回单个 blob
+    if lines.len() <= max_lines {
+        return vec![BlobItem { path: path.to_string(), content: content.to_string() }];
+    }
+
+    let points = language_for(path)
+        .map(|(language, item)| split_points(&lines, language, item))
+        .unwrap_or_default();
+    // 重叠不超过半块，保证每块都有新内容
+    let overlap = options.overlap_lines.min(max_lines / 2);
+
+    let mut blobs = Vec::new();
+    let mut start = 0;
+    while start < lines.len() {
+        let chunk_start = start.saturating_sub(overlap);
+        let limit = chunk_start + max_lines;
+        let end = if limit >= lines.len() {
+            lines.len()
+        } else {
+            points
+                .iter()
+                .rev()
+                .find(|&&point| point > start && point <= limit)
+                .copied()
+                .unwrap_or(limit)
+        };
+
+        blobs.push(BlobItem {
+            path: format!("{}#L{}-{}", path, chunk_start + 1, end),
+            content: lines[chunk_start..end].join(""),
+        });
+        start = end;
+    }
+
+    blobs
+}
+
+/// 分块路径中的行号范围（从 1 开始，含两端），未分块时返回 None
+pub fn blob_line_range(blob_path: &str) -> Option<(usize, usize)> {
+    let (_, range) = blob_path.rsplit_once("#L")?;
+    let (start, end) = range.split_once('-')?;
+    Some((start.parse().ok()?, end.parse().ok()?))
+}
+
+/// 去掉分块后缀 `#L起-止` 得到原文件路径
+pub fn blob_file_path(blob_path: &str) -> &str {
+    match blob_path.rsplit_once("#L") {
+        Some((path, _)) if blob_line_range(blob_path).is_some() => path,
+        _ => blob_path,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranges(blobs: &[BlobItem]) -> Vec<(usize, usize)> {
+        blobs.iter().map(|blob| blob_line_range(&blob.path).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_split_rust_on_items() {
+        let mut content = String::from("use std::fs;\n\n");
+        for name in ["a", "b", "c"] {
+            content.push_str(&format!("/// {}\n#[inline]\nfn {}() {{\n", name, name));
+            for i in 0..6 {
+                content.push_str(&format!("    let x{} = {};\n", i, i));
+            }
+            content.push_str("}\n\n");
+        }
+        // 每个函数 11 行（含空行），分别从第 3、14、25 行开始
+        let options = ChunkOptions { max_lines: 15, overlap_lines: 0 };
+        let blobs = split_content("src/lib.rs", &content, options);
+        assert_eq!(ranges(&blobs), [(1, 13), (14, 24), (25, 35)]);
+        assert!(blobs[1].content.starts_with("/// b\n#[inline]\nfn b()"));
+
+        let options = ChunkOptions { max_lines: 15, overlap_lines: 2 };
+        let blobs = split_content("src/lib.rs", &content, options);
+        assert_eq!(ranges(&blobs), [(1, 13), (12, 24), (23, 35)]);
+        assert_eq!(blob_file_path(&blobs[1].path), "src/lib.rs");
+    }
+
+    #[test]
+    fn test_split_unknown_type_by_lines() {
+        let content: String = (1..=25).map(|i| format!("line {}\n", i)).collect();
+        let options = ChunkOptions { max_lines: 10, overlap_lines: 0 };
+        assert_eq!(ranges(&split_content("notes.txt", &content, options)), [(1, 10), (11, 20), (21, 25)]);
+
+        let options = ChunkOptions { max_lines: 10, overlap_lines: 3 };
+        assert_eq!(ranges(&split_content("notes.txt", &content, options)), [(1, 10), (8, 17), (15, 24), (22, 25)]);
+
+        // 不超过最大行数的文件不加后缀
+        let small = split_content("notes.txt", "a\nb\n", options);
+        assert_eq!(small[0].path, "notes.txt");
+        assert_eq!(blob_line_range("notes.txt"), None);
+        assert_eq!(blob_file_path("docs/#Label.md"), "docs/#Label.md");
+    }
+
+    #[test]
+    fn test_language_rules_compile() {
+        assert_eq!(compiled_languages().len(), LANGUAGES.len());
+        let python = "import os\n\n@cache\ndef load():\n    pass\n\nclass A:\n    def m(self):\n        pass\n";
+        let lines: Vec<&str> = python.split_inclusive('\n').collect();
+        let (language, item) = language_for("a.py").unwrap();
+        assert_eq!(split_points(&lines, language, item), [2, 6, 7]);
+    }
+}
@@ -0,0 +1,374 @@
+// 寸止命令行子命令
+// 不经过 MCP 客户端直接建立索引、查看索引状态、检索和清除缓存，便于在 CI 中预热索引和排查检索结果；
+// 加 --json 时标准输出只包含一个 JSON 对象，日志仍输出到标准错误
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+
+use super::cache::cache_file;
+use super::checkpoint::checkpoint_file;
+use super::chunker::blob_file_path;
+use super::embedding::vector_index_file;
+use super::index::{data_dir, normalize_root, peek_blobs};
+use super::journal::{journal_file, read_queue};
+use super::local::local_index_file;
+use super::mcp::AcemcpTool;
+use super::provider::{provider_for, IndexStats, RetrievalProvider};
+use super::roots::{indexed_roots, resolve_roots, IndexRoot};
+use super::scope::SearchScope;
+use super::types::{AcemcpConfig, AcemcpRequest};
+
+/// 数据目录中保存索引数据的子目录，不含密钥审计日志
+const INDEX_DATA_DIRS: [&str; 4] = ["projects", "cache", "local", "vectors"];
+
+/// 子命令
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    Index,
+    Search,
+    Status,
+    Clear,
+    Help,
+}
+
+impl Command {
+    /// 识别子命令名称，不是子命令时返回 None
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "index" => Some(Self::Index),
+            "search" => Some(Self::Search),
+            "status" => Some(Self::Status),
+            "clear" => Some(Self::Clear),
+            "help" | "--help" | "-h" => Some(Self::Help),
+            _ => None,
+        }
+    }
+}
+
+/// 解析后的命令行参数
+#[derive(Debug)]
+pub struct CliArgs {
+    pub command: Command,
+    /// 项目路径、查询和检索范围，与 MCP 工具的参数一致
+    pub request: AcemcpRequest,
+    /// 是否输出 JSON
+    pub json: bool,
+}
+
+/// 单个根目录的索引状态
+#[derive(Debug, Serialize)]
+struct RootStatus {
+    root: String,
+    /// 可索引的文件数
+    files: usize,
+    /// 当前的代码块数
+    blobs: usize,
+    /// 已写入索引的当前代码块数，尚未建立索引时为 None
+    indexed: Option<usize>,
+    /// 尚未写入索引的代码块数
+    pending: usize,
+    /// 上传失败、等待重试的代码块数
+    retry_queue: usize,
+}
+
+/// 解析子命令的参数，`args` 不含程序名
+pub fn parse_args(args: &[String]) -> Result<CliArgs> {
+    let command = args
+        .first()
+        .and_then(|name| Command::parse(name))
+        .ok_or_else(|| anyhow::anyhow!("未知命令: {}", args.first().map(String::as_str).unwrap_or("")))?;
+
+    let mut request = AcemcpRequest::default();
+    let mut json = false;
+    let mut positional = Vec::new();
+    let mut iter = args[1..].iter();
+    while let Some(arg) = iter.next() {
+        let mut value = || iter.next().cloned().ok_or_else(|| anyhow::anyhow!("参数 {} 缺少取值", arg));
+        match arg.as_str() {
+            "--json" => json = true,
+            "--discover" => request.discover_workspace = Some(true),
+            "--root" => request.additional_roots.get_or_insert_with(Vec::new).push(value()?),
+            "--include" => request.include_globs.get_or_insert_with(Vec::new).push(value()?),
+            "--exclude" => request.exclude_globs.get_or_insert_with(Vec::new).push(value()?),
+            "--lang" => request.languages.get_or_insert_with(Vec::new).push(value()?),
+            "--max-results" => {
+                let raw = value()?;
+                let max = raw.parse::<usize>().map_err(|_| anyhow::anyhow!("--max-results 应为正整数: {}", raw))?;
+                request.max_results = Some(max);
+            }
+            flag if flag.starts_with("--") => anyhow::bail!("未知参数: {}", flag),
+            _ => positional.push(arg.clone()),
+        }
+    }
+
+    let mut positional = positional.into_iter();
+    match command {
+        Command::Help => {}
+        Command::Clear => request.project_root_path = positional.next().unwrap_or_default(),
+        _ => {
+            request.project_root_path = positional.next().ok_or_else(|| anyhow::anyhow!("缺少项目路径"))?;
+        }
+    }
+    if command == Command::Search {
+        let query: Vec<String> = positional.by_ref().collect();
+        if query.is_empty() {
+            anyhow::bail!("缺少检索内容");
+        }
+        request.query = query.join(" ");
+    }
+    if let Some(extra) = positional.next() {
+        anyhow::bail!("多余的参数: {}", extra);
+    }
+
+    Ok(CliArgs { command, request, json })
+}
+
+/// 执行子命令，返回进程退出码
+pub async fn run_cli(args: &[String]) -> i32 {
+    let cli = match parse_args(args) {
+        Ok(cli) => cli,
+        Err(e) => {
+            eprintln!("{}", e);
+            eprintln!();
+            print_help();
+            return 2;
+        }
+    };
+    if cli.command == Command::Help {
+        print_help();
+        return 0;
+    }
+
+    let json = cli.json;
+    match execute(cli).await {
+        Ok(()) => 0,
+        Err(e) => {
+            if json {
+                println!("{}", serde_json::json!({ "error": e.to_string() }));
+            } else {
+                eprintln!("错误: {}", e);
+            }
+            1
+        }
+    }
+}
+
+async fn execute(mut cli: CliArgs) -> Result<()> {
+    if cli.command == Command::Clear {
+        return clear(&cli.request.project_root_path, cli.json);
+    }
+
+    let mut config = AcemcpTool::get_acemcp_config().await?;
+    // 命令行进程执行完即退出，不启动后台监听
+    config.watch = Some(false);
+
+    cli.request.project_root_path = absolute_dir(&cli.request.project_root_path)?;
+    if let Some(roots) = cli.request.additional_roots.as_mut() {
+        for root in roots.iter_mut() {
+            *root = absolute_dir(root)?;
+        }
+    }
+    let roots = resolve_roots(&cli.request, &config)?;
+    let provider = provider_for(config.clone());
+
+    match cli.command {
+        Command::Index => {
+            let stats = provider.index(&roots).await?;
+            print_index(provider.name(), &stats, cli.json);
+        }
+        Command::Search => {
+            let scope = SearchScope::from_request(&cli.request)?;
+            let output = provider.search(&roots, &cli.request.query, &scope).await?;
+            if cli.json {
+                let value = serde_json::json!({ "backend": provider.name(), "hits": output.hits, "text": output.text });
+                println!("{}", value);
+            } else {
+                println!("{}", output.text);
+            }
+        }
+        Command::Status => {
+            let statuses = indexed_roots(&roots)
+                .map(|root| root_status(&config, provider.as_ref(), root))
+                .collect::<Result<Vec<_>>>()?;
+            print_status(provider.name(), &statuses, cli.json);
+        }
+        Command::Clear | Command::Help => {}
+    }
+    Ok(())
+}
+
+/// 转为使用正斜杠的绝对路径，目录不存在时返回错误
+fn absolute_dir(path: &str) -> Result<String> {
+    let absolute = std::path::absolute(path).map_err(|e| anyhow::anyhow!("无法解析路径 {}: {}", path, e))?;
+    if !absolute.is_dir() {
+        anyhow::bail!("目录不存在: {}", absolute.display());
+    }
+    Ok(absolute.to_string_lossy().replace('\\', "/"))
+}
+
+/// 对比当前代码块与后端索引，只读取哈希缓存，不更新缓存和审计日志
+fn root_status(config: &AcemcpConfig, provider: &dyn RetrievalProvider, root: &IndexRoot) -> Result<RootStatus> {
+    let blobs = peek_blobs(
+        &root.path,
+        &config.text_extensions_or_default(),
+        &config.exclude_patterns_or_default(),
+        config.chunk_options(),
+        &config.secret_mode_or_default(),
+    )?;
+    let files: HashSet<&str> = blobs.iter().map(|blob| blob_file_path(&blob.path)).collect();
+    let indexed = provider
+        .indexed_hashes(root)
+        .map(|hashes| blobs.iter().filter(|blob| hashes.contains(&blob.hash)).count());
+
+    Ok(RootStatus {
+        root: root.path.clone(),
+        files: files.len(),
+        blobs: blobs.len(),
+        indexed,
+        pending: blobs.len() - indexed.unwrap_or(0),
+        retry_queue: read_queue(&root.normalized).len(),
+    })
+}
+
+/// 清除缓存：指定项目时只删除该项目的索引数据，否则删除所有项目的索引数据
+fn clear(project_root_path: &str, json: bool) -> Result<()> {
+    let project_root_path = if project_root_path.is_empty() {
+        String::new()
+    } else {
+        std::path::absolute(project_root_path)?.to_string_lossy().replace('\\', "/")
+    };
+    let removed: Vec<PathBuf> = if project_root_path.is_empty() {
+        clear_index_data()?
+    } else {
+        let normalized = normalize_root(&project_root_path);
+        clear_project(&normalized)
+    };
+
+    if json {
+        println!("{}", serde_json::json!({ "cleared": project_root_path, "removed": removed }));
+    } else if project_root_path.is_empty() {
+        println!("已清除所有项目的索引数据: {}", data_dir().display());
+    } else {
+        println!("已清除 {} 的索引数据，删除 {} 个文件", project_root_path, removed.len());
+    }
+    Ok(())
+}
+
+/// 删除所有项目的上传记录（含重试队列）、检查点、哈希缓存、本地索引和向量索引，保留密钥审计日志
+pub fn clear_index_data() -> Result<Vec<PathBuf>> {
+    let mut removed = Vec::new();
+    for name in INDEX_DATA_DIRS {
+        let dir = data_dir().join(name);
+        if dir.exists() {
+            fs::remove_dir_all(&dir)?;
+            removed.push(dir);
+        }
+    }
+    Ok(removed)
+}
+
+/// 删除项目的上传记录（含重试队列）、检查点、哈希缓存、本地索引和向量索引
+fn clear_project(normalized_root: &str) -> Vec<PathBuf> {
+    let journal = journal_file(normalized_root);
+    let files = [
+        journal.with_extension("lock"),
+        journal,
+        checkpoint_file(normalized_root),
+        cache_file(normalized_root),
+        local_index_file(normalized_root),
+        vector_index_file(normalized_root),
+    ];
+    files.into_iter().filter(|file| fs::remove_file(file).is_ok()).collect()
+}
+
+fn print_index(backend: &str, stats: &[IndexStats], json: bool) {
+    if json {
+        println!("{}", serde_json::json!({ "backend": backend, "roots": stats }));
+        return;
+    }
+    println!("索引完成（后端: {}）", backend);
+    for root in stats {
+        println!("  {}: 代码块 {}，新增 {}，失败 {}", root.root, root.blobs, root.added, root.failed);
+    }
+}
+
+fn print_status(backend: &str, statuses: &[RootStatus], json: bool) {
+    if json {
+        let value = serde_json::json!({ "backend": backend, "data_dir": data_dir(), "roots": statuses });
+        println!("{}", value);
+        return;
+    }
+    println!("后端: {}", backend);
+    println!("数据目录: {}", data_dir().display());
+    for status in statuses {
+        println!("{}", status.root);
+        println!("  文件: {}，代码块: {}", status.files, status.blobs);
+        match status.indexed {
+            Some(indexed) => println!("  已索引: {}，待索引: {}", indexed, status.pending),
+            None => println!("  尚未建立索引"),
+        }
+        if status.retry_queue > 0 {
+            println!("  等待重试上传: {}", status.retry_queue);
+        }
+    }
+}
+
+fn print_help() {
+    println!("寸止 - MCP 服务器与代码搜索命令行");
+    println!();
+    println!("用法:");
+    println!("  寸止                                启动 MCP 服务器");
+    println!("  寸止 index <路径> [选项]            建立或增量更新项目索引");
+    println!("  寸止 search <路径> <查询> [选项]    检索项目代码");
+    println!("  寸止 status <路径> [选项]           查看索引状态和统计");
+    println!("  寸止 clear [<路径>] [--json]        清除项目的索引数据，不指定路径时清除所有项目的索引数据");
+    println!("  寸止 help                           显示此帮助信息");
+    println!();
+    println!("选项:");
+    println!("  --root <目录>          与项目一起索引的其他根目录，可重复");
+    println!("  --discover             自动发现嵌套的 git 仓库、子模块和 Cargo 工作区成员");
+    println!("  --include <glob>       只检索匹配的文件或目录，可重复（search）");
+    println!("  --exclude <glob>       不检索匹配的文件或目录，可重复（search）");
+    println!("  --lang <语言>          只检索指定语言，可重复（search）");
+    println!("  --max-results <数量>   最多返回的代码片段数（search）");
+    println!("  --json                 以 JSON 输出结果");
+    println!();
+    println!("检索后端和端点使用设置界面中的代码搜索配置；日志输出到标准错误，可用 RUST_LOG=warn 减少输出。");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let cli = parse_args(&args("search /repo send message --include src/** --lang rust --max-results 5 --json")).unwrap();
+        assert_eq!(cli.command, Command::Search);
+        assert_eq!(cli.request.project_root_path, "/repo");
+        assert_eq!(cli.request.query, "send message");
+        assert_eq!(cli.request.include_globs, Some(vec!["src/**".to_string()]));
+        assert_eq!(cli.request.languages, Some(vec!["rust".to_string()]));
+        assert_eq!(cli.request.max_results, Some(5));
+        assert!(cli.json);
+
+        let cli = parse_args(&args("index /repo --root /other --discover")).unwrap();
+        assert_eq!(cli.request.additional_roots, Some(vec!["/other".to_string()]));
+        assert_eq!(cli.request.discover_workspace, Some(true));
+        assert!(!cli.json);
+
+        assert_eq!(parse_args(&args("clear")).unwrap().request.project_root_path, "");
+        assert!(parse_args(&args("search /repo")).is_err());
+        assert!(parse_args(&args("status")).is_err());
+        assert!(parse_args(&args("status /repo extra")).is_err());
+        assert!(parse_args(&args("index /repo --max-results")).is_err());
+        assert!(parse_args(&args("index /repo --unknown")).is_err());
+        assert!(parse_args(&args("reindex /repo")).is_err());
+    }
+}
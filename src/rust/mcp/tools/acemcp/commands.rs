@@ -1,7 +1,13 @@
 use tauri::{AppHandle, State};
 
 use crate::config::{AppState, save_config};
+use crate::constants::mcp::{
+    ACEMCP_DEFAULT_CHUNK_OVERLAP_LINES, ACEMCP_DEFAULT_EMBEDDING_MODEL, ACEMCP_DEFAULT_EXCLUDE_PATTERNS, ACEMCP_DEFAULT_MAX_BATCH_BYTES, ACEMCP_DEFAULT_TEXT_EXTENSIONS,
+    ACEMCP_DEFAULT_UPLOAD_CONCURRENCY, ACEMCP_SECRET_MODE_REDACT,
+};
 use super::{AcemcpTool};
+use super::cli::clear_index_data;
+use super::index::data_dir;
 use super::types::AcemcpRequest;
 use reqwest;
 
@@ -15,10 +21,28 @@ pub struct SaveAcemcpConfigArgs {
     pub batch_size: u32,
     #[serde(alias = "maxLinesPerBlob", alias = "_max_lines_per_blob")]
     pub max_lines_per_blob: u32,
+    #[serde(default, alias = "chunkOverlapLines")]
+    pub chunk_overlap_lines: Option<u32>,
     #[serde(alias = "textExtensions", alias = "_text_extensions")]
     pub text_extensions: Vec<String>,
     #[serde(alias = "excludePatterns", alias = "_exclude_patterns")]
     pub exclude_patterns: Vec<String>,
+    #[serde(default)]
+    pub backend: Option<String>,
+    #[serde(default)]
+    pub watch: Option<bool>,
+    #[serde(default, alias = "uploadConcurrency")]
+    pub upload_concurrency: Option<u32>,
+    #[serde(default, alias = "maxBatchBytes")]
+    pub max_batch_bytes: Option<u64>,
+    #[serde(default, alias = "embeddingBaseUrl")]
+    pub embedding_base_url: Option<String>,
+    #[serde(default, alias = "embeddingToken")]
+    pub embedding_token: Option<String>,
+    #[serde(default, alias = "embeddingModel")]
+    pub embedding_model: Option<String>,
+    #[serde(default, alias = "secretMode")]
+    pub secret_mode: Option<String>,
 }
 
 #[tauri::command]
@@ -28,7 +52,8 @@ pub async fn save_acemcp_config(
     app: AppHandle,
 ) -> Result<(), String> {
     let mut base_url = args.base_url.trim().to_string();
-    if !(base_url.starts_with("http://") || base_url.starts_with("https://")) {
+    // 本地后端可以不配置端点
+    if !base_url.is_empty() && !(base_url.starts_with("http://") || base_url.starts_with("https://")) {
         base_url = format!("http://{}", base_url);
         log::warn!("BASE_URL 缺少协议，已自动补全为: {}", base_url);
     }
@@ -38,12 +63,39 @@ pub async fn save_acemcp_config(
             .lock()
             .map_err(|e| format!("获取配置失败: {}", e))?;
 
-        config.mcp_config.acemcp_base_url = Some(base_url.clone());
+        config.mcp_config.acemcp_base_url = if base_url.is_empty() { None } else { Some(base_url.clone()) };
         config.mcp_config.acemcp_token = Some(args.token.clone());
         config.mcp_config.acemcp_batch_size = Some(args.batch_size);
         config.mcp_config.acemcp_max_lines_per_blob = Some(args.max_lines_per_blob);
+        if args.chunk_overlap_lines.is_some() {
+            config.mcp_config.acemcp_chunk_overlap_lines = args.chunk_overlap_lines;
+        }
         config.mcp_config.acemcp_text_extensions = Some(args.text_extensions.clone());
         config.mcp_config.acemcp_exclude_patterns = Some(args.exclude_patterns.clone());
+        config.mcp_config.acemcp_backend = args.backend.clone().filter(|backend| !backend.trim().is_empty());
+        if args.watch.is_some() {
+            config.mcp_config.acemcp_watch = args.watch;
+        }
+        if args.upload_concurrency.is_some() {
+            config.mcp_config.acemcp_upload_concurrency = args.upload_concurrency;
+        }
+        if args.max_batch_bytes.is_some() {
+            config.mcp_config.acemcp_max_batch_bytes = args.max_batch_bytes;
+        }
+        // 向量检索配置：传入空字符串表示清除
+        let non_empty = |value: &String| Some(value.trim().to_string()).filter(|value| !value.is_empty());
+        if let Some(url) = &args.embedding_base_url {
+            config.mcp_config.acemcp_embedding_base_url = non_empty(url);
+        }
+        if let Some(token) = &args.embedding_token {
+            config.mcp_config.acemcp_embedding_token = non_empty(token);
+        }
+        if let Some(model) = &args.embedding_model {
+            config.mcp_config.acemcp_embedding_model = non_empty(model);
+        }
+        if let Some(mode) = &args.secret_mode {
+            config.mcp_config.acemcp_secret_mode = non_empty(mode);
+        }
     }
 
     save_config(&state, &app)
@@ -227,13 +279,10 @@ pub async fn read_acemcp_logs(_state: State<'_, AppState>) -> Result<Vec<String>
 
 #[tauri::command]
 pub async fn clear_acemcp_cache(_state: State<'_, AppState>) -> Result<String, String> {
-    let cache_dir = std::env::var("HOME").unwrap_or_default() + "/.acemcp/data";
-    if std::path::Path::new(&cache_dir).exists() {
-        std::fs::remove_dir_all(&cache_dir).map_err(|e| e.to_string())?;
-    }
-    std::fs::create_dir_all(&cache_dir).map_err(|e| e.to_string())?;
-    log::info!("acemcp缓存已清除: {}", cache_dir);
-    Ok(cache_dir)
+    let cache_dir = data_dir();
+    clear_index_data().map_err(|e| e.to_string())?;
+    log::info!("acemcp缓存已清除: {}", cache_dir.display());
+    Ok(cache_dir.to_string_lossy().to_string())
 }
 
 #[derive(Debug, serde::Serialize)]
@@ -242,8 +291,17 @@ pub struct AcemcpConfigResponse {
     pub token: Option<String>,
     pub batch_size: u32,
     pub max_lines_per_blob: u32,
+    pub chunk_overlap_lines: u32,
     pub text_extensions: Vec<String>,
     pub exclude_patterns: Vec<String>,
+    pub backend: Option<String>,
+    pub watch: bool,
+    pub upload_concurrency: u32,
+    pub max_batch_bytes: u64,
+    pub embedding_base_url: Option<String>,
+    pub embedding_token: Option<String>,
+    pub embedding_model: String,
+    pub secret_mode: String,
 }
 
 #[tauri::command]
@@ -256,12 +314,29 @@ pub async fn get_acemcp_config(state: State<'_, AppState>) -> Result<AcemcpConfi
         token: config.mcp_config.acemcp_token.clone(),
         batch_size: config.mcp_config.acemcp_batch_size.unwrap_or(10),
         max_lines_per_blob: config.mcp_config.acemcp_max_lines_per_blob.unwrap_or(800),
+        chunk_overlap_lines: config.mcp_config.acemcp_chunk_overlap_lines.unwrap_or(ACEMCP_DEFAULT_CHUNK_OVERLAP_LINES),
         text_extensions: config.mcp_config.acemcp_text_extensions.clone().unwrap_or_else(|| {
-            vec![".rs".to_string(), ".ts".to_string(), ".js".to_string(), ".md".to_string(), ".toml".to_string()]
+            ACEMCP_DEFAULT_TEXT_EXTENSIONS.iter().map(|s| s.to_string()).collect()
         }),
         exclude_patterns: config.mcp_config.acemcp_exclude_patterns.clone().unwrap_or_else(|| {
-            vec!["node_modules".to_string(), ".git".to_string(), "target".to_string(), "dist".to_string()]
+            ACEMCP_DEFAULT_EXCLUDE_PATTERNS.iter().map(|s| s.to_string()).collect()
         }),
+        backend: config.mcp_config.acemcp_backend.clone(),
+        watch: config.mcp_config.acemcp_watch.unwrap_or(true),
+        upload_concurrency: config.mcp_config.acemcp_upload_concurrency.unwrap_or(ACEMCP_DEFAULT_UPLOAD_CONCURRENCY),
+        max_batch_bytes: config.mcp_config.acemcp_max_batch_bytes.unwrap_or(ACEMCP_DEFAULT_MAX_BATCH_BYTES),
+        embedding_base_url: config.mcp_config.acemcp_embedding_base_url.clone(),
+        embedding_token: config.mcp_config.acemcp_embedding_token.clone(),
+        embedding_model: config
+            .mcp_config
+            .acemcp_embedding_model
+            .clone()
+            .unwrap_or_else(|| ACEMCP_DEFAULT_EMBEDDING_MODEL.to_string()),
+        secret_mode: config
+            .mcp_config
+            .acemcp_secret_mode
+            .clone()
+            .unwrap_or_else(|| ACEMCP_SECRET_MODE_REDACT.to_string()),
     })
 }
 
@@ -279,7 +354,7 @@ pub async fn debug_acemcp_search(
     query: String,
     _app: AppHandle,
 ) -> Result<DebugSearchResult, String> {
-    let req = AcemcpRequest { project_root_path, query };
+    let req = AcemcpRequest { project_root_path, query, ..Default::default() };
     
     // 调用搜索函数（日志会通过 log crate 输出到 stderr）
     let search_result = AcemcpTool::search_context(req).await;
@@ -333,9 +408,14 @@ pub async fn execute_acemcp_tool(
                 .and_then(|v| v.as_str())
                 .ok_or_else(|| "缺少query参数".to_string())?
                 .to_string();
+
+            // 其余可选参数（结构化结果、检索范围等）
+            let mut req: AcemcpRequest = serde_json::from_value(arguments.clone())
+                .map_err(|e| format!("参数解析失败: {}", e))?;
+            req.project_root_path = project_root_path;
+            req.query = query;
             
             // 执行搜索
-            let req = AcemcpRequest { project_root_path, query };
             match AcemcpTool::search_context(req).await {
                 Ok(result) => {
                     // 转换结果为JSON
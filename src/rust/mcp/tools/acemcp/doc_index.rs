@@ -0,0 +1,90 @@
+// 按代码块哈希增量维护的检索索引
+// 本地索引和向量索引共用：哈希未变的代码块复用已有结果，只为新增代码块读取内容并建立索引，
+// 已不存在的代码块随之删除；索引有变化时才写回文件
+
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use super::index::{load_contents, HashedBlob};
+use super::roots::IndexRoot;
+use super::types::AcemcpConfig;
+use super::watcher::current_blobs;
+use crate::log_important;
+use crate::utils::write_atomic;
+
+/// 索引中的代码块
+pub trait IndexedDoc {
+    /// 与远程上传一致的 blob 哈希
+    fn hash(&self) -> &str;
+}
+
+/// 一次增量更新中需要处理的代码块
+pub struct DocChanges<D> {
+    /// 哈希未变、直接保留的代码块，按当前遍历顺序排列
+    pub kept: Vec<D>,
+    /// 新增的代码块，已读取内容
+    pub added: Vec<HashedBlob>,
+    /// 已不存在、从索引中删除的代码块数
+    pub removed: usize,
+}
+
+/// 读取索引文件，不存在、损坏或 `usable` 判断不可用时返回 None
+pub fn load_index<T: DeserializeOwned>(index_path: &Path, kind: &str, usable: impl FnOnce(&T) -> bool) -> Option<T> {
+    let data = fs::read_to_string(index_path).ok()?;
+    match serde_json::from_str::<T>(&data) {
+        Ok(index) if usable(&index) => Some(index),
+        _ => {
+            log_important!(info, "{}不可用，重新建立: {}", kind, index_path.display());
+            None
+        }
+    }
+}
+
+/// 对比根目录当前的代码块与已有索引
+pub fn diff_docs<D: IndexedDoc>(config: &AcemcpConfig, root: &IndexRoot, docs: Vec<D>) -> Result<DocChanges<D>> {
+    let blobs = current_blobs(config, root)?;
+
+    let mut existing: HashMap<String, D> = docs.into_iter().map(|doc| (doc.hash().to_string(), doc)).collect();
+    let mut kept = Vec::new();
+    let mut missing = Vec::new();
+    for blob in blobs {
+        match existing.remove(&blob.hash) {
+            Some(doc) => kept.push(doc),
+            None => missing.push(blob),
+        }
+    }
+
+    // 来自缓存的 blob 不含内容，只为新增代码块读取文件
+    let added = load_contents(config, root, missing);
+    Ok(DocChanges { kept, added, removed: existing.len() })
+}
+
+/// 增量更新索引：保留未变的代码块，用 `build` 为每个新增代码块建立索引，返回新增和删除的数量
+pub fn update_docs<D: IndexedDoc>(
+    config: &AcemcpConfig,
+    root: &IndexRoot,
+    docs: &mut Vec<D>,
+    build: impl FnMut(HashedBlob) -> D,
+) -> Result<(usize, usize)> {
+    let changes = diff_docs(config, root, std::mem::take(docs))?;
+    let added = changes.added.len();
+    *docs = changes.kept;
+    docs.extend(changes.added.into_iter().map(build));
+    Ok((added, changes.removed))
+}
+
+/// 写回索引文件
+pub fn save_index<T: Serialize>(index_path: &Path, index: &T, kind: &str) {
+    match serde_json::to_string(index) {
+        Ok(data) => {
+            if let Err(e) = write_atomic(index_path, &data) {
+                log_important!(warn, "写入{}失败: {}", kind, e);
+            }
+        }
+        Err(e) => log_important!(warn, "序列化{}失败: {}", kind, e),
+    }
+}
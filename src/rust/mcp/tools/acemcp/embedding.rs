@@ -0,0 +1,326 @@
+// 向量检索
+// 调用 OpenAI 兼容的 embeddings 接口为代码块生成向量，在 ~/.acemcp/data/vectors 下维护每个根目录的向量索引，
+// 按与查询向量的余弦相似度排序；哈希未变的代码块复用已有向量，只为新增代码块请求接口
+
+use anyhow::Result;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use super::chunker::blob_line_range;
+use super::doc_index::{diff_docs, load_index, save_index, IndexedDoc};
+use super::index::{keyed_data_file, HashedBlob};
+use super::local::{best_snippet, tokenize_code, MAX_RESULTS};
+use super::mcp::{post_json, retry_request};
+use super::provider::{IndexFuture, IndexStats, RetrievalProvider, SearchFuture};
+use super::results::{SearchHit, SearchOutput};
+use super::roots::{attribute_hits, indexed_roots, IndexRoot};
+use super::scope::SearchScope;
+use super::types::AcemcpConfig;
+use crate::constants::mcp::ACEMCP_BACKEND_EMBEDDING;
+use crate::log_important;
+
+/// 向量索引格式版本，存储结构变化时递增以触发重建
+const VECTOR_INDEX_VERSION: u32 = 1;
+
+/// 每次请求 embeddings 接口的代码块数量
+const EMBEDDING_BATCH_SIZE: usize = 64;
+
+/// 每个代码块送入模型的最大字符数，超出部分截断以免超过模型的输入长度
+const MAX_INPUT_CHARS: usize = 6000;
+
+/// 向量索引文件
+#[derive(Serialize, Deserialize, Default)]
+struct VectorIndex {
+    version: u32,
+    root: String,
+    /// 生成向量所用的模型，模型变化时重建
+    model: String,
+    docs: Vec<VectorDoc>,
+}
+
+/// 已生成向量的代码块
+#[derive(Serialize, Deserialize, Clone)]
+struct VectorDoc {
+    /// 与远程上传一致的 blob 哈希
+    hash: String,
+    /// blob 路径，大文件分块时带行号范围后缀
+    path: String,
+    /// 代码块在原文件中的起始行（从 1 开始）
+    start_line: usize,
+    content: String,
+    vector: Vec<f32>,
+}
+
+/// 向量检索：OpenAI 兼容的 embeddings 接口加本地向量索引
+pub struct EmbeddingProvider {
+    config: AcemcpConfig,
+    client: Client,
+}
+
+impl RetrievalProvider for EmbeddingProvider {
+    fn name(&self) -> &'static str {
+        ACEMCP_BACKEND_EMBEDDING
+    }
+
+    fn search<'a>(&'a self, roots: &'a [IndexRoot], query: &'a str, scope: &'a SearchScope) -> SearchFuture<'a> {
+        Box::pin(self.index_and_search(roots, query, scope))
+    }
+
+    fn index<'a>(&'a self, roots: &'a [IndexRoot]) -> IndexFuture<'a> {
+        Box::pin(self.index_roots(roots))
+    }
+
+    /// 向量索引中的代码块哈希，模型已变化的索引视为尚未建立
+    fn indexed_hashes(&self, root: &IndexRoot) -> Option<HashSet<String>> {
+        let index_path = vector_index_file(&root.normalized);
+        if !index_path.exists() {
+            return None;
+        }
+        let index = read_index(&index_path, &root.normalized, &self.config.embedding_model_or_default());
+        Some(index.docs.into_iter().map(|doc| doc.hash).collect())
+    }
+}
+
+impl EmbeddingProvider {
+    pub fn new(config: AcemcpConfig) -> Self {
+        Self { config, client: Client::new() }
+    }
+
+    /// 向量接口的端点、令牌和模型
+    fn endpoint(&self) -> Result<(String, String, String)> {
+        let base_url = self
+            .config
+            .embedding_base_url
+            .clone()
+            .filter(|url| !url.trim().is_empty())
+            .ok_or_else(|| anyhow::anyhow!("未配置向量检索端点 embedding_base_url"))?;
+        let token = self.config.embedding_token.clone().unwrap_or_default();
+        let model = self.config.embedding_model_or_default();
+        log_important!(info, "向量检索配置: base_url={}, model={}", base_url, model);
+        Ok((base_url, token, model))
+    }
+
+    /// 只更新各根目录的向量索引，不检索
+    async fn index_roots(&self, roots: &[IndexRoot]) -> Result<Vec<IndexStats>> {
+        let (base_url, token, model) = self.endpoint()?;
+        let mut stats = Vec::new();
+        for root in indexed_roots(roots) {
+            stats.push(self.update_index(root, &base_url, &token, &model).await?.1);
+        }
+        Ok(stats)
+    }
+
+    /// 增量更新各根目录的向量索引，合并后按与查询的相似度排序，路径加上根目录的前缀
+    async fn index_and_search(&self, roots: &[IndexRoot], query: &str, scope: &SearchScope) -> Result<SearchOutput> {
+        let (base_url, token, model) = self.endpoint()?;
+
+        let mut docs = Vec::new();
+        for root in indexed_roots(roots) {
+            docs.extend(self.update_index(root, &base_url, &token, &model).await?.0.docs.into_iter().map(|mut doc| {
+                doc.path = root.merged_path(&doc.path);
+                doc
+            }));
+        }
+        if docs.is_empty() {
+            anyhow::bail!("未在项目中找到可索引的文本文件");
+        }
+
+        let query_vector = embed_texts(&self.client, &base_url, &token, &model, &[query.to_string()])
+            .await?
+            .pop()
+            .unwrap_or_default();
+        let hits = rank_docs(&docs, &query_vector, query, scope, scope.max_results.unwrap_or(MAX_RESULTS));
+
+        log_important!(info, "向量检索完成: query={}, 代码块总数={}, 命中代码块={}", query, docs.len(), hits.len());
+        Ok(attribute_hits(SearchOutput::from_hits(hits), roots))
+    }
+
+    /// 为新增代码块生成向量，删除已不存在的代码块
+    async fn update_index(&self, root: &IndexRoot, base_url: &str, token: &str, model: &str) -> Result<(VectorIndex, IndexStats)> {
+        let index_path = vector_index_file(&root.normalized);
+        let mut index = read_index(&index_path, &root.normalized, model);
+        let previous_count = index.docs.len();
+
+        // 向量按批次并发请求，不能逐个代码块建立，因此只取差异，生成后再追加
+        let changes = diff_docs(&self.config, root, std::mem::take(&mut index.docs))?;
+        index.docs = changes.kept;
+        let removed = changes.removed;
+        let requested = changes.added.len();
+        let (added, failed) = self.embed_blobs(changes.added, base_url, token, model, &mut index.docs).await;
+
+        log_important!(
+            info,
+            "向量索引更新: 根目录={}, 代码块总数={}, 新增={}, 删除={}, 原有={}, 失败={}",
+            root.normalized,
+            index.docs.len(),
+            added,
+            removed,
+            previous_count,
+            failed.len()
+        );
+
+        if added > 0 || removed > 0 {
+            save_index(&index_path, &index, "向量索引");
+        }
+
+        // 部分批次失败时使用已有向量继续检索，失败的代码块在下次检索时重新请求
+        if index.docs.is_empty() {
+            if let Some(error) = failed.into_iter().next() {
+                anyhow::bail!("生成向量失败: {}", error);
+            }
+        }
+        let stats = IndexStats { root: root.path.clone(), blobs: index.docs.len(), added, failed: requested - added };
+        Ok((index, stats))
+    }
+
+    /// 并发请求新增代码块的向量，返回成功的数量和失败批次的错误信息
+    async fn embed_blobs(
+        &self,
+        blobs: Vec<HashedBlob>,
+        base_url: &str,
+        token: &str,
+        model: &str,
+        docs: &mut Vec<VectorDoc>,
+    ) -> (usize, Vec<String>) {
+        let semaphore = Arc::new(Semaphore::new(self.config.upload_concurrency_or_default()));
+        let mut tasks = JoinSet::new();
+        let mut pending = blobs.into_iter().peekable();
+        while pending.peek().is_some() {
+            let batch: Vec<HashedBlob> = pending.by_ref().take(EMBEDDING_BATCH_SIZE).collect();
+            let client = self.client.clone();
+            let (base_url, token, model) = (base_url.to_string(), token.to_string(), model.to_string());
+            let semaphore = semaphore.clone();
+            tasks.spawn(async move {
+                let _permit = semaphore.acquire_owned().await;
+                let inputs: Vec<String> = batch.iter().map(embedding_input).collect();
+                let result = embed_texts(&client, &base_url, &token, &model, &inputs).await;
+                (batch, result)
+            });
+        }
+
+        let mut added = 0;
+        let mut failed = Vec::new();
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
+                Ok((batch, Ok(vectors))) => {
+                    added += batch.len();
+                    docs.extend(batch.into_iter().zip(vectors).map(|(blob, vector)| VectorDoc {
+                        start_line: blob_line_range(&blob.path).map_or(1, |(start, _)| start),
+                        hash: blob.hash,
+                        path: blob.path,
+                        content: blob.content.unwrap_or_default(),
+                        vector,
+                    }));
+                }
+                Ok((batch, Err(e))) => {
+                    log_important!(warn, "生成向量失败: blobs={}, 错误={}", batch.len(), e);
+                    failed.push(e.to_string());
+                }
+                Err(e) => log_important!(warn, "向量生成任务异常: {}", e),
+            }
+        }
+        (added, failed)
+    }
+}
+
+impl IndexedDoc for VectorDoc {
+    fn hash(&self) -> &str {
+        &self.hash
+    }
+}
+
+/// 调用 OpenAI 兼容的 `/embeddings` 接口，按输入顺序返回向量
+pub async fn embed_texts(client: &Client, base_url: &str, token: &str, model: &str, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+    let url = format!("{}/embeddings", base_url);
+    let payload = serde_json::json!({ "model": model, "input": texts });
+    let value = retry_request(|| post_json(client, &url, token, &payload), 3, 1.0).await?;
+
+    let data = value
+        .get("data")
+        .and_then(|v| v.as_array())
+        .ok_or_else(|| anyhow::anyhow!("响应中缺少data"))?;
+    let mut vectors: Vec<Option<Vec<f32>>> = vec![None; texts.len()];
+    for (position, item) in data.iter().enumerate() {
+        let index = item.get("index").and_then(|v| v.as_u64()).map_or(position, |i| i as usize);
+        let embedding = item
+            .get("embedding")
+            .and_then(|v| v.as_array())
+            .ok_or_else(|| anyhow::anyhow!("响应中缺少embedding"))?;
+        if let Some(slot) = vectors.get_mut(index) {
+            *slot = Some(embedding.iter().filter_map(|v| v.as_f64()).map(|v| v as f32).collect());
+        }
+    }
+
+    vectors
+        .into_iter()
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| anyhow::anyhow!("返回的向量数量与输入不一致: 输入={}, 返回={}", texts.len(), data.len()))
+}
+
+/// 送入模型的文本：路径加内容，过长时截断
+fn embedding_input(blob: &HashedBlob) -> String {
+    let content = blob.content.as_deref().unwrap_or("");
+    let mut input = format!("{}\n{}", blob.path, content);
+    if let Some((cut, _)) = input.char_indices().nth(MAX_INPUT_CHARS) {
+        input.truncate(cut);
+    }
+    input
+}
+
+/// 按余弦相似度对检索范围内的代码块排序并截取最相关的片段
+fn rank_docs(docs: &[VectorDoc], query_vector: &[f32], query: &str, scope: &SearchScope, limit: usize) -> Vec<SearchHit> {
+    let query_terms: HashSet<String> = tokenize_code(query).into_iter().collect();
+    let mut scored: Vec<(&VectorDoc, f64)> = docs
+        .iter()
+        .filter(|doc| scope.matches(&doc.path))
+        .map(|doc| (doc, cosine_similarity(&doc.vector, query_vector)))
+        .filter(|(_, score)| *score > 0.0)
+        .collect();
+
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.path.cmp(&b.0.path)));
+    scored.truncate(limit);
+
+    scored
+        .into_iter()
+        .map(|(doc, score)| best_snippet(&doc.path, doc.start_line, &doc.content, score, &query_terms))
+        .collect()
+}
+
+/// 余弦相似度，维度不一致或任一向量为零时为 0
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f64 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    let (mut dot, mut norm_a, mut norm_b) = (0.0f64, 0.0f64, 0.0f64);
+    for (x, y) in a.iter().zip(b) {
+        let (x, y) = (*x as f64, *y as f64);
+        dot += x * y;
+        norm_a += x * x;
+        norm_b += y * y;
+    }
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a.sqrt() * norm_b.sqrt())
+}
+
+/// 读取向量索引，不存在、损坏、版本或模型不一致时返回空索引
+fn read_index(index_path: &Path, normalized_root: &str, model: &str) -> VectorIndex {
+    let usable = |index: &VectorIndex| index.version == VECTOR_INDEX_VERSION && index.model == model;
+    load_index(index_path, "向量索引", usable).unwrap_or_else(|| VectorIndex {
+        version: VECTOR_INDEX_VERSION,
+        root: normalized_root.to_string(),
+        model: model.to_string(),
+        docs: Vec::new(),
+    })
+}
+
+/// 向量索引文件路径
+pub fn vector_index_file(normalized_root: &str) -> PathBuf {
+    keyed_data_file("vectors", normalized_root, "json")
+}
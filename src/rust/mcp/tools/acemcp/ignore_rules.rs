@@ -0,0 +1,179 @@
+// 忽略规则
+// 遍历项目时使用 ignore 的 walker，支持各级 .gitignore、.ignore、.git/info/exclude、全局 excludesFile 和 .acemcpignore；
+// 后台索引处理单个文件变化时按同样的规则逐级判断
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::{Match, WalkBuilder};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::constants::mcp::ACEMCP_IGNORE_FILENAME;
+use crate::log_debug;
+
+/// 每个目录中的忽略文件，按优先级从高到低排列
+const IGNORE_FILENAMES: [&str; 3] = [ACEMCP_IGNORE_FILENAME, ".ignore", ".gitignore"];
+
+/// 创建项目遍历器
+///
+/// 不跳过隐藏文件，跟随符号链接（walker 会检测并跳过链接循环），
+/// 不要求项目是 git 仓库也应用 .gitignore
+pub fn walk_builder(root: &Path) -> WalkBuilder {
+    let mut builder = WalkBuilder::new(root);
+    builder
+        .hidden(false)
+        .follow_links(true)
+        .require_git(false)
+        .add_custom_ignore_filename(ACEMCP_IGNORE_FILENAME);
+    builder
+}
+
+/// 是否为忽略规则文件，变化后需要重新遍历项目
+pub fn is_ignore_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| IGNORE_FILENAMES.contains(&name))
+        || path.ends_with(".git/info/exclude")
+}
+
+/// 单个路径的忽略规则判断，各目录的规则在首次用到时加载
+pub struct IgnoreRules {
+    dirs: RefCell<HashMap<PathBuf, Vec<Gitignore>>>,
+    git_exclude: Option<Gitignore>,
+    global: Option<Gitignore>,
+}
+
+impl IgnoreRules {
+    pub fn new(root: &Path) -> Self {
+        let git_exclude = root
+            .ancestors()
+            .find(|dir| dir.join(".git").exists())
+            .and_then(|repo| load_ignore_file(repo, &repo.join(".git").join("info").join("exclude")));
+
+        let (global, err) = Gitignore::global();
+        if let Some(e) = err {
+            log_debug!("读取全局忽略规则出错: {}", e);
+        }
+
+        Self {
+            dirs: RefCell::new(HashMap::new()),
+            git_exclude,
+            global: (!global.is_empty()).then_some(global),
+        }
+    }
+
+    /// 路径是否被忽略
+    ///
+    /// 从路径所在目录逐级向上查找，越深的规则优先级越高；
+    /// 之后依次检查 .git/info/exclude 和全局规则
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        for dir in path.ancestors().skip(1) {
+            for matcher in self.matchers_for(dir) {
+                match matcher.matched_path_or_any_parents(path, is_dir) {
+                    Match::Ignore(_) => return true,
+                    Match::Whitelist(_) => return false,
+                    Match::None => {}
+                }
+            }
+        }
+
+        for matcher in [&self.git_exclude, &self.global].into_iter().flatten() {
+            if path.starts_with(matcher.path()) {
+                match matcher.matched_path_or_any_parents(path, is_dir) {
+                    Match::Ignore(_) => return true,
+                    Match::Whitelist(_) => return false,
+                    Match::None => {}
+                }
+            }
+        }
+
+        false
+    }
+
+    fn matchers_for(&self, dir: &Path) -> Vec<Gitignore> {
+        self.dirs
+            .borrow_mut()
+            .entry(dir.to_path_buf())
+            .or_insert_with(|| {
+                IGNORE_FILENAMES
+                    .iter()
+                    .filter_map(|name| load_ignore_file(dir, &dir.join(name)))
+                    .collect()
+            })
+            .clone()
+    }
+}
+
+/// 加载单个忽略文件，文件中个别规则有误时保留其余规则
+fn load_ignore_file(root: &Path, path: &Path) -> Option<Gitignore> {
+    if !path.is_file() {
+        return None;
+    }
+    let mut builder = GitignoreBuilder::new(root);
+    if let Some(e) = builder.add(path) {
+        log_debug!("忽略文件中存在无效规则，已跳过: {:?}, 错误: {}", path, e);
+    }
+    match builder.build() {
+        Ok(matcher) => Some(matcher),
+        Err(e) => {
+            log_debug!("加载忽略文件失败: {:?}, 错误: {}", path, e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_walker_and_rules_agree() {
+        let root = std::env::temp_dir().join(format!("acemcp-ignore-test-{}", uuid::Uuid::new_v4()));
+        let write = |rel: &str, content: &str| {
+            let path = root.join(rel);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        };
+        write(".git/info/exclude", "secret.rs\n");
+        write(".gitignore", "*.log\nbuild/\n");
+        write("src/.gitignore", "generated.rs\n!keep.log\n");
+        write(".ignore", "vendor/\n");
+        write(ACEMCP_IGNORE_FILENAME, "docs/*.md\n");
+        for file in [
+            "main.rs", "secret.rs", "debug.log", "build/out.rs", "src/lib.rs", "src/generated.rs",
+            "src/keep.log", "vendor/dep.rs", "docs/guide.md", "docs/guide.rs",
+        ] {
+            write(file, "x");
+        }
+
+        let mut walked: Vec<String> = walk_builder(&root)
+            .filter_entry(|entry| entry.file_name() != ".git")
+            .build()
+            .flatten()
+            .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+            .map(|entry| entry.path().strip_prefix(&root).unwrap().to_string_lossy().replace('\\', "/"))
+            .filter(|rel| !is_ignore_file(Path::new(rel)))
+            .collect();
+        walked.sort();
+        assert_eq!(walked, ["docs/guide.rs", "main.rs", "src/keep.log", "src/lib.rs"]);
+
+        let rules = IgnoreRules::new(&root);
+        for (rel, ignored) in [
+            ("main.rs", false),
+            ("secret.rs", true),
+            ("debug.log", true),
+            ("build/out.rs", true),
+            ("src/lib.rs", false),
+            ("src/generated.rs", true),
+            ("src/keep.log", false),
+            ("vendor/dep.rs", true),
+            ("docs/guide.md", true),
+            ("docs/guide.rs", false),
+        ] {
+            assert_eq!(rules.is_ignored(&root.join(rel), false), ignored, "{}", rel);
+        }
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,445 @@
+// 代码文件收集与分块
+// 远程与本地检索后端共用：按扩展名、排除模式和忽略规则收集文件，并分割为 blob
+
+use anyhow::Result;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use encoding_rs::{GBK, WINDOWS_1252, UTF_8};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ring::digest::{Context as ShaContext, SHA256};
+use serde::{Deserialize, Serialize};
+
+use super::cache::{FileHashCache, FileStamp};
+use super::chunker::{blob_file_path, split_content, ChunkOptions};
+use super::ignore_rules::{walk_builder, IgnoreRules};
+use super::roots::IndexRoot;
+use super::secrets::SecretPolicy;
+use super::types::AcemcpConfig;
+
+use crate::log_debug;
+use crate::log_important;
+
+/// 检测二进制内容时检查的字节数
+const BINARY_SNIFF_LEN: usize = 8000;
+
+/// 待索引的代码块
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BlobItem {
+    pub path: String,
+    pub content: String,
+}
+
+/// 带哈希的代码块
+///
+/// 来自哈希缓存或后台索引时不含内容，需要上传或建立本地索引时再用 `load_contents` 读取
+#[derive(Clone)]
+pub struct HashedBlob {
+    pub hash: String,
+    pub path: String,
+    pub content: Option<String>,
+}
+
+impl HashedBlob {
+    pub fn new(blob: BlobItem) -> Self {
+        Self { hash: sha256_hex(&blob.path, &blob.content), path: blob.path, content: Some(blob.content) }
+    }
+
+    /// 去掉内容只保留哈希
+    pub fn without_content(self) -> Self {
+        Self { content: None, ..self }
+    }
+
+    /// 转换为待上传的 blob，不含内容时返回 None
+    pub fn into_blob_item(self) -> Option<BlobItem> {
+        Some(BlobItem { path: self.path, content: self.content? })
+    }
+}
+
+/// 为不含内容的 blob 重新读取文件内容
+///
+/// 文件在计算哈希后又被修改时使用最新内容并重新计算哈希；文件已不存在时丢弃对应的 blob。
+/// 内容按与收集时相同的密钥检测策略处理，哈希保持一致
+pub fn load_contents(config: &AcemcpConfig, root: &IndexRoot, blobs: Vec<HashedBlob>) -> Vec<HashedBlob> {
+    let root_path = PathBuf::from(&root.path);
+    let chunk_options = config.chunk_options();
+    let secrets = SecretPolicy::load(&root_path, &config.secret_mode_or_default());
+    let mut out = Vec::with_capacity(blobs.len());
+    let mut missing: std::collections::HashMap<String, Vec<String>> = std::collections::HashMap::new();
+
+    for blob in blobs {
+        if blob.content.is_some() {
+            out.push(blob);
+        } else {
+            missing.entry(blob_file_path(&blob.path).to_string()).or_default().push(blob.path);
+        }
+    }
+
+    for (file, paths) in missing {
+        let Some(content) = read_file_with_encoding(&root_path.join(&file)) else {
+            log_debug!("读取文件内容失败，跳过: {}", file);
+            continue;
+        };
+        let Some(content) = secrets.apply(&file, content, false) else {
+            continue;
+        };
+        out.extend(
+            split_content(&file, &content, chunk_options)
+                .into_iter()
+                .filter(|chunk| paths.contains(&chunk.path))
+                .map(HashedBlob::new),
+        );
+    }
+
+    out
+}
+
+/// 索引数据目录 `~/.acemcp/data`，测试时使用临时目录，不影响本机已有的索引
+pub fn data_dir() -> PathBuf {
+    #[cfg(test)]
+    let base = std::env::temp_dir().join(format!("acemcp-test-{}", std::process::id()));
+    #[cfg(not(test))]
+    let base = dirs::home_dir().unwrap_or_else(|| PathBuf::from(".")).join(".acemcp");
+
+    let dir = base.join("data");
+    let _ = fs::create_dir_all(&dir);
+    dir
+}
+
+/// 数据目录的 `subdir` 下以键的哈希命名的文件，各项目的记录、缓存和索引据此分开存放
+pub fn keyed_data_file(subdir: &str, key: &str, extension: &str) -> PathBuf {
+    let dir = data_dir().join(subdir);
+    let _ = fs::create_dir_all(&dir);
+    let hash = sha256_hex(key, "");
+    dir.join(format!("{}.{}", &hash[..16], extension))
+}
+
+/// 规范化项目根目录，作为索引数据的键
+pub fn normalize_root(project_root_path: &str) -> String {
+    PathBuf::from(project_root_path)
+        .canonicalize()
+        .unwrap_or_else(|_| PathBuf::from(project_root_path))
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// 判断内容是否为二进制
+fn is_binary(buf: &[u8]) -> bool {
+    buf.iter().take(BINARY_SNIFF_LEN).any(|&byte| byte == 0)
+}
+
+/// 读取文件内容，支持多种编码检测，二进制文件返回 None
+/// 尝试的编码顺序：utf-8, gbk (包含 gb2312), windows-1252 (包含 latin-1)
+/// 如果都失败，则使用 utf-8 with errors='ignore'
+pub fn read_file_with_encoding(path: &Path) -> Option<String> {
+    let mut file = fs::File::open(path).ok()?;
+    let mut buf = Vec::new();
+    if file.read_to_end(&mut buf).is_err() {
+        return None;
+    }
+
+    // 与 git 相同，开头包含 NUL 字节的视为二进制文件
+    if is_binary(&buf) {
+        log_debug!("跳过二进制文件: {:?}", path);
+        return None;
+    }
+
+    // 尝试 utf-8
+    let (decoded, _, had_errors) = UTF_8.decode(&buf);
+    if !had_errors {
+        return Some(decoded.into_owned());
+    }
+
+    // 尝试 gbk
+    let (decoded, _, had_errors) = GBK.decode(&buf);
+    if !had_errors {
+        log_debug!("成功使用 GBK 编码读取文件: {:?}", path);
+        return Some(decoded.into_owned());
+    }
+
+    // 尝试 gb2312 (GBK 是 GB2312 的超集，可以处理 GB2312 编码)
+    // encoding_rs 中没有单独的 GB2312，使用 GBK 代替
+    // GBK 已经在上一步尝试过了，这里跳过
+
+    // 尝试 latin-1 (WINDOWS_1252 是 ISO-8859-1 的超集，可以处理大部分 latin-1 编码)
+    let (decoded, _, had_errors) = WINDOWS_1252.decode(&buf);
+    if !had_errors {
+        log_debug!("成功使用 WINDOWS_1252 编码读取文件: {:?}", path);
+        return Some(decoded.into_owned());
+    }
+
+    // 如果所有编码都失败，使用 utf-8 with errors='ignore' (lossy 解码)
+    let (decoded, _, _) = UTF_8.decode(&buf);
+    log_debug!("使用 UTF-8 (lossy) 读取文件，部分字符可能丢失: {:?}", path);
+    Some(decoded.into_owned())
+}
+
+pub fn sha256_hex(path: &str, content: &str) -> String {
+    let mut ctx = ShaContext::new(&SHA256);
+    // 先更新路径的哈希，再更新内容的哈希，与Python版本保持一致
+    ctx.update(path.as_bytes());
+    ctx.update(content.as_bytes());
+    let digest = ctx.finish();
+    hex::encode(digest.as_ref())
+}
+
+/// 构建排除模式的 GlobSet
+pub fn build_exclude_globset(exclude_patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in exclude_patterns {
+        // 尝试将模式转换为 Glob
+        if let Ok(glob) = Glob::new(pattern) {
+            builder.add(glob);
+        } else {
+            log_debug!("无效的排除模式，跳过: {}", pattern);
+        }
+    }
+    builder.build().map_err(|e| anyhow::anyhow!("构建排除模式失败: {}", e))
+}
+
+/// 检查路径是否应该被排除
+/// 使用 globset 进行完整的 fnmatch 模式匹配（与 Python 版本保持一致）
+/// Python 版本使用 fnmatch.fnmatch 检查路径的各个部分和完整路径
+pub fn should_exclude(path: &Path, root: &Path, exclude_globset: Option<&GlobSet>) -> bool {
+    if exclude_globset.is_none() {
+        return false;
+    }
+    let globset = exclude_globset.unwrap();
+
+    // 获取相对路径
+    let rel = match path.strip_prefix(root) {
+        Ok(rel) => rel,
+        Err(_) => path,
+    };
+
+    // 转换为使用正斜杠的字符串（用于匹配）
+    let rel_forward = rel.to_string_lossy().replace('\\', "/");
+    
+    // 检查完整相对路径（与 Python 版本的 fnmatch(path_str, pattern) 一致）
+    if globset.is_match(&rel_forward) {
+        return true;
+    }
+
+    // 检查路径的各个部分（与 Python 版本的 fnmatch(part, pattern) 一致）
+    for part in rel.iter() {
+        if let Some(part_str) = part.to_str() {
+            if globset.is_match(part_str) {
+                return true;
+            }
+        }
+    }
+
+    false
+}
+
+/// 文件过滤规则：扩展名、排除模式与忽略规则
+pub struct FileFilter {
+    root: PathBuf,
+    text_exts: Vec<String>,
+    exclude_globset: Option<GlobSet>,
+    ignore_rules: IgnoreRules,
+    secrets: SecretPolicy,
+}
+
+impl FileFilter {
+    pub fn new(root: &Path, text_exts: &[String], exclude_patterns: &[String], secret_mode: &str) -> Self {
+        // 构建排除模式的 GlobSet
+        let exclude_globset = if exclude_patterns.is_empty() {
+            None
+        } else {
+            match build_exclude_globset(exclude_patterns) {
+                Ok(gs) => Some(gs),
+                Err(e) => {
+                    log_debug!("构建排除模式失败，将使用简单匹配: {}", e);
+                    None
+                }
+            }
+        };
+
+        Self {
+            root: root.to_path_buf(),
+            text_exts: text_exts.to_vec(),
+            exclude_globset,
+            ignore_rules: IgnoreRules::new(root),
+            secrets: SecretPolicy::load(root, secret_mode),
+        }
+    }
+
+    /// 是否被忽略规则忽略
+    fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        self.ignore_rules.is_ignored(path, is_dir)
+    }
+
+    /// 是否命中排除模式，`.git` 目录始终排除
+    fn is_excluded(&self, path: &Path) -> bool {
+        is_git_dir(path, &self.root) || should_exclude(path, &self.root, self.exclude_globset.as_ref())
+    }
+
+    /// 是否为要索引的扩展名
+    fn has_text_extension(&self, path: &Path) -> bool {
+        path.extension().and_then(|s| s.to_str()).map(|e| {
+            let dot = format!(".{}", e).to_lowercase();
+            self.text_exts.iter().any(|te| te.eq_ignore_ascii_case(&dot))
+        }).unwrap_or(false)
+    }
+
+    /// 文件是否需要索引
+    pub fn accepts_file(&self, path: &Path) -> bool {
+        !self.is_ignored(path, false) && !self.is_excluded(path) && self.has_text_extension(path)
+    }
+
+    /// 目录是否需要遍历
+    pub fn accepts_dir(&self, path: &Path) -> bool {
+        !self.is_ignored(path, true) && !self.is_excluded(path)
+    }
+
+    /// 相对项目根目录、使用正斜杠的路径
+    pub fn relative_path(&self, path: &Path) -> String {
+        path.strip_prefix(&self.root).unwrap_or(path).to_string_lossy().replace('\\', "/")
+    }
+
+    /// 读取文件内容并按密钥检测策略处理，检测到密钥且策略为跳过时返回 None
+    pub fn read_content(&self, path: &Path) -> Option<String> {
+        let content = read_file_with_encoding(path)?;
+        self.secrets.apply(&self.relative_path(path), content, true)
+    }
+
+    /// 读取单个文件并分块，文件不需要索引或无法读取时返回 None
+    pub fn read_blobs(&self, path: &Path, chunk_options: ChunkOptions) -> Option<Vec<BlobItem>> {
+        if !path.is_file() || !self.accepts_file(path) {
+            return None;
+        }
+        let content = self.read_content(path)?;
+        Some(split_content(&self.relative_path(path), &content, chunk_options))
+    }
+}
+
+/// 路径是否位于项目的 `.git` 目录中
+pub fn is_git_dir(path: &Path, root: &Path) -> bool {
+    path.strip_prefix(root).unwrap_or(path).components().any(|part| part.as_os_str() == ".git")
+}
+
+/// 收集项目中需要索引的 blob 及其哈希
+///
+/// 遵循各级 .gitignore、.ignore、.git/info/exclude、全局忽略规则和 .acemcpignore，跳过符号链接循环和二进制文件。
+/// 文件路径、修改时间、大小和 inode 与缓存一致时直接使用缓存的哈希，不读取文件，返回的 blob 不含内容。
+/// blob 路径始终相对项目根目录，与该目录参与的是单根目录还是多根目录检索无关。
+/// 文件内容按 `secret_mode` 和项目的 `.acemcpsecrets` 检测密钥，在计算哈希前替换或跳过
+pub fn collect_blobs(
+    root: &str,
+    text_exts: &[String],
+    exclude_patterns: &[String],
+    chunk_options: ChunkOptions,
+    secret_mode: &str,
+) -> anyhow::Result<Vec<HashedBlob>> {
+    scan_blobs(root, text_exts, exclude_patterns, chunk_options, secret_mode, true)
+}
+
+/// 与 `collect_blobs` 相同，但不写入哈希缓存，也不记录密钥审计日志，用于查看索引状态
+pub fn peek_blobs(
+    root: &str,
+    text_exts: &[String],
+    exclude_patterns: &[String],
+    chunk_options: ChunkOptions,
+    secret_mode: &str,
+) -> anyhow::Result<Vec<HashedBlob>> {
+    scan_blobs(root, text_exts, exclude_patterns, chunk_options, secret_mode, false)
+}
+
+/// `persist` 为 false 时只读取哈希缓存，不更新缓存和审计日志
+fn scan_blobs(
+    root: &str,
+    text_exts: &[String],
+    exclude_patterns: &[String],
+    chunk_options: ChunkOptions,
+    secret_mode: &str,
+    persist: bool,
+) -> anyhow::Result<Vec<HashedBlob>> {
+    let root_path = PathBuf::from(root);
+    if !root_path.exists() { anyhow::bail!("项目根目录不存在: {}", root); }
+    
+    log_important!(info, "开始收集代码文件: 根目录={}, 扩展名={:?}, 排除模式={:?}", root, text_exts, exclude_patterns);
+    
+    let filter = FileFilter::new(&root_path, text_exts, exclude_patterns, secret_mode);
+    let mut cache = FileHashCache::load(&normalize_root(root), text_exts, chunk_options, filter.secrets.fingerprint());
+    let now = SystemTime::now();
+    let mut out = Vec::new();
+    let mut scanned_files = 0;
+    let mut indexed_files = 0;
+    let excluded_count = Arc::new(AtomicUsize::new(0));
+
+    // 排除模式在遍历时直接剪掉整个目录
+    let exclude_globset = filter.exclude_globset.clone();
+    let walk_root = root_path.clone();
+    let walk_excluded = excluded_count.clone();
+    let walker = walk_builder(&root_path)
+        .filter_entry(move |entry| {
+            let excluded =
+                is_git_dir(entry.path(), &walk_root) || should_exclude(entry.path(), &walk_root, exclude_globset.as_ref());
+            if excluded {
+                walk_excluded.fetch_add(1, Ordering::Relaxed);
+            }
+            !excluded
+        })
+        .build();
+
+    for result in walker {
+        let entry = match result {
+            Ok(entry) => entry,
+            Err(e) => {
+                // 符号链接循环、无权限的目录等
+                log_debug!("遍历时跳过: {}", e);
+                continue;
+            }
+        };
+        if !entry.file_type().is_some_and(|file_type| file_type.is_file()) {
+            continue;
+        }
+        let p = entry.path();
+        scanned_files += 1;
+
+        // 检查文件扩展名
+        if !filter.has_text_extension(p) { continue; }
+
+        // 文件未变化时使用缓存的哈希
+        let rel = filter.relative_path(p);
+        let stamp = FileStamp::of(p);
+        if let Some(cached) = stamp.and_then(|stamp| cache.lookup(&rel, stamp)) {
+            if !cached.is_empty() {
+                indexed_files += 1;
+            }
+            out.extend(cached);
+            continue;
+        }
+
+        // 读取文件内容（使用多编码支持，跳过二进制文件）
+        let Some(content) = read_file_with_encoding(p) else {
+            log_debug!("无法读取文件: {:?}", p);
+            continue;
+        };
+        // 按策略跳过的含密钥文件以空 blob 列表记入缓存，文件和策略都未变化时不再重复读取和审计
+        let parts: Vec<HashedBlob> = match filter.secrets.apply(&rel, content, persist) {
+            Some(content) => {
+                let parts: Vec<HashedBlob> = split_content(&rel, &content, chunk_options).into_iter().map(HashedBlob::new).collect();
+                indexed_files += 1;
+                log_important!(info, "索引文件: path={}, content_length={}, blobs={}", rel, content.len(), parts.len());
+                parts
+            }
+            None => Vec::new(),
+        };
+        if let Some(stamp) = stamp.filter(|_| persist) {
+            cache.record(&rel, stamp, &parts, now);
+        }
+        out.extend(parts);
+    }
+    
+    log_important!(info, "文件收集完成: 扫描文件数={}, 索引文件数={}, 命中缓存文件数={}, 生成blobs数={}, 排除文件/目录数={}", scanned_files, indexed_files, cache.hits(), out.len(), excluded_count.load(Ordering::Relaxed));
+    if persist {
+        cache.save();
+    }
+    Ok(out)
+}
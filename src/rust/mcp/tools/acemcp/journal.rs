@@ -0,0 +1,239 @@
+// 项目上传记录
+// 每个项目单独记录服务端已有的 blob 名称和上传失败待重试的 blob，写入临时文件后重命名替换，
+// 进程中断也不会留下写了一半的文件；索引同一项目时持有该项目的文件锁，多个客户端同时检索不会互相覆盖记录。
+// 记录带校验和，损坏时向服务端核对 blob 名称，只重新上传服务端确实没有的 blob
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use super::index::{data_dir, keyed_data_file, sha256_hex};
+use super::upload::UploadQueue;
+use crate::log_important;
+use crate::utils::write_atomic;
+
+/// 上传记录格式版本
+const JOURNAL_VERSION: u32 = 1;
+
+/// 等待其他客户端释放项目锁的最长时间，超时后不加锁继续，记录仍以原子方式写入
+const LOCK_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// 轮询项目锁的间隔
+const LOCK_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// 上传记录文件
+#[derive(Serialize, Deserialize)]
+struct JournalFile {
+    version: u32,
+    root: String,
+    /// 服务端已有的 blob 名称，按字典序排列
+    blob_names: Vec<String>,
+    /// 根目录和 blob 名称的校验和
+    checksum: String,
+    /// 上传失败、下次索引时优先重试的 blob，不计入校验和
+    #[serde(default)]
+    retry_queue: UploadQueue,
+}
+
+/// 读取记录的结果
+#[derive(Debug, PartialEq)]
+pub enum JournalState {
+    /// 记录完整
+    Loaded(HashSet<String>),
+    /// 尚未建立记录
+    Missing,
+    /// 记录损坏，需要向服务端核对
+    Corrupted(String),
+}
+
+/// 单个项目的上传记录，持有期间独占该项目的锁
+pub struct ProjectJournal {
+    root: String,
+    path: PathBuf,
+    state: JournalState,
+    queue: UploadQueue,
+    /// 锁文件，关闭时释放锁
+    _lock: Option<File>,
+}
+
+impl ProjectJournal {
+    /// 获取项目锁并读取记录；没有记录时沿用旧版 projects.json 中该项目的条目
+    pub async fn open(normalized_root: &str) -> Self {
+        let path = journal_file(normalized_root);
+        let lock = acquire_lock(&path.with_extension("lock")).await;
+
+        let (state, queue) = read_project(normalized_root);
+        if let JournalState::Corrupted(reason) = &state {
+            log_important!(warn, "上传记录已损坏，将向服务端核对blob: root={}, 原因={}", normalized_root, reason);
+        }
+
+        Self { root: normalized_root.to_string(), path, state, queue, _lock: lock }
+    }
+
+    pub fn state(&self) -> &JournalState {
+        &self.state
+    }
+
+    /// 项目的重试队列，随 `save` 一起写入
+    pub fn queue_mut(&mut self) -> &mut UploadQueue {
+        &mut self.queue
+    }
+
+    /// 以原子方式写入新的 blob 名称
+    pub fn save(&mut self, blob_names: &[String]) {
+        let mut names = blob_names.to_vec();
+        names.sort();
+        names.dedup();
+        let file = JournalFile {
+            version: JOURNAL_VERSION,
+            root: self.root.clone(),
+            checksum: journal_checksum(&self.root, &names),
+            blob_names: names,
+            retry_queue: self.queue.clone(),
+        };
+
+        match serde_json::to_string_pretty(&file) {
+            Ok(data) => match write_atomic(&self.path, &data) {
+                Ok(()) => self.state = JournalState::Loaded(file.blob_names.into_iter().collect()),
+                Err(e) => log_important!(warn, "保存上传记录失败: {}", e),
+            },
+            Err(e) => log_important!(warn, "序列化上传记录失败: {}", e),
+        }
+    }
+}
+
+/// 不加锁读取项目的上传记录，用于查看索引状态
+pub fn read_state(normalized_root: &str) -> JournalState {
+    read_project(normalized_root).0
+}
+
+/// 不加锁读取项目的重试队列，用于查看索引状态
+pub fn read_queue(normalized_root: &str) -> UploadQueue {
+    read_project(normalized_root).1
+}
+
+/// 读取上传记录和重试队列；没有记录时沿用旧版 projects.json 中该项目的条目
+fn read_project(normalized_root: &str) -> (JournalState, UploadQueue) {
+    match read_journal(&journal_file(normalized_root), normalized_root) {
+        (JournalState::Missing, queue) => {
+            let state = legacy_blob_names(normalized_root).map_or(JournalState::Missing, JournalState::Loaded);
+            (state, queue)
+        }
+        loaded => loaded,
+    }
+}
+
+/// 读取并校验记录，记录损坏时重试队列为空
+fn read_journal(path: &Path, normalized_root: &str) -> (JournalState, UploadQueue) {
+    let data = match fs::read_to_string(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return (JournalState::Missing, UploadQueue::default()),
+        Err(e) => return (JournalState::Corrupted(format!("读取失败: {}", e)), UploadQueue::default()),
+    };
+    let file: JournalFile = match serde_json::from_str(&data) {
+        Ok(file) => file,
+        Err(e) => return (JournalState::Corrupted(format!("解析失败: {}", e)), UploadQueue::default()),
+    };
+
+    let state = if file.version != JOURNAL_VERSION {
+        JournalState::Corrupted(format!("不支持的版本 {}", file.version))
+    } else if file.root != normalized_root {
+        JournalState::Corrupted(format!("根目录不一致: {}", file.root))
+    } else if file.checksum != journal_checksum(&file.root, &file.blob_names) {
+        JournalState::Corrupted("校验和不一致".to_string())
+    } else {
+        return (JournalState::Loaded(file.blob_names.into_iter().collect()), file.retry_queue);
+    };
+    (state, UploadQueue::default())
+}
+
+fn journal_checksum(root: &str, blob_names: &[String]) -> String {
+    sha256_hex(root, &blob_names.join("\n"))
+}
+
+/// 旧版所有项目共用的 projects.json 中该项目的 blob 名称，只读取不再写入
+fn legacy_blob_names(normalized_root: &str) -> Option<HashSet<String>> {
+    let data = fs::read_to_string(data_dir().join("projects.json")).ok()?;
+    let mut projects: HashMap<String, Vec<String>> = serde_json::from_str(&data).ok()?;
+    projects.remove(normalized_root).map(|names| names.into_iter().collect())
+}
+
+/// 获取项目锁，其他客户端持有锁时等待，超时或不支持文件锁时返回 None
+async fn acquire_lock(lock_path: &Path) -> Option<File> {
+    let file = match OpenOptions::new().create(true).truncate(false).write(true).open(lock_path) {
+        Ok(file) => file,
+        Err(e) => {
+            log_important!(warn, "打开项目锁文件失败，不加锁继续: {}", e);
+            return None;
+        }
+    };
+
+    let started = Instant::now();
+    loop {
+        match file.try_lock() {
+            Ok(()) => return Some(file),
+            Err(TryLockError::WouldBlock) if started.elapsed() < LOCK_TIMEOUT => {
+                tokio::time::sleep(LOCK_POLL_INTERVAL).await;
+            }
+            Err(TryLockError::WouldBlock) => {
+                log_important!(warn, "等待项目锁超时，不加锁继续: {}", lock_path.display());
+                return None;
+            }
+            Err(TryLockError::Error(e)) => {
+                log_important!(warn, "获取项目锁失败，不加锁继续: {}", e);
+                return None;
+            }
+        }
+    }
+}
+
+/// 上传记录路径，重试队列保存在同一文件中
+pub fn journal_file(normalized_root: &str) -> PathBuf {
+    keyed_data_file("projects", normalized_root, "json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::tools::acemcp::index::HashedBlob;
+
+    #[tokio::test]
+    async fn test_journal_round_trip_and_corruption() {
+        let root = "/journal-test/project";
+        let _ = fs::remove_file(journal_file(root));
+        let mut journal = ProjectJournal::open(root).await;
+        assert_eq!(journal.state(), &JournalState::Missing);
+        let failed = HashedBlob { hash: "c".to_string(), path: "src/c.rs".to_string(), content: None };
+        journal.queue_mut().record_failure(&[failed], "HTTP 500");
+        journal.save(&["b".to_string(), "a".to_string(), "a".to_string()]);
+        drop(journal);
+
+        let mut journal = ProjectJournal::open(root).await;
+        let expected: HashSet<String> = ["a".to_string(), "b".to_string()].into_iter().collect();
+        assert_eq!(journal.state(), &JournalState::Loaded(expected));
+        assert!(journal.queue_mut().contains("c"));
+        drop(journal);
+
+        // 篡改记录后校验和不一致
+        let path = journal_file(root);
+        let data = fs::read_to_string(&path).unwrap().replace("\"b\"", "\"c\"");
+        fs::write(&path, data).unwrap();
+        assert!(matches!(read_journal(&path, root), (JournalState::Corrupted(_), queue) if queue.is_empty()));
+
+        fs::write(&path, "{\"version\": 1, \"root\"").unwrap();
+        assert!(matches!(read_journal(&path, root).0, JournalState::Corrupted(_)));
+    }
+
+    #[tokio::test]
+    async fn test_journal_lock_is_exclusive() {
+        let root = "/journal-test/locked";
+        let lock_path = journal_file(root).with_extension("lock");
+        let held = ProjectJournal::open(root).await;
+        let other = OpenOptions::new().write(true).open(&lock_path).unwrap();
+        assert!(matches!(other.try_lock(), Err(TryLockError::WouldBlock)));
+        drop(held);
+        assert!(other.try_lock().is_ok());
+    }
+}
@@ -0,0 +1,371 @@
+// 本地离线代码检索
+// 复用文件收集与分块逻辑，在 ~/.acemcp/data/local 下维护每个项目的倒排统计，
+// 使用 BM25 加符号感知分词对代码块排序，输出与远程检索一致的 formatted_retrieval 文本
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use super::chunker::{blob_file_path, blob_line_range};
+use super::doc_index::{load_index, save_index, update_docs, IndexedDoc};
+use super::index::keyed_data_file;
+use super::provider::IndexStats;
+use super::results::{SearchHit, SearchOutput};
+use super::roots::{attribute_hits, combined_key, indexed_roots, IndexRoot};
+use super::scope::SearchScope;
+use super::types::AcemcpConfig;
+use crate::log_important;
+
+/// 本地索引格式版本，分词或存储结构变化时递增以触发重建
+const LOCAL_INDEX_VERSION: u32 = 2;
+
+/// BM25 词频饱和参数
+const BM25_K1: f64 = 1.2;
+
+/// BM25 文档长度归一化参数
+const BM25_B: f64 = 0.75;
+
+/// 路径中的词额外计入的次数，命中文件名的代码块排序更靠前
+const PATH_TERM_WEIGHT: u32 = 3;
+
+/// 默认返回的代码块数量
+pub const MAX_RESULTS: usize = 10;
+
+/// 每个代码片段最多展示的行数
+const SNIPPET_LINES: usize = 40;
+
+/// 本地索引文件
+#[derive(Serialize, Deserialize, Default)]
+struct LocalIndex {
+    version: u32,
+    root: String,
+    docs: Vec<LocalDoc>,
+}
+
+/// 已索引的代码块
+#[derive(Serialize, Deserialize, Clone)]
+struct LocalDoc {
+    /// 与远程上传一致的 blob 哈希
+    hash: String,
+    /// blob 路径，大文件分块时带行号范围后缀
+    path: String,
+    /// 代码块在原文件中的起始行（从 1 开始）
+    start_line: usize,
+    content: String,
+    /// 词频
+    terms: HashMap<String, u32>,
+    /// 词总数
+    length: u32,
+}
+
+/// 更新本地索引并检索
+///
+/// 每个根目录单独维护索引，检索时合并为一个索引统一排序，路径加上根目录的前缀
+pub fn index_and_search_local(
+    config: &AcemcpConfig,
+    roots: &[IndexRoot],
+    query: &str,
+    scope: &SearchScope,
+) -> Result<SearchOutput> {
+    let mut docs = Vec::new();
+    for root in indexed_roots(roots) {
+        docs.extend(update_index(config, root)?.0.docs.into_iter().map(|mut doc| {
+            doc.path = root.merged_path(&doc.path);
+            doc
+        }));
+    }
+    if docs.is_empty() {
+        anyhow::bail!("未在项目中找到可索引的文本文件");
+    }
+
+    let index = LocalIndex { version: LOCAL_INDEX_VERSION, root: combined_key(roots), docs };
+    let hits = search_index(&index, query, scope, scope.max_results.unwrap_or(MAX_RESULTS));
+
+    if hits.is_empty() {
+        log_important!(info, "本地检索无结果: query={}", query);
+    } else {
+        log_important!(info, "本地检索完成: query={}, 命中代码块={}", query, hits.len());
+    }
+    Ok(attribute_hits(SearchOutput::from_hits(hits), roots))
+}
+
+/// 只更新各根目录的本地索引，不检索
+pub fn index_local(config: &AcemcpConfig, roots: &[IndexRoot]) -> Result<Vec<IndexStats>> {
+    indexed_roots(roots).map(|root| update_index(config, root).map(|(_, stats)| stats)).collect()
+}
+
+/// 本地索引中的代码块哈希，尚未建立索引时返回 None
+pub fn local_indexed_hashes(normalized_root: &str) -> Option<HashSet<String>> {
+    let index_path = local_index_file(normalized_root);
+    if !index_path.exists() {
+        return None;
+    }
+    Some(read_index(&index_path, normalized_root).docs.into_iter().map(|doc| doc.hash).collect())
+}
+
+/// 增量更新项目索引：哈希未变的代码块复用已有统计，只为新增代码块分词
+fn update_index(config: &AcemcpConfig, root: &IndexRoot) -> Result<(LocalIndex, IndexStats)> {
+    let index_path = local_index_file(&root.normalized);
+    let mut index = read_index(&index_path, &root.normalized);
+    let previous_count = index.docs.len();
+
+    let (added, removed) = update_docs(config, root, &mut index.docs, |blob| {
+        let start_line = blob_line_range(&blob.path).map_or(1, |(start, _)| start);
+        build_doc(blob.hash, blob.path, start_line, blob.content.unwrap_or_default())
+    })?;
+
+    log_important!(
+        info,
+        "本地索引更新: 根目录={}, 代码块总数={}, 新增={}, 删除={}, 原有={}",
+        root.normalized,
+        index.docs.len(),
+        added,
+        removed,
+        previous_count
+    );
+
+    if added > 0 || removed > 0 {
+        save_index(&index_path, &index, "本地索引");
+    }
+
+    let stats = IndexStats { root: root.path.clone(), blobs: index.docs.len(), added, failed: 0 };
+    Ok((index, stats))
+}
+
+/// 读取本地索引，不存在、损坏或版本不一致时返回空索引
+fn read_index(index_path: &Path, normalized_root: &str) -> LocalIndex {
+    load_index(index_path, "本地索引", |index: &LocalIndex| index.version == LOCAL_INDEX_VERSION).unwrap_or_else(|| {
+        LocalIndex { version: LOCAL_INDEX_VERSION, root: normalized_root.to_string(), docs: Vec::new() }
+    })
+}
+
+/// 本地索引文件路径
+pub fn local_index_file(normalized_root: &str) -> PathBuf {
+    keyed_data_file("local", normalized_root, "json")
+}
+
+impl IndexedDoc for LocalDoc {
+    fn hash(&self) -> &str {
+        &self.hash
+    }
+}
+
+fn build_doc(hash: String, path: String, start_line: usize, content: String) -> LocalDoc {
+    let mut terms: HashMap<String, u32> = HashMap::new();
+    for term in tokenize_code(&content) {
+        *terms.entry(term).or_default() += 1;
+    }
+    for term in tokenize_code(blob_file_path(&path)) {
+        *terms.entry(term).or_default() += PATH_TERM_WEIGHT;
+    }
+    let length = terms.values().sum();
+
+    LocalDoc { hash, path, start_line, content, terms, length }
+}
+
+/// 符号感知分词
+///
+/// 标识符保留完整形式，同时按 snake_case、camelCase 拆出子词；
+/// 中日韩文字按相邻两字切分。所有词转为小写，忽略单个字母
+pub fn tokenize_code(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut identifier = String::new();
+    let mut cjk_run: Vec<char> = Vec::new();
+
+    for ch in text.chars() {
+        if is_cjk(ch) {
+            push_identifier(&mut identifier, &mut tokens);
+            cjk_run.push(ch);
+        } else if ch.is_alphanumeric() || ch == '_' {
+            push_cjk(&mut cjk_run, &mut tokens);
+            identifier.push(ch);
+        } else {
+            push_identifier(&mut identifier, &mut tokens);
+            push_cjk(&mut cjk_run, &mut tokens);
+        }
+    }
+    push_identifier(&mut identifier, &mut tokens);
+    push_cjk(&mut cjk_run, &mut tokens);
+
+    tokens
+}
+
+fn push_identifier(identifier: &mut String, tokens: &mut Vec<String>) {
+    if identifier.is_empty() {
+        return;
+    }
+
+    let parts = split_identifier(identifier);
+    let full = identifier.to_lowercase();
+    identifier.clear();
+
+    if full.chars().count() < 2 {
+        return;
+    }
+    if parts.len() > 1 {
+        tokens.extend(parts.into_iter().filter(|part| part.chars().count() >= 2));
+    }
+    tokens.push(full);
+}
+
+/// 按下划线和大小写边界拆分标识符，如 `parseHTTPRequest_v2` → parse、http、request、v2
+fn split_identifier(identifier: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+
+    for segment in identifier.split('_').filter(|segment| !segment.is_empty()) {
+        let chars: Vec<char> = segment.chars().collect();
+        let mut current = String::new();
+        for (i, &ch) in chars.iter().enumerate() {
+            let boundary = i > 0 && ch.is_uppercase() && {
+                let prev = chars[i - 1];
+                let next_is_lower = chars.get(i + 1).is_some_and(|next| next.is_lowercase());
+                prev.is_lowercase() || prev.is_ascii_digit() || (prev.is_uppercase() && next_is_lower)
+            };
+            if boundary && !current.is_empty() {
+                parts.push(current.to_lowercase());
+                current.clear();
+            }
+            current.push(ch);
+        }
+        if !current.is_empty() {
+            parts.push(current.to_lowercase());
+        }
+    }
+
+    parts
+}
+
+fn push_cjk(run: &mut Vec<char>, tokens: &mut Vec<String>) {
+    match run.len() {
+        0 => {}
+        1 => tokens.push(run[0].to_string()),
+        _ => tokens.extend(run.windows(2).map(|pair| pair.iter().collect())),
+    }
+    run.clear();
+}
+
+fn is_cjk(ch: char) -> bool {
+    matches!(ch as u32, 0x4E00..=0x9FFF | 0x3400..=0x4DBF | 0x3040..=0x30FF | 0xAC00..=0xD7AF)
+}
+
+/// 用 BM25 对检索范围内的代码块排序并截取最相关的片段
+fn search_index(index: &LocalIndex, query: &str, scope: &SearchScope, limit: usize) -> Vec<SearchHit> {
+    let query_terms: HashSet<String> = tokenize_code(query).into_iter().collect();
+    if query_terms.is_empty() || index.docs.is_empty() {
+        return Vec::new();
+    }
+
+    let total_docs = index.docs.len() as f64;
+    let avg_len = (index.docs.iter().map(|doc| doc.length as f64).sum::<f64>() / total_docs).max(1.0);
+
+    let idf: HashMap<&str, f64> = query_terms
+        .iter()
+        .map(|term| {
+            let df = index.docs.iter().filter(|doc| doc.terms.contains_key(term)).count() as f64;
+            (term.as_str(), (1.0 + (total_docs - df + 0.5) / (df + 0.5)).ln())
+        })
+        .collect();
+
+    // 词频统计使用整个项目，只对范围内的代码块打分
+    let mut scored: Vec<(&LocalDoc, f64)> = index
+        .docs
+        .iter()
+        .filter(|doc| scope.matches(&doc.path))
+        .map(|doc| {
+            let norm = BM25_K1 * (1.0 - BM25_B + BM25_B * doc.length as f64 / avg_len);
+            let score = query_terms
+                .iter()
+                .filter_map(|term| {
+                    let tf = *doc.terms.get(term)? as f64;
+                    Some(idf[term.as_str()] * tf * (BM25_K1 + 1.0) / (tf + norm))
+                })
+                .sum::<f64>();
+            (doc, score)
+        })
+        .filter(|(_, score)| *score > 0.0)
+        .collect();
+
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.path.cmp(&b.0.path)));
+    scored.truncate(limit);
+
+    scored
+        .into_iter()
+        .map(|(doc, score)| best_snippet(&doc.path, doc.start_line, &doc.content, score, &query_terms))
+        .collect()
+}
+
+/// 选取查询词最密集的连续行作为片段，没有查询词出现时取代码块开头
+pub fn best_snippet(blob_path: &str, start_line: usize, content: &str, score: f64, query_terms: &HashSet<String>) -> SearchHit {
+    let lines: Vec<&str> = content.lines().collect();
+    let hits: Vec<usize> = lines
+        .iter()
+        .map(|line| tokenize_code(line).iter().filter(|term| query_terms.contains(*term)).count())
+        .collect();
+
+    let window = SNIPPET_LINES.min(lines.len());
+    let mut best_start = 0;
+    if window > 0 {
+        let mut current: usize = hits[..window].iter().sum();
+        let mut best = current;
+        for start in 1..=(lines.len() - window) {
+            current = current + hits[start + window - 1] - hits[start - 1];
+            if current > best {
+                best = current;
+                best_start = start;
+            }
+        }
+    }
+
+    let start_line = start_line + best_start;
+    SearchHit {
+        path: blob_file_path(blob_path).to_string(),
+        root: None,
+        start_line: Some(start_line),
+        end_line: Some(start_line + window.saturating_sub(1)),
+        score: Some(score),
+        snippet: lines[best_start..best_start + window].join("\n"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::tools::acemcp::types::AcemcpRequest;
+
+    #[test]
+    fn test_tokenize_code() {
+        let tokens = tokenize_code("fn parseHTTPRequest_v2(max_lines: usize) // 解析请求");
+        for expected in ["fn", "parse", "http", "request", "v2", "parsehttprequest_v2", "max", "lines", "max_lines", "usize", "解析", "请求"] {
+            assert!(tokens.contains(&expected.to_string()), "缺少 {}", expected);
+        }
+        assert!(!tokens.contains(&"v".to_string()));
+    }
+
+    #[test]
+    fn test_search_index() {
+        let docs = vec![
+            build_doc("a".into(), "src/telegram/bot.rs".into(), 1, "fn send_message() {}\nfn other() {}".into()),
+            build_doc("b".into(), "src/config.rs".into(), 1, "fn load_config() {}\nlet timeout = 5;".into()),
+            build_doc("c".into(), "src/popup.rs".into(), 1, "fn create_popup() {}\n// timeout handling".into()),
+        ];
+        let index = LocalIndex { version: LOCAL_INDEX_VERSION, root: "/p".into(), docs };
+        let all = SearchScope::default();
+
+        let results = search_index(&index, "telegram sendMessage", &all, 5);
+        assert_eq!(results[0].path, "src/telegram/bot.rs");
+
+        let results = search_index(&index, "timeout", &all, 5);
+        assert_eq!(results.len(), 2);
+        assert!(search_index(&index, "database", &all, 5).is_empty());
+
+        let config_only = SearchScope::from_request(&AcemcpRequest {
+            include_globs: Some(vec!["src/config.rs".to_string()]),
+            ..Default::default()
+        })
+        .unwrap();
+        let results = search_index(&index, "timeout", &config_only, 5);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].path, "src/config.rs");
+    }
+}
@@ -1,22 +1,26 @@
 use anyhow::Result;
 use rmcp::{model::*, Error as McpError};
 use std::borrow::Cow;
-use std::collections::HashMap;
-use std::fs;
-use std::io::Read;
-use std::path::{Path, PathBuf};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
 use reqwest::header::{AUTHORIZATION, CONTENT_TYPE};
 use reqwest::Client;
-use ring::digest::{Context as ShaContext, SHA256};
-use ignore::gitignore::{Gitignore, GitignoreBuilder};
-use serde::{Deserialize, Serialize};
-use encoding_rs::{GBK, WINDOWS_1252, UTF_8};
-use globset::{Glob, GlobSet, GlobSetBuilder};
 
+use super::checkpoint::{checkpoint_id_from_response, is_checkpoint_rejected, BlobDelta, Checkpoint};
+use super::chunker::blob_file_path;
+use super::index::{load_contents, HashedBlob};
+use super::journal::{JournalState, ProjectJournal};
+use super::provider::{provider_for, IndexStats};
+use super::results::SearchOutput;
+use super::roots::{attribute_hits, combined_key, indexed_roots, resolve_roots, IndexRoot};
+use super::scope::SearchScope;
 use super::types::{AcemcpRequest, AcemcpConfig};
+use super::upload::{upload_blobs, UploadLimits};
+use super::watcher::current_blobs;
+use crate::constants::mcp::ACEMCP_CHECKPOINT_DELTA_THRESHOLD;
 use crate::log_debug;
 use crate::log_important;
 
@@ -32,37 +36,72 @@ impl AcemcpTool {
         );
 
         // 读取配置
-        let mut acemcp_config = Self::get_acemcp_config()
+        let acemcp_config = Self::get_acemcp_config()
             .await
             .map_err(|e| McpError::internal_error(format!("获取acemcp配置失败: {}", e), None))?;
 
-        // 规范化 base_url（缺协议时补 http://），并去除末尾斜杠
-        if let Some(base) = &acemcp_config.base_url {
-            let normalized = normalize_base_url(base);
-            acemcp_config.base_url = Some(normalized);
-        }
+        let scope = SearchScope::from_request(&request)
+            .map_err(|e| McpError::invalid_params(format!("检索范围参数无效: {}", e), None))?;
+        let roots = resolve_roots(&request, &acemcp_config)
+            .map_err(|e| McpError::invalid_params(format!("根目录参数无效: {}", e), None))?;
 
-        // 执行：增量索引（含批量上传）+ 检索
-        match index_and_search(&acemcp_config, &request.project_root_path, &request.query).await {
-            Ok(text) => Ok(CallToolResult { content: vec![Content::text(text)], is_error: None }),
+        // 执行：由配置选择的检索后端完成增量索引与检索
+        let provider = provider_for(acemcp_config);
+        log_debug!("代码搜索后端: {}", provider.name());
+        let result = provider.search(&roots, &request.query, &scope).await;
+
+        match result {
+            Ok(output) if request.structured.unwrap_or(false) => {
+                Ok(CallToolResult { content: Self::structured_contents(output), is_error: None })
+            }
+            Ok(output) => Ok(CallToolResult { content: vec![Content::text(output.text)], is_error: None }),
             Err(e) => Ok(CallToolResult { content: vec![Content::text(format!("Acemcp执行失败: {}", e))], is_error: Some(true) })
         }
     }
 
-    /// 获取acemcp配置
-    async fn get_acemcp_config() -> Result<AcemcpConfig> {
+    /// 每个命中片段作为一个内容项，内容为片段的 JSON；无法拆分出片段时返回原始文本
+    fn structured_contents(output: SearchOutput) -> Vec<Content> {
+        if output.hits.is_empty() {
+            return vec![Content::text(output.text)];
+        }
+        output
+            .hits
+            .iter()
+            .map(|hit| Content::text(serde_json::to_string(hit).unwrap_or_default()))
+            .collect()
+    }
+
+    /// 获取acemcp配置，端点 URL 已规范化（缺协议时补 http://，并去除末尾斜杠）
+    pub async fn get_acemcp_config() -> Result<AcemcpConfig> {
         // 从配置文件中读取acemcp配置
         let config = crate::config::load_standalone_config()
             .map_err(|e| anyhow::anyhow!("读取配置文件失败: {}", e))?;
         
-        Ok(AcemcpConfig {
+        let mut acemcp_config = AcemcpConfig {
             base_url: config.mcp_config.acemcp_base_url,
             token: config.mcp_config.acemcp_token,
             batch_size: config.mcp_config.acemcp_batch_size,
             max_lines_per_blob: config.mcp_config.acemcp_max_lines_per_blob,
+            chunk_overlap_lines: config.mcp_config.acemcp_chunk_overlap_lines,
             text_extensions: config.mcp_config.acemcp_text_extensions,
             exclude_patterns: config.mcp_config.acemcp_exclude_patterns,
-        })
+            backend: config.mcp_config.acemcp_backend,
+            watch: config.mcp_config.acemcp_watch,
+            upload_concurrency: config.mcp_config.acemcp_upload_concurrency,
+            max_batch_bytes: config.mcp_config.acemcp_max_batch_bytes,
+            embedding_base_url: config.mcp_config.acemcp_embedding_base_url,
+            embedding_token: config.mcp_config.acemcp_embedding_token,
+            embedding_model: config.mcp_config.acemcp_embedding_model,
+            secret_mode: config.mcp_config.acemcp_secret_mode,
+        };
+
+        if let Some(base) = &acemcp_config.base_url {
+            acemcp_config.base_url = Some(normalize_base_url(base));
+        }
+        if let Some(base) = &acemcp_config.embedding_base_url {
+            acemcp_config.embedding_base_url = Some(normalize_base_url(base));
+        }
+        Ok(acemcp_config)
     }
 
     /// 获取工具定义
@@ -82,6 +121,39 @@ impl AcemcpTool {
                 "query": {
                     "type": "string",
                     "description": "用于搜索相关代码的自然语言查询。执行语义搜索并返回与目标匹配的代码片段。"
+                },
+                "structured": {
+                    "type": "boolean",
+                    "description": "为 true 时按代码片段返回多个内容项，每项为包含 path、start_line、end_line、score、snippet 的 JSON，便于引用或打开准确位置；默认返回文本"
+                },
+                "include_globs": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "只检索匹配这些 glob 的文件或目录，路径相对项目根目录。例如：[\"src/rust/telegram\", \"src/**/*.vue\"]"
+                },
+                "exclude_globs": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "不检索匹配这些 glob 的文件或目录，路径相对项目根目录。例如：[\"**/tests/**\"]"
+                },
+                "languages": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "只检索这些语言的文件，可以是语言名称或扩展名。例如：[\"rust\", \"typescript\", \".vue\"]"
+                },
+                "max_results": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "最多返回的代码片段数"
+                },
+                "additional_roots": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "与 project_root_path 一起检索的其他根目录的绝对路径，如同一 monorepo 中的其他 git 仓库。每个根目录单独索引，结果中标注片段所属的根目录"
+                },
+                "discover_workspace": {
+                    "type": "boolean",
+                    "description": "为 true 时自动发现项目中的嵌套 git 仓库、子模块和 Cargo 工作区成员，分别索引后合并检索"
                 }
             },
             "required": ["project_root_path", "query"]
@@ -104,14 +176,94 @@ impl AcemcpTool {
 
 // ---------------- 整合 temp 逻辑：索引、上传、检索 ----------------
 
-#[derive(Serialize, Deserialize, Clone)]
-struct BlobItem {
-    path: String,
-    content: String,
+/// 核对 blob 名称时每次请求包含的名称数
+const FIND_MISSING_BATCH_SIZE: usize = 1000;
+
+/// 服务端不支持建立检查点时不再尝试
+static CHECKPOINT_UNSUPPORTED: AtomicBool = AtomicBool::new(false);
+
+/// 发送 POST 请求并解析 JSON 响应，非 2xx 状态返回 `HTTP {状态} {响应体}` 错误
+pub async fn post_json(client: &Client, url: &str, token: &str, payload: &serde_json::Value) -> anyhow::Result<serde_json::Value> {
+    let r = client
+        .post(url)
+        .header(AUTHORIZATION, format!("Bearer {}", token))
+        .header(CONTENT_TYPE, "application/json")
+        .json(payload)
+        .send()
+        .await?;
+
+    let status = r.status();
+    log_important!(info, "HTTP响应状态: url={}, status={}", url, status);
+
+    if !status.is_success() {
+        let body = r.text().await.unwrap_or_default();
+        anyhow::bail!("HTTP {} {}", status, body);
+    }
+
+    Ok(r.json().await?)
+}
+
+/// 发起代码检索
+async fn retrieve(client: &Client, search_url: &str, token: &str, query: &str, delta: &BlobDelta) -> anyhow::Result<serde_json::Value> {
+    let payload = serde_json::json!({
+        "information_request": query,
+        "blobs": delta.to_json(),
+        "dialog": [],
+        "max_output_length": 0,
+        "disable_codebase_retrieval": false,
+        "enable_commit_retrieval": false,
+    });
+
+    log_important!(info, "检索载荷大小: {} 字节", payload.to_string().len());
+
+    let value = retry_request(|| post_json(client, search_url, token, &payload), 3, 2.0).await?;
+    log_important!(info, "检索响应数据: {}", serde_json::to_string_pretty(&value).unwrap_or_default());
+    Ok(value)
+}
+
+/// 请求服务端为当前 blob 集合建立检查点
+async fn create_checkpoint(client: &Client, base_url: &str, token: &str, delta: &BlobDelta) -> anyhow::Result<Option<String>> {
+    let url = format!("{}/checkpoint-blobs", base_url);
+    let payload = serde_json::json!({ "blobs": delta.to_json() });
+
+    match retry_request(|| post_json(client, &url, token, &payload), 3, 1.0).await {
+        Ok(value) => Ok(checkpoint_id_from_response(&value)),
+        Err(e) => {
+            let error = e.to_string();
+            if error.starts_with("HTTP 404") || error.starts_with("HTTP 405") {
+                log_important!(info, "服务端不支持建立检查点，后续不再尝试");
+                CHECKPOINT_UNSUPPORTED.store(true, Ordering::Relaxed);
+            }
+            Err(e)
+        }
+    }
 }
 
-#[derive(Serialize, Deserialize, Default)]
-struct ProjectsFile(HashMap<String, Vec<String>>);
+/// 向服务端核对 blob 名称，返回服务端已有的部分；核对失败时返回空集合，全部作为新增重新上传
+async fn verify_blob_names(target: &RemoteTarget, blob_names: Vec<String>) -> std::collections::HashSet<String> {
+    let url = format!("{}/find-missing", target.base_url);
+    let mut known = std::collections::HashSet::new();
+
+    for chunk in blob_names.chunks(FIND_MISSING_BATCH_SIZE) {
+        let payload = serde_json::json!({ "model": "", "mem_object_names": chunk });
+        let value = match retry_request(|| post_json(&target.client, &url, &target.token, &payload), 3, 1.0).await {
+            Ok(value) => value,
+            Err(e) => {
+                log_important!(warn, "核对blob失败，重新上传全部blob: {}", e);
+                return std::collections::HashSet::new();
+            }
+        };
+        let Some(unknown) = value.get("unknown_memory_names").and_then(|v| v.as_array()) else {
+            log_important!(warn, "核对响应中缺少unknown_memory_names，重新上传全部blob");
+            return std::collections::HashSet::new();
+        };
+        let unknown: std::collections::HashSet<&str> = unknown.iter().filter_map(|v| v.as_str()).collect();
+        known.extend(chunk.iter().filter(|name| !unknown.contains(name.as_str())).cloned());
+    }
+
+    log_important!(info, "核对blob完成: 服务端已有 {}/{}", known.len(), blob_names.len());
+    known
+}
 
 fn normalize_base_url(input: &str) -> String {
     let mut url = input.trim().to_string();
@@ -122,7 +274,7 @@ fn normalize_base_url(input: &str) -> String {
     url
 }
 
-async fn retry_request<F, Fut, T>(mut f: F, max_retries: usize, base_delay_secs: f64) -> anyhow::Result<T>
+pub async fn retry_request<F, Fut, T>(mut f: F, max_retries: usize, base_delay_secs: f64) -> anyhow::Result<T>
 where
     F: FnMut() -> Fut,
     Fut: std::future::Future<Output = anyhow::Result<T>>,
@@ -167,230 +319,151 @@ where
         .unwrap_or_else(|| anyhow::anyhow!("未知错误")))
 }
 
-fn home_projects_file() -> PathBuf {
-    let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
-    let data_dir = home.join(".acemcp").join("data");
-    let _ = fs::create_dir_all(&data_dir);
-    data_dir.join("projects.json")
+/// 上传与检索使用的服务端连接
+struct RemoteTarget {
+    client: Client,
+    base_url: String,
+    token: String,
+    limits: UploadLimits,
 }
 
-/// 读取文件内容，支持多种编码检测
-/// 尝试的编码顺序：utf-8, gbk (包含 gb2312), windows-1252 (包含 latin-1)
-/// 如果都失败，则使用 utf-8 with errors='ignore'
-fn read_file_with_encoding(path: &Path) -> Option<String> {
-    let mut file = fs::File::open(path).ok()?;
-    let mut buf = Vec::new();
-    if file.read_to_end(&mut buf).is_err() {
-        return None;
-    }
-
-    // 尝试 utf-8
-    let (decoded, _, had_errors) = UTF_8.decode(&buf);
-    if !had_errors {
-        return Some(decoded.into_owned());
-    }
+/// 单个根目录的索引结果
+struct RootIndex {
+    /// 服务端已有的 blob 名称
+    blob_names: Vec<String>,
+    /// blob 哈希 → blob
+    blobs: HashMap<String, HashedBlob>,
+    /// 本次上传成功的 blob 数量
+    uploaded: usize,
+    /// 本次上传失败、已加入重试队列的 blob 数量
+    failed: usize,
+}
 
-    // 尝试 gbk
-    let (decoded, _, had_errors) = GBK.decode(&buf);
-    if !had_errors {
-        log_debug!("成功使用 GBK 编码读取文件: {:?}", path);
-        return Some(decoded.into_owned());
+/// 收集单个根目录的 blob 并上传新增部分，更新该根目录的上传记录
+async fn sync_root(
+    config: &AcemcpConfig,
+    target: &RemoteTarget,
+    root: &IndexRoot,
+    scope: &SearchScope,
+    journal: &mut ProjectJournal,
+) -> anyhow::Result<RootIndex> {
+    // 收集 blob（根据扩展名与排除规则，简化版 .gitignore 支持），已在后台监听的项目直接使用内存索引
+    log_important!(info, "开始收集代码文件: {}", root.path);
+    let blobs = current_blobs(config, root)?;
+    if blobs.is_empty() {
+        log_important!(info, "根目录中没有可索引的文本文件: {}", root.path);
+        return Ok(RootIndex { blob_names: Vec::new(), blobs: HashMap::new(), uploaded: 0, failed: 0 });
     }
 
-    // 尝试 gb2312 (GBK 是 GB2312 的超集，可以处理 GB2312 编码)
-    // encoding_rs 中没有单独的 GB2312，使用 GBK 代替
-    // GBK 已经在上一步尝试过了，这里跳过
 
-    // 尝试 latin-1 (WINDOWS_1252 是 ISO-8859-1 的超集，可以处理大部分 latin-1 编码)
-    let (decoded, _, had_errors) = WINDOWS_1252.decode(&buf);
-    if !had_errors {
-        log_debug!("成功使用 WINDOWS_1252 编码读取文件: {:?}", path);
-        return Some(decoded.into_owned());
-    }
-
-    // 如果所有编码都失败，使用 utf-8 with errors='ignore' (lossy 解码)
-    let (decoded, _, _) = UTF_8.decode(&buf);
-    log_debug!("使用 UTF-8 (lossy) 读取文件，部分字符可能丢失: {:?}", path);
-    Some(decoded.into_owned())
-}
+    // 建立哈希到 blob 的映射
+    let blobs_count = blobs.len();
+    let blob_hash_map: std::collections::HashMap<String, HashedBlob> = blobs.into_iter().map(|hashed| (hashed.hash.clone(), hashed)).collect();
 
-fn sha256_hex(path: &str, content: &str) -> String {
-    let mut ctx = ShaContext::new(&SHA256);
-    // 先更新路径的哈希，再更新内容的哈希，与Python版本保持一致
-    ctx.update(path.as_bytes());
-    ctx.update(content.as_bytes());
-    let digest = ctx.finish();
-    hex::encode(digest.as_ref())
-}
+    // 上传记录损坏时向服务端核对，只把服务端没有的 blob 作为新增
+    let existing_blob_names: std::collections::HashSet<String> = match journal.state() {
+        JournalState::Loaded(names) => names.clone(),
+        JournalState::Missing => std::collections::HashSet::new(),
+        JournalState::Corrupted(_) => verify_blob_names(target, blob_hash_map.keys().cloned().collect()).await,
+    };
 
-/// 分割文件内容为多个 blob（如果超过最大行数）
-/// 与 Python 版本保持一致：chunk 索引从 1 开始
-fn split_content(path: &str, content: &str, max_lines: usize) -> Vec<BlobItem> {
-    let lines: Vec<&str> = content.split_inclusive('\n').collect();
-    let total_lines = lines.len();
-    
-    // 如果文件在限制内，返回单个 blob
-    if total_lines <= max_lines {
-        return vec![BlobItem { path: path.to_string(), content: content.to_string() }];
-    }
+    // 分离已存在和新增加的 blob（与 Python 版本保持一致）
+    let all_blob_hashes: std::collections::HashSet<String> = blob_hash_map.keys().cloned().collect();
+    let existing_hashes: std::collections::HashSet<String> = all_blob_hashes.intersection(&existing_blob_names).cloned().collect();
+    let new_hashes: std::collections::HashSet<String> = all_blob_hashes.difference(&existing_blob_names).cloned().collect();
 
-    // 计算需要的 chunk 数量
-    let num_chunks = (total_lines + max_lines - 1) / max_lines;
-    let mut blobs = Vec::new();
+    // 需要上传的新 blob，重试队列中的 blob 优先上传
+    // 来自缓存的 blob 不含内容，只为需要上传的 blob 读取文件
+    // 限定检索范围时只上传范围内的新 blob，其余留待之后的检索上传
+    journal.queue_mut().retain_current(&all_blob_hashes);
+    let upload_queue = journal.queue_mut();
+    let mut new_blobs: Vec<HashedBlob> = load_contents(
+        config,
+        root,
+        new_hashes
+            .iter()
+            .filter_map(|h| blob_hash_map.get(h).cloned())
+            .filter(|blob| scope.matches(&root.merged_path(&blob.path)))
+            .collect(),
+    );
+    new_blobs.sort_by_key(|blob| !upload_queue.contains(&blob.hash));
 
-    // 按 chunk 索引分割（从 0 开始，但显示时从 1 开始）
-    for chunk_idx in 0..num_chunks {
-        let start_line = chunk_idx * max_lines;
-        let end_line = usize::min(start_line + max_lines, total_lines);
-        let chunk_lines = &lines[start_line..end_line];
-        let chunk_content = chunk_lines.join("");
+    log_important!(info,
+        "=== 索引统计 ==="
+    );
+    log_important!(info,
+        "收集到blobs总数: {}, 既有blobs: {}, 新增blobs: {}, 需要上传: {}, 其中待重试: {}",
+        blobs_count,
+        existing_hashes.len(),
+        new_hashes.len(),
+        new_blobs.len(),
+        upload_queue.len()
+    );
 
-        // chunk 编号从 1 开始（与 Python 版本保持一致）
-        let chunk_path = format!("{}#chunk{}of{}", path, chunk_idx + 1, num_chunks);
-        blobs.push(BlobItem { path: chunk_path, content: chunk_content });
-    }
+    // 并发批量上传新增 blobs
+    let (uploaded_names, failed): (Vec<String>, usize) = if !new_blobs.is_empty() {
+        log_important!(info,
+            "=== 开始批量上传代码索引 ==="
+        );
+        let outcome = upload_blobs(&target.client, &target.base_url, &target.token, new_blobs, target.limits).await;
 
-    blobs
-}
+        // 成功的 blob 移出重试队列，失败的 blob 入队等待下次检索时重新上传
+        upload_queue.remove(&outcome.uploaded_hashes);
+        for (batch, error) in &outcome.failed_batches {
+            upload_queue.record_failure(batch, error);
+        }
 
-/// 构建排除模式的 GlobSet
-fn build_exclude_globset(exclude_patterns: &[String]) -> Result<GlobSet> {
-    let mut builder = GlobSetBuilder::new();
-    for pattern in exclude_patterns {
-        // 尝试将模式转换为 Glob
-        if let Ok(glob) = Glob::new(pattern) {
-            builder.add(glob);
+        // 上传结果总结
+        log_important!(info,
+            "=== 上传结果总结 ==="
+        );
+        if !outcome.failed_batches.is_empty() {
+            log_important!(info,
+                "上传完成，但有 {} 个批次失败，{} 个blobs已加入重试队列, 成功上传blobs: {}",
+                outcome.failed_batches.len(),
+                outcome.failed_count(),
+                outcome.uploaded_names.len()
+            );
         } else {
-            log_debug!("无效的排除模式，跳过: {}", pattern);
+            log_important!(info, "所有批次上传成功，共上传 {} 个blobs", outcome.uploaded_names.len());
         }
-    }
-    builder.build().map_err(|e| anyhow::anyhow!("构建排除模式失败: {}", e))
-}
-
-/// 检查路径是否应该被排除
-/// 使用 globset 进行完整的 fnmatch 模式匹配（与 Python 版本保持一致）
-/// Python 版本使用 fnmatch.fnmatch 检查路径的各个部分和完整路径
-fn should_exclude(path: &Path, root: &Path, exclude_globset: Option<&GlobSet>) -> bool {
-    if exclude_globset.is_none() {
-        return false;
-    }
-    let globset = exclude_globset.unwrap();
-
-    // 获取相对路径
-    let rel = match path.strip_prefix(root) {
-        Ok(rel) => rel,
-        Err(_) => path,
+        let failed = outcome.failed_count();
+        (outcome.uploaded_names, failed)
+    } else {
+        log_important!(info, "没有新的blob需要上传，使用已有索引");
+        (Vec::new(), 0)
     };
+    let uploaded = uploaded_names.len();
 
-    // 转换为使用正斜杠的字符串（用于匹配）
-    let rel_forward = rel.to_string_lossy().replace('\\', "/");
-    
-    // 检查完整相对路径（与 Python 版本的 fnmatch(path_str, pattern) 一致）
-    if globset.is_match(&rel_forward) {
-        return true;
-    }
-
-    // 检查路径的各个部分（与 Python 版本的 fnmatch(part, pattern) 一致）
-    for part in rel.iter() {
-        if let Some(part_str) = part.to_str() {
-            if globset.is_match(part_str) {
-                return true;
-            }
-        }
-    }
-
-    false
-}
+    // 更新上传记录（与 Python 版本保持一致）
+    // 只保留当前项目中仍然存在的 blob 的哈希值（自动删除已删除的 blob）
+    let all_blob_names: Vec<String> = existing_hashes.into_iter().chain(uploaded_names.into_iter()).collect();
+    journal.save(&all_blob_names);
 
-fn build_gitignore(root: &Path) -> Option<Gitignore> {
-    let mut builder = GitignoreBuilder::new(root);
-    let gi_path = root.join(".gitignore");
-    if gi_path.exists() {
-        if builder.add(gi_path).is_some() { return None; }
-        return match builder.build() { Ok(gi) => Some(gi), Err(_) => None };
-    }
-    None
+    Ok(RootIndex { blob_names: all_blob_names, blobs: blob_hash_map, uploaded, failed })
 }
 
-fn collect_blobs(root: &str, text_exts: &[String], exclude_patterns: &[String], max_lines_per_blob: usize) -> anyhow::Result<Vec<BlobItem>> {
-    let root_path = PathBuf::from(root);
-    if !root_path.exists() { anyhow::bail!("项目根目录不存在: {}", root); }
-    
-    log_important!(info, "开始收集代码文件: 根目录={}, 扩展名={:?}, 排除模式={:?}", root, text_exts, exclude_patterns);
-    
-    // 构建排除模式的 GlobSet
-    let exclude_globset = if exclude_patterns.is_empty() {
-        None
-    } else {
-        match build_exclude_globset(exclude_patterns) {
-            Ok(gs) => Some(gs),
-            Err(e) => {
-                log_debug!("构建排除模式失败，将使用简单匹配: {}", e);
-                None
-            }
-        }
-    };
-    
-    let mut out = Vec::new();
-    let gitignore = build_gitignore(&root_path);
-    let mut dirs_stack = vec![root_path.clone()];
-    let mut scanned_files = 0;
-    let mut indexed_files = 0;
-    let mut excluded_count = 0;
-    
-    while let Some(dir) = dirs_stack.pop() {
-        let entries = match fs::read_dir(&dir) { Ok(e) => e, Err(_) => continue };
-        for entry in entries.flatten() {
-            let p = entry.path();
-            
-            // 检查 .gitignore
-            if let Some(gi) = &gitignore {
-                if gi.matched_path_or_any_parents(&p, p.is_dir()).is_ignore() { continue; }
-            }
-            
-            // 检查排除模式
-            if p.is_dir() {
-                if should_exclude(&p, &root_path, exclude_globset.as_ref()) {
-                    excluded_count += 1;
-                    continue;
-                }
-                dirs_stack.push(p);
-                continue;
-            }
-            
-            scanned_files += 1;
-            if should_exclude(&p, &root_path, exclude_globset.as_ref()) {
-                excluded_count += 1;
-                log_debug!("排除文件: {:?}", p);
-                continue;
-            }
-            
-            // 检查文件扩展名
-            let ext_ok = p.extension().and_then(|s| s.to_str()).map(|e| {
-                let dot = format!(".{}", e).to_lowercase();
-                text_exts.iter().any(|te| te.eq_ignore_ascii_case(&dot))
-            }).unwrap_or(false);
-            if !ext_ok { continue; }
-            
-            // 读取文件内容（使用多编码支持）
-            let rel = p.strip_prefix(&root_path).unwrap_or(&p).to_string_lossy().replace('\\', "/");
-            if let Some(content) = read_file_with_encoding(&p) {
-                let parts = split_content(&rel, &content, max_lines_per_blob);
-                let blob_count = parts.len();
-                indexed_files += 1;
-                out.extend(parts);
-                log_important!(info, "索引文件: path={}, content_length={}, blobs={}", rel, content.len(), blob_count);
-            } else {
-                log_debug!("无法读取文件: {:?}", p);
-            }
-        }
+/// 只增量上传各根目录的 blob，不检索
+pub async fn index_remote(config: &AcemcpConfig, roots: &[IndexRoot]) -> anyhow::Result<Vec<IndexStats>> {
+    let target = remote_target(config, roots)?;
+    let scope = SearchScope::default();
+
+    let mut stats = Vec::new();
+    for root in indexed_roots(roots) {
+        let mut journal = ProjectJournal::open(&root.normalized).await;
+        let indexed = sync_root(config, &target, root, &scope, &mut journal).await?;
+        stats.push(IndexStats {
+            root: root.path.clone(),
+            blobs: indexed.blobs.len(),
+            added: indexed.uploaded,
+            failed: indexed.failed,
+        });
     }
-    
-    log_important!(info, "文件收集完成: 扫描文件数={}, 索引文件数={}, 生成blobs数={}, 排除文件/目录数={}", scanned_files, indexed_files, out.len(), excluded_count);
-    Ok(out)
+    Ok(stats)
 }
 
-async fn index_and_search(config: &AcemcpConfig, project_root_path: &str, query: &str) -> anyhow::Result<String> {
+/// 校验远程端点配置并建立连接
+fn remote_target(config: &AcemcpConfig, roots: &[IndexRoot]) -> anyhow::Result<RemoteTarget> {
     let base_url = config.base_url.clone().ok_or_else(|| anyhow::anyhow!("未配置 base_url"))?;
     // 严格校验 base_url
     let has_scheme = base_url.starts_with("http://") || base_url.starts_with("https://");
@@ -398,234 +471,135 @@ async fn index_and_search(config: &AcemcpConfig, project_root_path: &str, query:
     if !has_scheme || !has_host { anyhow::bail!("无效的 base_url，请填写完整的 http(s)://host[:port] 格式"); }
     let token = config.token.clone().ok_or_else(|| anyhow::anyhow!("未配置 token"))?;
     let batch_size = config.batch_size.unwrap_or(10) as usize;
-    let max_lines = config.max_lines_per_blob.unwrap_or(800) as usize;
-    let text_exts = config.text_extensions.clone().unwrap_or_default();
-    let exclude_patterns = config.exclude_patterns.clone().unwrap_or_default();
+    let chunk_options = config.chunk_options();
+    let text_exts = config.text_extensions_or_default();
+    let exclude_patterns = config.exclude_patterns_or_default();
 
     // 日志：基础配置
     log_important!(info,
         "=== 开始索引代码库 ==="
     );
     log_important!(info,
-        "Acemcp配置: base_url={}, batch_size={}, max_lines_per_blob={}, text_exts数量={}, exclude_patterns数量={}",
+        "Acemcp配置: base_url={}, batch_size={}, max_lines_per_blob={}, chunk_overlap_lines={}, text_exts数量={}, exclude_patterns数量={}",
         base_url,
         batch_size,
-        max_lines,
+        chunk_options.max_lines,
+        chunk_options.overlap_lines,
         text_exts.len(),
         exclude_patterns.len()
     );
     log_important!(info,
-        "项目路径: {}", project_root_path
+        "项目路径: {}", roots.iter().map(|root| root.path.as_str()).collect::<Vec<_>>().join(", ")
     );
 
-    // 收集 blob（根据扩展名与排除规则，简化版 .gitignore 支持）
-    log_important!(info, "开始收集代码文件...");
-    let blobs = collect_blobs(project_root_path, &text_exts, &exclude_patterns, max_lines)?;
-    if blobs.is_empty() { anyhow::bail!("未在项目中找到可索引的文本文件"); }
-
-    // 加载 projects.json
-    let projects_path = home_projects_file();
-    let mut projects: ProjectsFile = if projects_path.exists() {
-        let data = fs::read_to_string(&projects_path).unwrap_or_default();
-        serde_json::from_str(&data).unwrap_or_default()
-    } else { ProjectsFile::default() };
-
-    let normalized_root = PathBuf::from(project_root_path).canonicalize().unwrap_or_else(|_| PathBuf::from(project_root_path)).to_string_lossy().replace('\\', "/");
-    let existing_blob_names: std::collections::HashSet<String> = projects.0.get(&normalized_root).cloned().unwrap_or_default().into_iter().collect();
-
-    // 计算所有 blob 的哈希值，建立哈希到 blob 的映射
-    let mut blob_hash_map: std::collections::HashMap<String, BlobItem> = std::collections::HashMap::new();
-    for blob in &blobs {
-        let hash = sha256_hex(&blob.path, &blob.content);
-        blob_hash_map.insert(hash.clone(), blob.clone());
-    }
+    Ok(RemoteTarget {
+        client: Client::new(),
+        base_url,
+        token,
+        limits: UploadLimits {
+            batch_size,
+            max_batch_bytes: config.max_batch_bytes_or_default(),
+            concurrency: config.upload_concurrency_or_default(),
+        },
+    })
+}
 
-    // 分离已存在和新增加的 blob（与 Python 版本保持一致）
-    let all_blob_hashes: std::collections::HashSet<String> = blob_hash_map.keys().cloned().collect();
-    let existing_hashes: std::collections::HashSet<String> = all_blob_hashes.intersection(&existing_blob_names).cloned().collect();
-    let new_hashes: std::collections::HashSet<String> = all_blob_hashes.difference(&existing_blob_names).cloned().collect();
+/// 增量上传各根目录的 blob 并调用远程检索
+pub async fn index_and_search(config: &AcemcpConfig, roots: &[IndexRoot], query: &str, scope: &SearchScope) -> anyhow::Result<SearchOutput> {
+    let target = remote_target(config, roots)?;
+
+    // 各根目录单独索引，检索时合并所有根目录的 blob，合并后的路径带有根目录的前缀
+    // 索引期间持有该根目录的锁，其他客户端同时检索同一项目时等待记录写入后再读取
+    let mut blob_names: Vec<String> = Vec::new();
+    let mut blob_hash_map: HashMap<String, HashedBlob> = HashMap::new();
+    let mut root_files: Vec<(&IndexRoot, HashSet<String>)> = Vec::new();
+    for root in indexed_roots(roots) {
+        let mut journal = ProjectJournal::open(&root.normalized).await;
+        let indexed = sync_root(config, &target, root, scope, &mut journal).await?;
+        blob_names.extend(indexed.blob_names);
+        root_files.push((root, indexed.blobs.values().map(|blob| blob_file_path(&blob.path).to_string()).collect()));
+        blob_hash_map.extend(indexed.blobs.into_iter().map(|(hash, mut blob)| {
+            blob.path = root.merged_path(&blob.path);
+            (hash, blob)
+        }));
+    }
 
-    // 需要上传的新 blob
-    let new_blobs: Vec<BlobItem> = new_hashes.iter().filter_map(|h| blob_hash_map.get(h).cloned()).collect();
+    if blob_hash_map.is_empty() { anyhow::bail!("未在项目中找到可索引的文本文件"); }
+    let index_key = combined_key(roots);
+    if blob_names.is_empty() { 
+        log_important!(info, "索引后未找到 blobs，项目路径: {}", index_key);
+        anyhow::bail!("索引后未找到 blobs"); 
+    }
 
+    // 发起检索：有检查点时只发送相对检查点的变化，多根目录检索的检查点对应所有根目录的 blob 集合
     log_important!(info,
-        "=== 索引统计 ==="
-    );
-    log_important!(info,
-        "收集到blobs总数: {}, 既有blobs: {}, 新增blobs: {}, 需要上传: {}",
-        blobs.len(),
-        existing_hashes.len(),
-        new_hashes.len(),
-        new_blobs.len()
+        "=== 开始代码检索 ==="
     );
+    let client = &target.client;
+    let base_url = target.base_url.as_str();
+    let token = target.token.as_str();
+    let mut checkpoint = Checkpoint::load(&index_key);
+
+    // 限定检索范围时只发送范围内的 blob，检查点对应整个项目，此时不使用也不更新
+    let use_checkpoint = !scope.is_restricted();
+    let mut delta = if use_checkpoint {
+        BlobDelta::since(checkpoint.as_ref(), &blob_names)
+    } else {
+        let scoped_names: Vec<String> = blob_names
+            .iter()
+            .filter(|name| blob_hash_map.get(*name).is_some_and(|blob| scope.matches(&blob.path)))
+            .cloned()
+            .collect();
+        log_important!(info, "检索范围内的blobs: {}/{}", scoped_names.len(), blob_names.len());
+        if scoped_names.is_empty() {
+            return Ok(SearchOutput::from_hits(Vec::new()));
+        }
+        BlobDelta::full(&scoped_names)
+    };
 
-    let client = Client::new();
-
-    // 批量上传新增 blobs
-    let mut uploaded_names: Vec<String> = Vec::new();
-    let mut failed_batches: Vec<usize> = Vec::new();
-    
-    if !new_blobs.is_empty() {
-        let total_batches = (new_blobs.len() + batch_size - 1) / batch_size;
-        log_important!(info,
-            "=== 开始批量上传代码索引 ==="
-        );
-        log_important!(info,
-            "目标端点: {}/batch-upload, 总批次: {}, 每批上限: {}, 总blobs: {}",
-            base_url,
-            total_batches,
-            batch_size,
-            new_blobs.len()
-        );
-        
-        for i in 0..total_batches {
-            let start = i * batch_size;
-            let end = usize::min(start + batch_size, new_blobs.len());
-            let batch = &new_blobs[start..end];
-            let url = format!("{}/batch-upload", base_url);
-            
-            log_important!(info,
-                "上传批次 {}/{}: url={}, blobs={}",
-                i + 1,
-                total_batches,
-                url,
-                batch.len()
-            );
-            
-            // 详细记录每个 blob 的信息
-            for (idx, blob) in batch.iter().enumerate() {
-                log_important!(info,
-                    "  批次 {} - Blob {}/{}: path={}, content_length={}",
-                    i + 1,
-                    idx + 1,
-                    batch.len(),
-                    blob.path,
-                    blob.content.len()
-                );
-            }
-            
-            let payload = serde_json::json!({"blobs": batch});
-            log_important!(info, "批次载荷大小: {} 字节", payload.to_string().len());
-            
-            match retry_request(|| async {
-                let r = client
-                    .post(&url)
-                    .header(AUTHORIZATION, format!("Bearer {}", token))
-                    .header(CONTENT_TYPE, "application/json")
-                    .json(&payload)
-                    .send()
-                    .await?;
-                
-                let status = r.status();
-                log_important!(info, "HTTP响应状态: {}", status);
-                
-                if !status.is_success() {
-                    let body = r.text().await.unwrap_or_default();
-                    anyhow::bail!("HTTP {} {}", status, body);
-                }
-                
-                let v: serde_json::Value = r.json().await?;
-                log_important!(info, "响应数据: {}", serde_json::to_string_pretty(&v).unwrap_or_default());
-                Ok(v)
-            }, 3, 1.0).await {
-                Ok(value) => {
-                    if let Some(arr) = value.get("blob_names").and_then(|v| v.as_array()) {
-                        let mut batch_names: Vec<String> = Vec::new();
-                        for v in arr { 
-                            if let Some(s) = v.as_str() { 
-                                batch_names.push(s.to_string()); 
-                            }
-                        }
-                        
-                        if batch_names.is_empty() {
-                            log_important!(info, "批次 {} 返回了空的blob名称列表", i + 1);
-                            failed_batches.push(i + 1);
-                        } else {
-                            uploaded_names.extend(batch_names.clone());
-                            log_important!(info, "批次 {} 上传成功，获得 {} 个blob名称", i + 1, batch_names.len());
-                            // 详细记录每个上传成功的 blob 名称
-                            for (idx, name) in batch_names.iter().enumerate() {
-                                log_important!(info, "  批次 {} - 上传成功 Blob {}/{}: name={}", i + 1, idx + 1, batch_names.len(), name);
-                            }
-                        }
-                    } else {
-                        log_important!(info, "批次 {} 响应中缺少blob_names字段", i + 1);
-                        failed_batches.push(i + 1);
-                    }
-                }
-                Err(e) => {
-                    log_important!(info, "批次 {} 上传失败: {}", i + 1, e);
-                    failed_batches.push(i + 1);
-                }
+    // 变化较多时先让服务端建立新检查点，之后的查询只需发送新的变化
+    if use_checkpoint && delta.len() > ACEMCP_CHECKPOINT_DELTA_THRESHOLD && !CHECKPOINT_UNSUPPORTED.load(Ordering::Relaxed) {
+        match create_checkpoint(client, base_url, token, &delta).await {
+            Ok(Some(checkpoint_id)) => {
+                log_important!(info, "已建立检查点: checkpoint_id={}, blobs数量={}", checkpoint_id, blob_names.len());
+                checkpoint = Some(Checkpoint::save(&index_key, checkpoint_id, &blob_names));
+                delta = BlobDelta::since(checkpoint.as_ref(), &blob_names);
             }
+            Ok(None) => log_important!(info, "服务端未返回检查点，本次发送全部变化"),
+            Err(e) => log_important!(warn, "建立检查点失败，本次发送全部变化: {}", e),
         }
-        
-        // 上传结果总结
-        log_important!(info,
-            "=== 上传结果总结 ==="
-        );
-        if !failed_batches.is_empty() {
-            log_important!(info, "上传完成，但有失败的批次: {:?}, 成功上传blobs: {}", failed_batches, uploaded_names.len());
-        } else {
-            log_important!(info, "所有批次上传成功，共上传 {} 个blobs", uploaded_names.len());
-        }
-    } else {
-        log_important!(info, "没有新的blob需要上传，使用已有索引");
-    }
-
-    // 合并并保存 projects.json（与 Python 版本保持一致）
-    // 只保留当前项目中仍然存在的 blob 的哈希值（自动删除已删除的 blob）
-    let all_blob_names: Vec<String> = existing_hashes.into_iter().chain(uploaded_names.into_iter()).collect();
-    projects.0.insert(normalized_root.clone(), all_blob_names.clone());
-    if let Ok(s) = serde_json::to_string_pretty(&projects) { let _ = fs::write(projects_path, s); }
-
-    // 使用合并后的 blob_names（与 Python 版本保持一致）
-    let blob_names = all_blob_names;
-    if blob_names.is_empty() { 
-        log_important!(info, "索引后未找到 blobs，项目路径: {}", normalized_root);
-        anyhow::bail!("索引后未找到 blobs"); 
     }
 
-    // 发起检索
+    let search_url = format!("{}/agents/codebase-retrieval", base_url);
     log_important!(info,
-        "=== 开始代码检索 ==="
+        "检索请求: url={}, checkpoint_id={:?}, 新增blobs={}, 删除blobs={}, 查询内容={}",
+        search_url,
+        delta.checkpoint_id,
+        delta.added_blobs.len(),
+        delta.deleted_blobs.len(),
+        query
     );
-    let search_url = format!("{}/agents/codebase-retrieval", base_url);
-    log_important!(info, "检索请求: url={}, 使用blobs数量={}, 查询内容={}", search_url, blob_names.len(), query);
-    
-    let payload = serde_json::json!({
-        "information_request": query,
-        "blobs": {"checkpoint_id": serde_json::Value::Null, "added_blobs": blob_names, "deleted_blobs": []},
-        "dialog": [],
-        "max_output_length": 0,
-        "disable_codebase_retrieval": false,
-        "enable_commit_retrieval": false,
-    });
-    
-    log_important!(info, "检索载荷大小: {} 字节", payload.to_string().len());
-    
-    let value: serde_json::Value = retry_request(|| async {
-        let r = client
-            .post(&search_url)
-            .header(AUTHORIZATION, format!("Bearer {}", token))
-            .header(CONTENT_TYPE, "application/json")
-            .json(&payload)
-            .send()
-            .await?;
-        
-        let status = r.status();
-        log_important!(info, "检索请求HTTP响应状态: {}", status);
-        
-        if !status.is_success() {
-            let body = r.text().await.unwrap_or_default();
-            anyhow::bail!("HTTP {} {}", status, body);
+
+    let value = match retrieve(client, &search_url, token, query, &delta).await {
+        Ok(value) => value,
+        // 服务端不认可检查点时丢弃检查点，重新发送全部 blob
+        Err(e) if delta.checkpoint_id.is_some() && is_checkpoint_rejected(&e.to_string()) => {
+            log_important!(warn, "检查点被服务端拒绝，改为发送全部blobs: {}", e);
+            Checkpoint::remove(&index_key);
+            delta = BlobDelta::full(&blob_names);
+            retrieve(client, &search_url, token, query, &delta).await?
         }
-        
-        let v: serde_json::Value = r.json().await?;
-        log_important!(info, "检索响应数据: {}", serde_json::to_string_pretty(&v).unwrap_or_default());
-        Ok(v)
-    }, 3, 2.0).await?;
-    
+        Err(e) => return Err(e),
+    };
+
+    // 服务端在检索时签发了新检查点，记录其对应的 blob 集合
+    if let Some(checkpoint_id) = checkpoint_id_from_response(&value).filter(|_| use_checkpoint) {
+        if delta.checkpoint_id.as_deref() != Some(checkpoint_id.as_str()) {
+            log_important!(info, "检索响应中包含新检查点: checkpoint_id={}", checkpoint_id);
+            Checkpoint::save(&index_key, checkpoint_id, &blob_names);
+        }
+    }
+
     let text = value
         .get("formatted_retrieval")
         .and_then(|v| v.as_str())
@@ -634,9 +608,49 @@ async fn index_and_search(config: &AcemcpConfig, project_root_path: &str, query:
         
     if text.is_empty() { 
         log_important!(info, "搜索返回空结果");
-        Ok("No relevant code context found for your query.".to_string()) 
     } else { 
         log_important!(info, "搜索成功，返回文本长度: {}", text.len());
-        Ok(text) 
     }
+    let output = prefix_remote_hits(SearchOutput::from_text(text), &root_files);
+    Ok(attribute_hits(scope.apply(output), roots))
+}
+
+/// 为远程检索结果中的路径加上所属根目录的前缀
+///
+/// 上传的 blob 路径相对各自的根目录，按各根目录的文件确定命中片段所属的根目录；
+/// 多个根目录中有同名文件时，取文件内容包含该片段的根目录
+fn prefix_remote_hits(output: SearchOutput, root_files: &[(&IndexRoot, HashSet<String>)]) -> SearchOutput {
+    if root_files.iter().all(|(root, _)| root.prefix.is_empty()) {
+        return output;
+    }
+
+    let hits = output
+        .hits
+        .into_iter()
+        .map(|mut hit| {
+            let candidates: Vec<&IndexRoot> =
+                root_files.iter().filter(|(_, files)| files.contains(&hit.path)).map(|(root, _)| *root).collect();
+            let root = match candidates.as_slice() {
+                [root] => Some(*root),
+                _ => candidates
+                    .iter()
+                    .copied()
+                    .find(|root| snippet_in_file(root, &hit.path, &hit.snippet))
+                    .or(candidates.first().copied()),
+            };
+            if let Some(root) = root {
+                hit.path = root.merged_path(&hit.path);
+            }
+            hit
+        })
+        .collect();
+    SearchOutput::from_hits(hits)
+}
+
+/// 根目录中的文件是否包含片段的第一行非空内容
+fn snippet_in_file(root: &IndexRoot, path: &str, snippet: &str) -> bool {
+    let Some(line) = snippet.lines().map(str::trim).find(|line| !line.is_empty()) else {
+        return false;
+    };
+    std::fs::read_to_string(std::path::Path::new(&root.path).join(path)).is_ok_and(|content| content.contains(line))
 }
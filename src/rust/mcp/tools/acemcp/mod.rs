@@ -1,9 +1,28 @@
 // Acemcp工具模块
 // 用于代码库索引和语义搜索的MCP工具
 
+pub mod cache;
+pub mod checkpoint;
+pub mod chunker;
+pub mod cli;
+pub mod doc_index;
+pub mod embedding;
+pub mod ignore_rules;
+pub mod index;
+pub mod journal;
+pub mod local;
 pub mod mcp;
+pub mod provider;
+pub mod results;
+pub mod roots;
+pub mod scope;
+pub mod secrets;
 pub mod types;
+pub mod upload;
+pub mod watcher;
 pub mod commands;
+#[cfg(test)]
+mod test_server;
 
 // 重新导出工具以便访问
 pub use mcp::AcemcpTool;
@@ -0,0 +1,262 @@
+// 检索后端
+// search_context 只依赖 RetrievalProvider：根据配置选择远程 ACE、向量检索或本地离线索引，
+// 各后端自行完成增量索引和排序，返回格式一致的检索结果
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::future::Future;
+use std::pin::Pin;
+
+use super::embedding::EmbeddingProvider;
+use super::journal::{read_state, JournalState};
+use super::local::{index_and_search_local, index_local, local_indexed_hashes};
+use super::mcp::{index_and_search, index_remote};
+use super::results::SearchOutput;
+use super::roots::IndexRoot;
+use super::scope::SearchScope;
+use super::types::AcemcpConfig;
+use crate::constants::mcp::{ACEMCP_BACKEND_EMBEDDING, ACEMCP_BACKEND_LOCAL, ACEMCP_BACKEND_REMOTE};
+
+/// 检索任务
+pub type SearchFuture<'a> = Pin<Box<dyn Future<Output = Result<SearchOutput>> + Send + 'a>>;
+
+/// 索引任务
+pub type IndexFuture<'a> = Pin<Box<dyn Future<Output = Result<Vec<IndexStats>>> + Send + 'a>>;
+
+/// 单个根目录的索引统计
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexStats {
+    /// 根目录路径
+    pub root: String,
+    /// 当前的代码块总数
+    pub blobs: usize,
+    /// 本次新写入索引的代码块数（远程检索为上传数）
+    pub added: usize,
+    /// 本次未能写入索引的代码块数，下次索引时重试
+    pub failed: usize,
+}
+
+/// 代码检索后端
+pub trait RetrievalProvider: Send + Sync {
+    /// 后端名称，与配置中的 backend 取值一致
+    fn name(&self) -> &'static str;
+
+    /// 增量更新各根目录的索引并检索
+    fn search<'a>(&'a self, roots: &'a [IndexRoot], query: &'a str, scope: &'a SearchScope) -> SearchFuture<'a>;
+
+    /// 只增量更新各根目录的索引，不检索
+    fn index<'a>(&'a self, roots: &'a [IndexRoot]) -> IndexFuture<'a>;
+
+    /// 该后端索引中已有的 blob 哈希，尚未建立索引时返回 None
+    fn indexed_hashes(&self, root: &IndexRoot) -> Option<HashSet<String>>;
+}
+
+/// 根据配置选择检索后端
+pub fn provider_for(config: AcemcpConfig) -> Box<dyn RetrievalProvider> {
+    let backend = config.effective_backend().to_string();
+    match backend.as_str() {
+        ACEMCP_BACKEND_LOCAL => Box::new(LocalProvider { config }),
+        ACEMCP_BACKEND_EMBEDDING => Box::new(EmbeddingProvider::new(config)),
+        _ => Box::new(AceProvider { config }),
+    }
+}
+
+/// 远程 ACE 检索：上传新增 blob 后调用 codebase-retrieval 接口
+pub struct AceProvider {
+    pub config: AcemcpConfig,
+}
+
+impl RetrievalProvider for AceProvider {
+    fn name(&self) -> &'static str {
+        ACEMCP_BACKEND_REMOTE
+    }
+
+    fn search<'a>(&'a self, roots: &'a [IndexRoot], query: &'a str, scope: &'a SearchScope) -> SearchFuture<'a> {
+        Box::pin(index_and_search(&self.config, roots, query, scope))
+    }
+
+    fn index<'a>(&'a self, roots: &'a [IndexRoot]) -> IndexFuture<'a> {
+        Box::pin(index_remote(&self.config, roots))
+    }
+
+    /// 上传记录中的 blob 名称即服务端已有的 blob 哈希
+    fn indexed_hashes(&self, root: &IndexRoot) -> Option<HashSet<String>> {
+        match read_state(&root.normalized) {
+            JournalState::Loaded(names) => Some(names),
+            _ => None,
+        }
+    }
+}
+
+/// 本地离线检索：BM25 排序，在阻塞线程中完成文件扫描与排序
+pub struct LocalProvider {
+    pub config: AcemcpConfig,
+}
+
+impl RetrievalProvider for LocalProvider {
+    fn name(&self) -> &'static str {
+        ACEMCP_BACKEND_LOCAL
+    }
+
+    fn search<'a>(&'a self, roots: &'a [IndexRoot], query: &'a str, scope: &'a SearchScope) -> SearchFuture<'a> {
+        let config = self.config.clone();
+        let roots = roots.to_vec();
+        let query = query.to_string();
+        let scope = scope.clone();
+        Box::pin(async move {
+            tokio::task::spawn_blocking(move || index_and_search_local(&config, &roots, &query, &scope))
+                .await
+                .unwrap_or_else(|e| Err(anyhow::anyhow!("本地检索任务异常: {}", e)))
+        })
+    }
+
+    fn index<'a>(&'a self, roots: &'a [IndexRoot]) -> IndexFuture<'a> {
+        let config = self.config.clone();
+        let roots = roots.to_vec();
+        Box::pin(async move {
+            tokio::task::spawn_blocking(move || index_local(&config, &roots))
+                .await
+                .unwrap_or_else(|e| Err(anyhow::anyhow!("本地索引任务异常: {}", e)))
+        })
+    }
+
+    fn indexed_hashes(&self, root: &IndexRoot) -> Option<HashSet<String>> {
+        local_indexed_hashes(&root.normalized)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::tools::acemcp::index::{data_dir, sha256_hex};
+    use crate::mcp::tools::acemcp::journal::journal_file;
+    use crate::mcp::tools::acemcp::results::{format_retrieval, SearchHit};
+    use crate::mcp::tools::acemcp::test_server::MockServer;
+    use std::fs;
+
+    fn test_config(backend: &str) -> AcemcpConfig {
+        AcemcpConfig {
+            base_url: None,
+            token: None,
+            batch_size: None,
+            max_lines_per_blob: None,
+            chunk_overlap_lines: None,
+            text_extensions: None,
+            exclude_patterns: None,
+            backend: Some(backend.to_string()),
+            watch: Some(false),
+            upload_concurrency: None,
+            max_batch_bytes: None,
+            embedding_base_url: None,
+            embedding_token: None,
+            embedding_model: None,
+            secret_mode: None,
+        }
+    }
+
+    fn test_project(name: &str, files: &[(&str, &str)]) -> Vec<IndexRoot> {
+        let dir = data_dir().parent().unwrap().join(name);
+        let _ = fs::remove_dir_all(&dir);
+        for (path, content) in files {
+            let file = dir.join(path);
+            fs::create_dir_all(file.parent().unwrap()).unwrap();
+            fs::write(file, content).unwrap();
+        }
+        vec![IndexRoot::primary(&dir.to_string_lossy())]
+    }
+
+    #[tokio::test]
+    async fn test_ace_provider_uploads_only_new_blobs() {
+        let server = MockServer::start(|path, body| match path {
+            "/batch-upload" => {
+                let names: Vec<String> = body["blobs"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|blob| sha256_hex(blob["path"].as_str().unwrap(), blob["content"].as_str().unwrap()))
+                    .collect();
+                serde_json::json!({ "blob_names": names })
+            }
+            "/find-missing" => serde_json::json!({ "unknown_memory_names": [], "nonindexed_blob_names": [] }),
+            _ => serde_json::json!({
+                "formatted_retrieval": format_retrieval(&[SearchHit {
+                    path: "src/bot.rs".to_string(),
+                    root: None,
+                    start_line: Some(1),
+                    end_line: Some(1),
+                    score: None,
+                    snippet: "fn send_message() {}".to_string(),
+                }])
+            }),
+        })
+        .await;
+
+        let roots = test_project("project-ace", &[("src/bot.rs", "fn send_message() {}\n"), ("README.md", "# bot\n")]);
+        let mut config = test_config(ACEMCP_BACKEND_REMOTE);
+        config.base_url = Some(server.base_url.clone());
+        config.token = Some("token".to_string());
+        let provider = provider_for(config);
+        assert_eq!(provider.name(), ACEMCP_BACKEND_REMOTE);
+
+        let scope = SearchScope::default();
+        let output = provider.search(&roots, "send message", &scope).await.unwrap();
+        assert_eq!(output.hits[0].path, "src/bot.rs");
+        let uploads = server.requests_to("/batch-upload");
+        assert_eq!(uploads.len(), 1);
+        assert_eq!(uploads[0]["blobs"].as_array().unwrap().len(), 2);
+
+        // 第二次检索只发起查询，不再上传
+        provider.search(&roots, "send message", &scope).await.unwrap();
+        assert_eq!(server.requests_to("/batch-upload").len(), 1);
+        assert_eq!(server.requests_to("/agents/codebase-retrieval").len(), 2);
+
+        // 上传记录损坏时向服务端核对，服务端已有的 blob 不再上传
+        fs::write(journal_file(&roots[0].normalized), "{\"version\": 1, \"root\"").unwrap();
+        provider.search(&roots, "send message", &scope).await.unwrap();
+        assert_eq!(server.requests_to("/find-missing")[0]["mem_object_names"].as_array().unwrap().len(), 2);
+        assert_eq!(server.requests_to("/batch-upload").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_embedding_provider_ranks_by_similarity() {
+        // 按关键词生成向量：第一维表示 telegram，第二维表示 config
+        let server = MockServer::start(|_, body| {
+            let data: Vec<serde_json::Value> = body["input"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .enumerate()
+                .map(|(index, input)| {
+                    let text = input.as_str().unwrap();
+                    let flag = |keyword: &str| if text.contains(keyword) { 1.0 } else { 0.0 };
+                    serde_json::json!({ "index": index, "embedding": [flag("telegram"), flag("config"), 0.1] })
+                })
+                .collect();
+            serde_json::json!({ "data": data })
+        })
+        .await;
+
+        let roots = test_project(
+            "project-embedding",
+            &[("src/telegram.rs", "fn send_message() {}\n"), ("src/settings.rs", "fn load_config() {}\n")],
+        );
+        let mut config = test_config(ACEMCP_BACKEND_EMBEDDING);
+        config.embedding_base_url = Some(server.base_url.clone());
+        let provider = provider_for(config);
+        assert_eq!(provider.name(), ACEMCP_BACKEND_EMBEDDING);
+
+        let scope = SearchScope::default();
+        let output = provider.search(&roots, "config loading", &scope).await.unwrap();
+        assert_eq!(output.hits[0].path, "src/settings.rs");
+        assert_eq!(server.requests_to("/embeddings").len(), 2);
+
+        // 向量已保存，第二次检索只为查询生成向量
+        let output = provider.search(&roots, "telegram", &scope).await.unwrap();
+        assert_eq!(output.hits[0].path, "src/telegram.rs");
+        let requests = server.requests_to("/embeddings");
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[2]["input"], serde_json::json!(["telegram"]));
+        assert_eq!(requests[0]["model"], "text-embedding-3-small");
+    }
+}
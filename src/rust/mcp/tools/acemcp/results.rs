@@ -0,0 +1,200 @@
+// 检索结果
+// 远程与本地检索后端共用：文本格式与 formatted_retrieval 一致，
+// 结构化结果按代码片段给出文件路径、行号范围、分数和内容；多根目录检索时还给出片段所属的根目录
+
+use serde::Serialize;
+
+use super::chunker::{blob_file_path, blob_line_range};
+
+/// 无结果时返回的文本
+pub const NO_RESULTS_TEXT: &str = "No relevant code context found for your query.";
+
+/// 命中的代码片段
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    /// 相对项目根目录的文件路径
+    pub path: String,
+    /// 片段所属的根目录，只在多根目录检索时给出
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub root: Option<String>,
+    /// 片段在文件中的起止行（从 1 开始，含两端），远程结果未标注行号时为空
+    pub start_line: Option<usize>,
+    pub end_line: Option<usize>,
+    /// 相关性分数，远程检索不返回分数时为空
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+    pub snippet: String,
+}
+
+/// 一次检索的结果
+pub struct SearchOutput {
+    /// formatted_retrieval 格式的文本
+    pub text: String,
+    pub hits: Vec<SearchHit>,
+}
+
+impl SearchOutput {
+    /// 由命中片段生成结果
+    pub fn from_hits(hits: Vec<SearchHit>) -> Self {
+        Self { text: format_retrieval(&hits), hits }
+    }
+
+    /// 由服务端返回的文本生成结果
+    pub fn from_text(text: String) -> Self {
+        if text.trim().is_empty() {
+            return Self::from_hits(Vec::new());
+        }
+        let hits = parse_formatted_retrieval(&text);
+        Self { text, hits }
+    }
+}
+
+/// 生成 formatted_retrieval 格式的文本，有行号时每行带行号
+pub fn format_retrieval(hits: &[SearchHit]) -> String {
+    if hits.is_empty() {
+        return NO_RESULTS_TEXT.to_string();
+    }
+
+    let mut output = String::from("The following code sections were retrieved:\n");
+    for hit in hits {
+        output.push_str(&format!("Path: {}\n", hit.path));
+        if let Some(root) = &hit.root {
+            output.push_str(&format!("Root: {}\n", root));
+        }
+        for (offset, line) in hit.snippet.lines().enumerate() {
+            match hit.start_line {
+                Some(start) => output.push_str(&format!("{:>6}\t{}\n", start + offset, line)),
+                None => output.push_str(&format!("{}\n", line)),
+            }
+        }
+        output.push_str("...\n");
+    }
+    output
+}
+
+/// 解析 formatted_retrieval 文本
+///
+/// `Path: ` 行开始一个文件，紧随其后的 `Root: ` 行给出所属根目录，`...` 行分隔同一文件中的不同片段；
+/// 代码行形如 `行号\t内容`，没有行号时尝试使用路径中的分块行号范围
+pub fn parse_formatted_retrieval(text: &str) -> Vec<SearchHit> {
+    let mut hits = Vec::new();
+    let mut path: Option<String> = None;
+    let mut root: Option<String> = None;
+    let mut segment = Segment::default();
+
+    for line in text.lines() {
+        if let Some(rest) = line.strip_prefix("Path: ") {
+            segment.flush(path.as_deref(), root.as_deref(), &mut hits);
+            path = Some(rest.trim().to_string());
+            root = None;
+        } else if let Some(rest) = line.strip_prefix("Root: ").filter(|_| path.is_some() && segment.is_empty()) {
+            root = Some(rest.trim().to_string());
+        } else if line.trim() == "..." {
+            segment.flush(path.as_deref(), root.as_deref(), &mut hits);
+        } else if path.is_some() {
+            segment.push(line);
+        }
+    }
+    segment.flush(path.as_deref(), root.as_deref(), &mut hits);
+
+    hits
+}
+
+/// 正在解析的片段
+#[derive(Default)]
+struct Segment {
+    start_line: Option<usize>,
+    end_line: Option<usize>,
+    lines: Vec<String>,
+}
+
+impl Segment {
+    fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    fn push(&mut self, line: &str) {
+        let numbered = line
+            .split_once('\t')
+            .and_then(|(number, content)| Some((number.trim().parse::<usize>().ok()?, content)));
+        match numbered {
+            Some((number, content)) => {
+                self.start_line.get_or_insert(number);
+                self.end_line = Some(number);
+                self.lines.push(content.to_string());
+            }
+            None => self.lines.push(line.to_string()),
+        }
+    }
+
+    fn flush(&mut self, blob_path: Option<&str>, root: Option<&str>, hits: &mut Vec<SearchHit>) {
+        let segment = std::mem::take(self);
+        let Some(blob_path) = blob_path else {
+            return;
+        };
+        if segment.lines.iter().all(|line| line.trim().is_empty()) {
+            return;
+        }
+
+        let range = blob_line_range(blob_path);
+        hits.push(SearchHit {
+            path: blob_file_path(blob_path).to_string(),
+            root: root.map(str::to_string),
+            start_line: segment.start_line.or(range.map(|(start, _)| start)),
+            end_line: segment.end_line.or(range.map(|(_, end)| end)),
+            score: None,
+            snippet: segment.lines.join("\n"),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_and_parse_round_trip() {
+        let hits = vec![
+            SearchHit {
+                path: "src/main.rs".to_string(),
+                root: None,
+                start_line: Some(10),
+                end_line: Some(11),
+                score: Some(2.5),
+                snippet: "fn main() {\n}".to_string(),
+            },
+            SearchHit {
+                path: "src/lib.rs".to_string(),
+                root: Some("/work/crates/core".to_string()),
+                start_line: Some(1),
+                end_line: Some(1),
+                score: Some(1.0),
+                snippet: "pub mod a;".to_string(),
+            },
+        ];
+        let output = SearchOutput::from_hits(hits.clone());
+        let parsed = SearchOutput::from_text(output.text).hits;
+        let without_scores: Vec<SearchHit> = hits.into_iter().map(|hit| SearchHit { score: None, ..hit }).collect();
+        assert_eq!(parsed, without_scores);
+
+        assert_eq!(SearchOutput::from_text(String::new()).text, NO_RESULTS_TEXT);
+    }
+
+    #[test]
+    fn test_parse_remote_sections() {
+        let text = "The following code sections were retrieved:\n\
+                    Path: src/a.rs#L120-240\n\
+                    fn a() {}\n\
+                    ...\n\
+                    Path: src/b.rs\n\
+                    \x20   5\tlet x = 1;\n\
+                    ...\n\
+                    \x20  40\tlet y = 2;\n\
+                    \x20  41\tlet z = 3;\n";
+        let hits = parse_formatted_retrieval(text);
+        assert_eq!(hits.len(), 3);
+        assert_eq!((hits[0].path.as_str(), hits[0].start_line, hits[0].end_line), ("src/a.rs", Some(120), Some(240)));
+        assert_eq!((hits[1].path.as_str(), hits[1].start_line, hits[1].snippet.as_str()), ("src/b.rs", Some(5), "let x = 1;"));
+        assert_eq!((hits[2].start_line, hits[2].end_line), (Some(40), Some(41)));
+    }
+}
@@ -0,0 +1,389 @@
+// 多根目录检索
+// 一次检索可以包含多个根目录：请求中额外指定的目录，以及在项目中发现的嵌套 git 仓库、子模块和 Cargo 工作区成员。
+// 每个根目录单独收集、上传并记录索引，blob 路径相对根目录自身，索引数据与单独检索该目录时完全一致；
+// 检索时合并各根目录的 blob 并为路径加上根目录的前缀，据此确定命中片段来自哪个根目录。
+// 位于其他根目录之内的根目录不单独索引，由外层根目录一并索引
+
+use anyhow::Result;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use regex::Regex;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::ignore_rules::walk_builder;
+use super::index::{build_exclude_globset, is_git_dir, normalize_root, should_exclude};
+use super::results::SearchOutput;
+use super::types::{AcemcpConfig, AcemcpRequest};
+use crate::constants::mcp::{ACEMCP_MAX_INDEX_ROOTS, ACEMCP_WORKSPACE_DISCOVERY_DEPTH};
+use crate::{log_debug, log_important};
+
+/// 参与检索的根目录
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexRoot {
+    /// 根目录路径
+    pub path: String,
+    /// 规范化后的路径，作为索引数据的键
+    pub normalized: String,
+    /// 合并检索结果时的路径前缀：主根目录为空，位于其他根目录内的为外层根目录的前缀加相对路径，
+    /// 其余为目录名，均以 `/` 结尾
+    pub prefix: String,
+    /// 位于其他根目录之内，由外层根目录一并索引，只用于标注命中片段
+    pub covered: bool,
+}
+
+impl IndexRoot {
+    /// 只检索一个根目录时使用，与单根目录的索引数据完全一致
+    pub fn primary(project_root_path: &str) -> Self {
+        Self {
+            path: project_root_path.to_string(),
+            normalized: normalize_root(project_root_path),
+            prefix: String::new(),
+            covered: false,
+        }
+    }
+
+    /// 合并检索时 blob 的路径
+    pub fn merged_path(&self, blob_path: &str) -> String {
+        format!("{}{}", self.prefix, blob_path)
+    }
+}
+
+/// 需要单独索引的根目录
+pub fn indexed_roots(roots: &[IndexRoot]) -> impl Iterator<Item = &IndexRoot> {
+    roots.iter().filter(|root| !root.covered)
+}
+
+/// 根据请求确定参与检索的根目录，第一个为主根目录
+///
+/// 额外指定的目录不存在时返回错误；重复的目录只保留一个，超过 `ACEMCP_MAX_INDEX_ROOTS` 的部分忽略
+pub fn resolve_roots(request: &AcemcpRequest, config: &AcemcpConfig) -> Result<Vec<IndexRoot>> {
+    let primary = IndexRoot::primary(&request.project_root_path);
+    let primary_path = PathBuf::from(&primary.normalized);
+
+    let mut candidates: Vec<(String, String)> = Vec::new();
+    for path in request.additional_roots.iter().flatten() {
+        if !Path::new(path).is_dir() {
+            anyhow::bail!("根目录不存在: {}", path);
+        }
+        candidates.push((path.clone(), normalize_root(path)));
+    }
+    if request.discover_workspace.unwrap_or(false) {
+        for path in discover_nested_roots(&primary_path, &config.exclude_patterns_or_default()) {
+            let path = path.to_string_lossy().replace('\\', "/");
+            let normalized = normalize_root(&path);
+            candidates.push((path, normalized));
+        }
+    }
+
+    let mut roots = vec![primary];
+    for (path, normalized) in candidates {
+        if roots.iter().any(|root| root.normalized == normalized) {
+            continue;
+        }
+        if roots.len() >= ACEMCP_MAX_INDEX_ROOTS {
+            log_important!(info, "根目录数已达上限 {}，忽略: {}", ACEMCP_MAX_INDEX_ROOTS, path);
+            continue;
+        }
+        let prefix = root_prefix(&primary_path, Path::new(&normalized), &roots);
+        roots.push(IndexRoot { path, normalized, prefix, covered: false });
+    }
+
+    // 根目录之间互相包含时由最外层的根目录索引，内层根目录的索引与是否参与多根目录检索无关
+    let outer: Vec<Option<(usize, String)>> = roots
+        .iter()
+        .map(|root| {
+            roots
+                .iter()
+                .enumerate()
+                .filter_map(|(index, other)| {
+                    relative_to(Path::new(&other.normalized), Path::new(&root.normalized)).map(|rel| (index, rel))
+                })
+                .min_by_key(|(index, _)| roots[*index].normalized.len())
+        })
+        .collect();
+    for (index, outer) in outer.into_iter().enumerate() {
+        if let Some((outer_index, rel)) = outer {
+            roots[index].prefix = format!("{}{}/", roots[outer_index].prefix, rel);
+            roots[index].covered = true;
+        }
+    }
+
+    if roots.len() > 1 {
+        log_important!(info,
+            "多根目录检索: {}",
+            roots
+                .iter()
+                .map(|root| format!("{} ({}{})", root.normalized, root.prefix, if root.covered { ", 由外层索引" } else { "" }))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+    Ok(roots)
+}
+
+/// 多个根目录共同的索引键，由需要单独索引的根目录组成，只有一个根目录时即为其规范化路径
+pub fn combined_key(roots: &[IndexRoot]) -> String {
+    indexed_roots(roots).map(|root| root.normalized.as_str()).collect::<Vec<_>>().join("|")
+}
+
+/// 标注命中片段所属的根目录，并将路径改为相对该根目录；只有一个根目录时保持不变
+pub fn attribute_hits(output: SearchOutput, roots: &[IndexRoot]) -> SearchOutput {
+    if roots.len() <= 1 || output.hits.is_empty() {
+        return output;
+    }
+
+    let hits = output
+        .hits
+        .into_iter()
+        .map(|mut hit| {
+            let root = roots
+                .iter()
+                .filter(|root| !root.prefix.is_empty() && hit.path.starts_with(&root.prefix))
+                .max_by_key(|root| root.prefix.len())
+                .unwrap_or(&roots[0]);
+            hit.path = hit.path[root.prefix.len()..].to_string();
+            hit.root = Some(root.normalized.clone());
+            hit
+        })
+        .collect();
+    SearchOutput::from_hits(hits)
+}
+
+/// `child` 严格位于 `parent` 之内时返回使用正斜杠的相对路径
+fn relative_to(parent: &Path, child: &Path) -> Option<String> {
+    let rel = child.strip_prefix(parent).ok()?;
+    if rel.as_os_str().is_empty() {
+        return None;
+    }
+    Some(rel.to_string_lossy().replace('\\', "/"))
+}
+
+/// 非主根目录的 blob 路径前缀
+///
+/// 位于主根目录内时为相对路径，与主根目录下的文件路径一致；
+/// 否则为目录名，与主根目录中已有的条目或其他根目录重名时加上序号
+fn root_prefix(primary: &Path, root: &Path, existing: &[IndexRoot]) -> String {
+    if let Some(rel) = relative_to(primary, root) {
+        return format!("{}/", rel);
+    }
+
+    let name = root.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_else(|| "root".to_string());
+    let taken = |candidate: &str| {
+        primary.join(candidate).exists() || existing.iter().any(|root| root.prefix.split('/').next() == Some(candidate))
+    };
+    let mut candidate = name.clone();
+    let mut n = 2;
+    while taken(&candidate) {
+        candidate = format!("{}-{}", name, n);
+        n += 1;
+    }
+    format!("{}/", candidate)
+}
+
+/// 在项目中发现嵌套的 git 仓库（含子模块）和 Cargo 工作区成员
+///
+/// 遵守忽略规则和排除模式，最多遍历 `ACEMCP_WORKSPACE_DISCOVERY_DEPTH` 层目录
+pub fn discover_nested_roots(root: &Path, exclude_patterns: &[String]) -> Vec<PathBuf> {
+    let (members, excluded_members) = fs::read_to_string(root.join("Cargo.toml"))
+        .map(|manifest| parse_workspace_members(&manifest))
+        .unwrap_or_default();
+    let members = build_member_globset(&members);
+    let excluded_members = build_member_globset(&excluded_members);
+
+    let exclude_globset = build_exclude_globset(exclude_patterns).ok();
+    let walk_root = root.to_path_buf();
+    let walker = walk_builder(root)
+        .max_depth(Some(ACEMCP_WORKSPACE_DISCOVERY_DEPTH))
+        .filter_entry(move |entry| {
+            !is_git_dir(entry.path(), &walk_root) && !should_exclude(entry.path(), &walk_root, exclude_globset.as_ref())
+        })
+        .build();
+
+    let mut found = Vec::new();
+    for entry in walker.flatten() {
+        if entry.depth() == 0 || !entry.file_type().is_some_and(|file_type| file_type.is_dir()) {
+            continue;
+        }
+        let path = entry.path();
+        let Some(rel) = relative_to(root, path) else {
+            continue;
+        };
+
+        // 子模块中的 .git 是指向上级仓库的文件
+        let is_git_root = path.join(".git").exists();
+        let is_member = path.join("Cargo.toml").is_file()
+            && members.as_ref().is_some_and(|members| members.is_match(&rel))
+            && !excluded_members.as_ref().is_some_and(|excluded| excluded.is_match(&rel));
+        if is_git_root || is_member {
+            log_debug!("发现嵌套根目录: {} (git={}, cargo={})", rel, is_git_root, is_member);
+            found.push(path.to_path_buf());
+        }
+    }
+
+    found
+}
+
+/// 从 Cargo.toml 的 `[workspace]` 中读取 members 和 exclude
+pub fn parse_workspace_members(manifest: &str) -> (Vec<String>, Vec<String>) {
+    let mut section = String::new();
+    let mut in_workspace = false;
+    for line in manifest.lines() {
+        let line = strip_toml_comment(line);
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') && trimmed.ends_with(']') && !trimmed.contains('"') {
+            in_workspace = trimmed == "[workspace]";
+            continue;
+        }
+        if in_workspace {
+            section.push_str(line);
+            section.push('\n');
+        }
+    }
+
+    let array = |key: &str| -> Vec<String> {
+        let pattern = Regex::new(&format!(r#"(?ms)^\s*{}\s*=\s*\[(.*?)\]"#, key)).expect("数组规则无效");
+        let item = Regex::new(r#""([^"]*)"|'([^']*)'"#).expect("字符串规则无效");
+        pattern
+            .captures(&section)
+            .map(|captures| {
+                item.captures_iter(&captures[1])
+                    .filter_map(|item| item.get(1).or_else(|| item.get(2)))
+                    .map(|value| value.as_str().to_string())
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    (array("members"), array("exclude"))
+}
+
+/// 去掉 TOML 行尾注释，忽略字符串中的 `#`
+fn strip_toml_comment(line: &str) -> &str {
+    let mut quote: Option<char> = None;
+    for (i, ch) in line.char_indices() {
+        match (quote, ch) {
+            (None, '"' | '\'') => quote = Some(ch),
+            (Some(open), _) if ch == open => quote = None,
+            (None, '#') => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+/// Cargo 工作区成员的 glob，`*` 不跨越目录
+fn build_member_globset(patterns: &[String]) -> Option<GlobSet> {
+    if patterns.is_empty() {
+        return None;
+    }
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let pattern = pattern.trim().trim_start_matches("./").trim_end_matches('/');
+        match GlobBuilder::new(pattern).literal_separator(true).build() {
+            Ok(glob) => {
+                builder.add(glob);
+            }
+            Err(e) => log_debug!("无效的工作区成员模式，跳过: {}, 错误: {}", pattern, e),
+        }
+    }
+    builder.build().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::tools::acemcp::results::SearchHit;
+
+    #[test]
+    fn test_parse_workspace_members() {
+        let manifest = r#"
+[package]
+name = "app"
+members = ["not-workspace"]
+
+[workspace]
+default-members = ["crates/cli"]
+members = [
+    "crates/*", # 所有 crate
+    'tools/gen',
+]
+exclude = ["crates/#legacy"]
+
+[dependencies]
+serde = "1"
+"#;
+        let (members, exclude) = parse_workspace_members(manifest);
+        assert_eq!(members, ["crates/*", "tools/gen"]);
+        assert_eq!(exclude, ["crates/#legacy"]);
+        assert_eq!(parse_workspace_members("[package]\nname = \"a\"\n"), (Vec::new(), Vec::new()));
+    }
+
+    #[test]
+    fn test_attribute_hits_by_prefix() {
+        let root = |normalized: &str, prefix: &str| IndexRoot {
+            path: normalized.to_string(),
+            normalized: normalized.to_string(),
+            prefix: prefix.to_string(),
+            covered: false,
+        };
+        let roots = vec![root("/repo", ""), root("/repo/crates/core", "crates/core/"), root("/other", "other/")];
+        let hit = |path: &str| SearchHit {
+            path: path.to_string(),
+            root: None,
+            start_line: Some(1),
+            end_line: Some(1),
+            score: None,
+            snippet: "x".to_string(),
+        };
+
+        let output = attribute_hits(
+            SearchOutput::from_hits(vec![hit("src/main.rs"), hit("crates/core/src/lib.rs"), hit("other/build.rs")]),
+            &roots,
+        );
+        let attributed: Vec<(&str, Option<&str>)> =
+            output.hits.iter().map(|hit| (hit.path.as_str(), hit.root.as_deref())).collect();
+        assert_eq!(
+            attributed,
+            [("src/main.rs", Some("/repo")), ("src/lib.rs", Some("/repo/crates/core")), ("build.rs", Some("/other"))]
+        );
+        assert!(output.text.contains("Path: src/lib.rs\nRoot: /repo/crates/core\n"));
+
+        // 单个根目录时保持不变
+        let single = attribute_hits(SearchOutput::from_hits(vec![hit("src/main.rs")]), &roots[..1]);
+        assert_eq!(single.hits[0].root, None);
+    }
+
+    #[test]
+    fn test_nested_roots_are_indexed_by_outer_root() {
+        let dir = std::env::temp_dir().join(format!("acemcp-roots-{}", std::process::id()));
+        let member = dir.join("crates/core");
+        let other = std::env::temp_dir().join(format!("acemcp-roots-other-{}", std::process::id()));
+        fs::create_dir_all(&member).unwrap();
+        fs::create_dir_all(&other).unwrap();
+
+        let request = AcemcpRequest {
+            project_root_path: dir.to_string_lossy().to_string(),
+            additional_roots: Some(vec![member.to_string_lossy().to_string(), other.to_string_lossy().to_string()]),
+            ..AcemcpRequest::default()
+        };
+        let config: AcemcpConfig = serde_json::from_value(serde_json::json!({})).unwrap();
+        let roots = resolve_roots(&request, &config).unwrap();
+        let summary: Vec<(&str, bool)> = roots.iter().map(|root| (root.prefix.as_str(), root.covered)).collect();
+        let other_prefix = format!("{}/", other.file_name().unwrap().to_string_lossy());
+        assert_eq!(summary, [("", false), ("crates/core/", true), (other_prefix.as_str(), false)]);
+        assert_eq!(indexed_roots(&roots).count(), 2);
+
+        // 主根目录位于额外根目录之内时同样由外层根目录索引
+        let request = AcemcpRequest {
+            project_root_path: member.to_string_lossy().to_string(),
+            additional_roots: Some(vec![dir.to_string_lossy().to_string()]),
+            ..AcemcpRequest::default()
+        };
+        let roots = resolve_roots(&request, &config).unwrap();
+        assert!(roots[0].covered && !roots[1].covered);
+        assert_eq!(roots[0].prefix, format!("{}crates/core/", roots[1].prefix));
+
+        fs::remove_dir_all(dir).unwrap();
+        fs::remove_dir_all(other).unwrap();
+    }
+}
@@ -0,0 +1,187 @@
+// 检索范围
+// 按请求中的 include_globs、exclude_globs、languages 限定参与检索的文件，并限制返回的结果数；
+// 只筛选已有索引中的 blob，不需要重新建立索引
+
+use anyhow::Result;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use std::collections::HashSet;
+
+use super::chunker::blob_file_path;
+use super::results::{format_retrieval, SearchOutput};
+use super::types::AcemcpRequest;
+
+/// 语言名称 → 文件扩展名，未列出的名称按扩展名处理
+const LANGUAGE_EXTENSIONS: &[(&str, &[&str])] = &[
+    ("rust", &["rs"]),
+    ("python", &["py", "pyi"]),
+    ("javascript", &["js", "jsx", "mjs", "cjs"]),
+    ("typescript", &["ts", "tsx", "mts", "cts"]),
+    ("go", &["go"]),
+    ("java", &["java"]),
+    ("kotlin", &["kt", "kts"]),
+    ("csharp", &["cs"]),
+    ("c#", &["cs"]),
+    ("c", &["c", "h"]),
+    ("cpp", &["cpp", "cc", "cxx", "hpp", "hh", "hxx", "h"]),
+    ("c++", &["cpp", "cc", "cxx", "hpp", "hh", "hxx", "h"]),
+    ("ruby", &["rb"]),
+    ("php", &["php"]),
+    ("shell", &["sh", "bash", "zsh"]),
+    ("bash", &["sh", "bash"]),
+    ("markdown", &["md", "markdown", "mdx"]),
+    ("yaml", &["yaml", "yml"]),
+    ("html", &["html", "htm"]),
+    ("css", &["css", "scss"]),
+    ("vue", &["vue"]),
+];
+
+/// 一次检索的范围
+#[derive(Default, Clone)]
+pub struct SearchScope {
+    include: Option<GlobSet>,
+    exclude: Option<GlobSet>,
+    extensions: Option<HashSet<String>>,
+    /// 最多返回的结果数
+    pub max_results: Option<usize>,
+}
+
+impl SearchScope {
+    /// 根据请求参数构建范围，glob 无效时返回错误
+    pub fn from_request(request: &AcemcpRequest) -> Result<Self> {
+        let extensions = request
+            .languages
+            .as_ref()
+            .filter(|languages| !languages.is_empty())
+            .map(|languages| languages.iter().flat_map(|language| language_extensions(language)).collect());
+
+        Ok(Self {
+            include: build_globset(request.include_globs.as_deref())?,
+            exclude: build_globset(request.exclude_globs.as_deref())?,
+            extensions,
+            max_results: request.max_results.map(|max| max.max(1)),
+        })
+    }
+
+    /// 是否限定了文件范围
+    pub fn is_restricted(&self) -> bool {
+        self.include.is_some() || self.exclude.is_some() || self.extensions.is_some()
+    }
+
+    /// blob 所在文件是否在范围内
+    ///
+    /// glob 匹配文件本身或其任一上级目录即视为命中，`src/rust/telegram` 表示该目录下的所有文件
+    pub fn matches(&self, blob_path: &str) -> bool {
+        let path = blob_file_path(blob_path);
+
+        if let Some(extensions) = &self.extensions {
+            let ext = path.rsplit_once('.').map(|(_, ext)| ext.to_lowercase()).unwrap_or_default();
+            if !extensions.contains(&ext) {
+                return false;
+            }
+        }
+        if let Some(include) = &self.include {
+            if !matches_path_or_parent(include, path) {
+                return false;
+            }
+        }
+        if let Some(exclude) = &self.exclude {
+            if matches_path_or_parent(exclude, path) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// 按范围过滤检索结果并截取前 max_results 个，结果有变化时重新生成文本
+    pub fn apply(&self, output: SearchOutput) -> SearchOutput {
+        let total = output.hits.len();
+        let mut hits: Vec<_> = output.hits.into_iter().filter(|hit| self.matches(&hit.path)).collect();
+        if let Some(max_results) = self.max_results {
+            hits.truncate(max_results);
+        }
+
+        if hits.len() == total {
+            SearchOutput { text: output.text, hits }
+        } else {
+            SearchOutput { text: format_retrieval(&hits), hits }
+        }
+    }
+}
+
+/// 语言名称或扩展名对应的扩展名（小写，不含点）
+fn language_extensions(language: &str) -> Vec<String> {
+    let name = language.trim().trim_start_matches('.').to_lowercase();
+    LANGUAGE_EXTENSIONS
+        .iter()
+        .find(|(known, _)| *known == name)
+        .map(|(_, extensions)| extensions.iter().map(|ext| ext.to_string()).collect())
+        .unwrap_or_else(|| vec![name])
+}
+
+/// `*` 不跨越目录，`**` 匹配任意层级
+fn build_globset(patterns: Option<&[String]>) -> Result<Option<GlobSet>> {
+    let Some(patterns) = patterns.filter(|patterns| !patterns.is_empty()) else {
+        return Ok(None);
+    };
+
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let pattern = pattern.trim().trim_start_matches("./").trim_end_matches('/');
+        let glob = GlobBuilder::new(pattern)
+            .literal_separator(true)
+            .build()
+            .map_err(|e| anyhow::anyhow!("无效的 glob 模式 {}: {}", pattern, e))?;
+        builder.add(glob);
+    }
+    Ok(Some(builder.build()?))
+}
+
+fn matches_path_or_parent(globset: &GlobSet, path: &str) -> bool {
+    let mut current = path;
+    loop {
+        if globset.is_match(current) {
+            return true;
+        }
+        match current.rsplit_once('/') {
+            Some((parent, _)) => current = parent,
+            None => return false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(include: &[&str], exclude: &[&str], languages: &[&str]) -> AcemcpRequest {
+        let list = |items: &[&str]| Some(items.iter().map(|s| s.to_string()).collect());
+        AcemcpRequest {
+            include_globs: list(include),
+            exclude_globs: list(exclude),
+            languages: list(languages),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_scope_matches() {
+        let scope = SearchScope::from_request(&request(&["src/rust/telegram"], &[], &[])).unwrap();
+        assert!(scope.matches("src/rust/telegram/core.rs"));
+        assert!(scope.matches("src/rust/telegram/mod.rs#L1-80"));
+        assert!(!scope.matches("src/rust/mcp/server.rs"));
+
+        let scope = SearchScope::from_request(&request(&["src/*.rs", "docs/**"], &["**/tests/**"], &[])).unwrap();
+        assert!(scope.matches("src/main.rs"));
+        assert!(!scope.matches("src/rust/main.rs"));
+        assert!(scope.matches("docs/a/b.md"));
+        assert!(!scope.matches("docs/tests/x.md"));
+
+        let scope = SearchScope::from_request(&request(&[], &[], &["Rust", ".vue"])).unwrap();
+        assert!(scope.matches("src/lib.rs"));
+        assert!(scope.matches("src/App.vue"));
+        assert!(!scope.matches("src/main.ts"));
+
+        assert!(!SearchScope::default().is_restricted());
+        assert!(SearchScope::from_request(&request(&["src/[a"], &[], &[])).is_err());
+    }
+}
@@ -0,0 +1,94 @@
+// 测试用的本地 HTTP 服务
+// 只处理 JSON POST 请求：记录请求路径和请求体，由测试提供的函数生成响应
+
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+type Handler = dyn Fn(&str, &serde_json::Value) -> serde_json::Value + Send + Sync;
+
+pub struct MockServer {
+    /// 服务地址，如 `http://127.0.0.1:12345`
+    pub base_url: String,
+    requests: Arc<Mutex<Vec<(String, serde_json::Value)>>>,
+}
+
+impl MockServer {
+    /// 在随机端口启动服务，每个请求用 handler 根据路径和请求体生成 JSON 响应
+    pub async fn start(handler: impl Fn(&str, &serde_json::Value) -> serde_json::Value + Send + Sync + 'static) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let handler: Arc<Handler> = Arc::new(handler);
+
+        let recorded = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let (handler, recorded) = (handler.clone(), recorded.clone());
+                tokio::spawn(async move {
+                    let _ = serve(stream, handler, recorded).await;
+                });
+            }
+        });
+
+        Self { base_url, requests }
+    }
+
+    /// 发往指定路径的请求体
+    pub fn requests_to(&self, path: &str) -> Vec<serde_json::Value> {
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(request_path, _)| request_path == path)
+            .map(|(_, body)| body.clone())
+            .collect()
+    }
+}
+
+async fn serve(
+    mut stream: TcpStream,
+    handler: Arc<Handler>,
+    recorded: Arc<Mutex<Vec<(String, serde_json::Value)>>>,
+) -> std::io::Result<()> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 8192];
+    let header_end = loop {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(());
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buf[..header_end]).to_string();
+    let path = head.split_whitespace().nth(1).unwrap_or("/").to_string();
+    let content_length = head
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+    while buf.len() < header_end + content_length {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+
+    let body: serde_json::Value = serde_json::from_slice(&buf[header_end..]).unwrap_or_default();
+    let response = handler(&path, &body).to_string();
+    recorded.lock().unwrap().push((path, body));
+
+    let reply = format!(
+        "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+        response.len(),
+        response
+    );
+    stream.write_all(reply.as_bytes()).await?;
+    stream.shutdown().await
+}
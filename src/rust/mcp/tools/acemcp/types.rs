@@ -1,12 +1,40 @@
 use serde::{Deserialize, Serialize};
 
+use super::chunker::ChunkOptions;
+use crate::constants::mcp::{
+    ACEMCP_BACKEND_EMBEDDING, ACEMCP_BACKEND_LOCAL, ACEMCP_BACKEND_REMOTE, ACEMCP_DEFAULT_CHUNK_OVERLAP_LINES, ACEMCP_DEFAULT_EMBEDDING_MODEL,
+    ACEMCP_DEFAULT_EXCLUDE_PATTERNS, ACEMCP_DEFAULT_MAX_BATCH_BYTES, ACEMCP_DEFAULT_TEXT_EXTENSIONS, ACEMCP_DEFAULT_UPLOAD_CONCURRENCY,
+    ACEMCP_SECRET_MODE_REDACT,
+};
+
 /// Acemcp搜索请求参数
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AcemcpRequest {
     /// 项目根目录的绝对路径
     pub project_root_path: String,
     /// 用于查找相关代码上下文的自然语言搜索查询
     pub query: String,
+    /// 是否按代码片段返回结构化结果
+    #[serde(default)]
+    pub structured: Option<bool>,
+    /// 只检索匹配这些 glob 的文件或目录
+    #[serde(default)]
+    pub include_globs: Option<Vec<String>>,
+    /// 不检索匹配这些 glob 的文件或目录
+    #[serde(default)]
+    pub exclude_globs: Option<Vec<String>>,
+    /// 只检索这些语言的文件，可以是语言名称或扩展名
+    #[serde(default)]
+    pub languages: Option<Vec<String>>,
+    /// 最多返回的结果数
+    #[serde(default)]
+    pub max_results: Option<usize>,
+    /// 与项目根目录一起检索的其他根目录
+    #[serde(default)]
+    pub additional_roots: Option<Vec<String>>,
+    /// 是否自动发现项目中的嵌套 git 仓库、子模块和 Cargo 工作区成员
+    #[serde(default)]
+    pub discover_workspace: Option<bool>,
 }
 
 /// Acemcp配置
@@ -20,8 +48,104 @@ pub struct AcemcpConfig {
     pub batch_size: Option<u32>,
     /// 大文件分割前的最大行数
     pub max_lines_per_blob: Option<u32>,
+    /// 相邻代码块重叠的行数
+    pub chunk_overlap_lines: Option<u32>,
     /// 要索引的文件扩展名列表
     pub text_extensions: Option<Vec<String>>,
     /// 要排除的模式列表
     pub exclude_patterns: Option<Vec<String>>,
+    /// 检索后端
+    pub backend: Option<String>,
+    /// 是否在后台监听项目变化并维护索引
+    pub watch: Option<bool>,
+    /// 同时上传的批次数
+    pub upload_concurrency: Option<u32>,
+    /// 每批上传的最大字节数
+    pub max_batch_bytes: Option<u64>,
+    /// 向量检索的 OpenAI 兼容端点URL
+    pub embedding_base_url: Option<String>,
+    /// 向量检索的API密钥
+    pub embedding_token: Option<String>,
+    /// 向量检索使用的模型
+    pub embedding_model: Option<String>,
+    /// 检测到密钥时的处理方式
+    pub secret_mode: Option<String>,
+}
+
+impl AcemcpConfig {
+    /// 实际使用的检索后端
+    ///
+    /// 未显式设置时，配置了远程端点和令牌则使用远程，否则使用本地索引；向量检索需要显式选择
+    pub fn effective_backend(&self) -> &str {
+        match self.backend.as_deref().map(str::trim) {
+            Some(ACEMCP_BACKEND_LOCAL) => ACEMCP_BACKEND_LOCAL,
+            Some(ACEMCP_BACKEND_REMOTE) => ACEMCP_BACKEND_REMOTE,
+            Some(ACEMCP_BACKEND_EMBEDDING) => ACEMCP_BACKEND_EMBEDDING,
+            _ => {
+                let configured = |value: &Option<String>| value.as_deref().is_some_and(|v| !v.trim().is_empty());
+                if configured(&self.base_url) && configured(&self.token) {
+                    ACEMCP_BACKEND_REMOTE
+                } else {
+                    ACEMCP_BACKEND_LOCAL
+                }
+            }
+        }
+    }
+
+    /// 是否启用后台索引，未配置时启用
+    pub fn watch_enabled(&self) -> bool {
+        self.watch.unwrap_or(true)
+    }
+
+    /// 分块参数，未配置时每块 800 行
+    pub fn chunk_options(&self) -> ChunkOptions {
+        ChunkOptions {
+            max_lines: self.max_lines_per_blob.unwrap_or(800).max(1) as usize,
+            overlap_lines: self.chunk_overlap_lines.unwrap_or(ACEMCP_DEFAULT_CHUNK_OVERLAP_LINES) as usize,
+        }
+    }
+
+    /// 同时上传的批次数，至少为 1
+    pub fn upload_concurrency_or_default(&self) -> usize {
+        self.upload_concurrency.unwrap_or(ACEMCP_DEFAULT_UPLOAD_CONCURRENCY).max(1) as usize
+    }
+
+    /// 每批上传的最大字节数
+    pub fn max_batch_bytes_or_default(&self) -> usize {
+        self.max_batch_bytes.unwrap_or(ACEMCP_DEFAULT_MAX_BATCH_BYTES) as usize
+    }
+
+    /// 向量检索使用的模型，未配置时使用默认模型
+    pub fn embedding_model_or_default(&self) -> String {
+        self.embedding_model
+            .as_deref()
+            .map(str::trim)
+            .filter(|model| !model.is_empty())
+            .unwrap_or(ACEMCP_DEFAULT_EMBEDDING_MODEL)
+            .to_string()
+    }
+
+    /// 检测到密钥时的处理方式，未配置时替换为占位符
+    pub fn secret_mode_or_default(&self) -> String {
+        self.secret_mode
+            .as_deref()
+            .map(str::trim)
+            .filter(|mode| !mode.is_empty())
+            .unwrap_or(ACEMCP_SECRET_MODE_REDACT)
+            .to_string()
+    }
+
+    /// 要索引的文件扩展名，未配置时使用默认列表
+    pub fn text_extensions_or_default(&self) -> Vec<String> {
+        self.text_extensions
+            .clone()
+            .unwrap_or_else(|| ACEMCP_DEFAULT_TEXT_EXTENSIONS.iter().map(|s| s.to_string()).collect())
+    }
+
+    /// 排除模式，未配置时使用默认列表
+    pub fn exclude_patterns_or_default(&self) -> Vec<String> {
+        self.exclude_patterns
+            .clone()
+            .unwrap_or_else(|| ACEMCP_DEFAULT_EXCLUDE_PATTERNS.iter().map(|s| s.to_string()).collect())
+    }
 }
@@ -0,0 +1,267 @@
+// 批量上传
+// 按数量和字节数将新增 blob 分批，以限定的并发数上传到 batch-upload 接口；
+// 上传失败的 blob 记入项目上传记录中的重试队列，下次检索时优先重新上传
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use super::index::HashedBlob;
+use super::mcp::{post_json, retry_request};
+use crate::{log_debug, log_important};
+
+/// 上传限制
+#[derive(Debug, Clone, Copy)]
+pub struct UploadLimits {
+    /// 每批最多的 blob 数量
+    pub batch_size: usize,
+    /// 每批最多的字节数（路径与内容长度之和），单个 blob 超出时独占一批
+    pub max_batch_bytes: usize,
+    /// 同时上传的批次数
+    pub concurrency: usize,
+}
+
+/// 上传结果
+#[derive(Default)]
+pub struct UploadOutcome {
+    /// 服务端返回的 blob 名称
+    pub uploaded_names: Vec<String>,
+    /// 上传成功的 blob 哈希
+    pub uploaded_hashes: Vec<String>,
+    /// 上传失败的批次（不含内容）及错误信息
+    pub failed_batches: Vec<(Vec<HashedBlob>, String)>,
+}
+
+impl UploadOutcome {
+    /// 上传失败的 blob 数量
+    pub fn failed_count(&self) -> usize {
+        self.failed_batches.iter().map(|(batch, _)| batch.len()).sum()
+    }
+}
+
+fn blob_bytes(blob: &HashedBlob) -> usize {
+    blob.path.len() + blob.content.as_ref().map_or(0, |content| content.len())
+}
+
+/// 按数量和字节数分批，保持 blob 原有顺序
+pub fn plan_batches(blobs: Vec<HashedBlob>, batch_size: usize, max_batch_bytes: usize) -> Vec<Vec<HashedBlob>> {
+    let batch_size = batch_size.max(1);
+    let mut batches = Vec::new();
+    let mut current: Vec<HashedBlob> = Vec::new();
+    let mut current_bytes = 0usize;
+
+    for blob in blobs {
+        let bytes = blob_bytes(&blob);
+        if !current.is_empty() && (current.len() >= batch_size || current_bytes + bytes > max_batch_bytes) {
+            batches.push(std::mem::take(&mut current));
+            current_bytes = 0;
+        }
+        current_bytes += bytes;
+        current.push(blob);
+    }
+    if !current.is_empty() {
+        batches.push(current);
+    }
+
+    batches
+}
+
+/// 并发上传 blob，返回各批次的结果
+pub async fn upload_blobs(
+    client: &Client,
+    base_url: &str,
+    token: &str,
+    blobs: Vec<HashedBlob>,
+    limits: UploadLimits,
+) -> UploadOutcome {
+    let batches = plan_batches(blobs, limits.batch_size, limits.max_batch_bytes);
+    let total_batches = batches.len();
+    let url = format!("{}/batch-upload", base_url);
+
+    log_important!(info,
+        "目标端点: {}, 总批次: {}, 每批上限: {} 个/{} 字节, 并发数: {}",
+        url,
+        total_batches,
+        limits.batch_size,
+        limits.max_batch_bytes,
+        limits.concurrency
+    );
+
+    let semaphore = Arc::new(Semaphore::new(limits.concurrency.max(1)));
+    let mut tasks = JoinSet::new();
+    for (i, batch) in batches.into_iter().enumerate() {
+        let client = client.clone();
+        let url = url.clone();
+        let token = token.to_string();
+        let semaphore = semaphore.clone();
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            let result = upload_batch(&client, &url, &token, &batch, i + 1, total_batches).await;
+            (batch, result)
+        });
+    }
+
+    let mut outcome = UploadOutcome::default();
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok((batch, Ok(names))) => {
+                outcome.uploaded_names.extend(names);
+                outcome.uploaded_hashes.extend(batch.into_iter().map(|blob| blob.hash));
+            }
+            Ok((batch, Err(e))) => {
+                let batch = batch.into_iter().map(HashedBlob::without_content).collect();
+                outcome.failed_batches.push((batch, e.to_string()));
+            }
+            // 任务异常时该批次既不计入成功也不入队，下次检索会作为新增 blob 重新上传
+            Err(e) => log_important!(warn, "上传任务异常: {}", e),
+        }
+    }
+
+    outcome
+}
+
+/// 上传单个批次，返回服务端确认的 blob 名称
+async fn upload_batch(
+    client: &Client,
+    url: &str,
+    token: &str,
+    batch: &[HashedBlob],
+    index: usize,
+    total_batches: usize,
+) -> anyhow::Result<Vec<String>> {
+    let items: Vec<serde_json::Value> = batch
+        .iter()
+        .map(|blob| serde_json::json!({"path": blob.path, "content": blob.content.as_deref().unwrap_or("")}))
+        .collect();
+    let payload = serde_json::json!({"blobs": items});
+
+    log_important!(info,
+        "上传批次 {}/{}: blobs={}, 载荷大小={} 字节",
+        index,
+        total_batches,
+        batch.len(),
+        payload.to_string().len()
+    );
+    for blob in batch {
+        log_debug!("  批次 {} - Blob: path={}, content_length={}", index, blob.path, blob_bytes(blob) - blob.path.len());
+    }
+
+    let value = retry_request(|| post_json(client, url, token, &payload), 3, 1.0).await.map_err(|e| {
+        log_important!(info, "批次 {} 上传失败: {}", index, e);
+        e
+    })?;
+
+    let names: Vec<String> = value
+        .get("blob_names")
+        .and_then(|v| v.as_array())
+        .map(|arr| arr.iter().filter_map(|v| v.as_str().map(|s| s.to_string())).collect())
+        .unwrap_or_default();
+    if names.is_empty() {
+        log_important!(info, "批次 {} 响应中缺少blob名称", index);
+        anyhow::bail!("响应中缺少blob_names");
+    }
+
+    log_important!(info, "批次 {} 上传成功，获得 {} 个blob名称", index, names.len());
+    Ok(names)
+}
+
+/// 重试队列中的 blob
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct QueuedBlob {
+    pub path: String,
+    /// 累计失败次数
+    pub attempts: u32,
+    /// 最近一次失败的错误信息
+    pub last_error: String,
+}
+
+/// 项目的重试队列：blob 哈希 → 待重试的 blob，保存在项目的上传记录中
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(transparent)]
+pub struct UploadQueue(HashMap<String, QueuedBlob>);
+
+impl UploadQueue {
+    /// 丢弃项目中已不存在的 blob（文件已删除或内容已变化）
+    pub fn retain_current(&mut self, current: &HashSet<String>) {
+        self.0.retain(|hash, _| current.contains(hash));
+    }
+
+    /// blob 是否在等待重试
+    pub fn contains(&self, hash: &str) -> bool {
+        self.0.contains_key(hash)
+    }
+
+    /// 等待重试的 blob 数量
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// 记录上传失败的 blob
+    pub fn record_failure(&mut self, blobs: &[HashedBlob], error: &str) {
+        for blob in blobs {
+            let entry = self.0.entry(blob.hash.clone()).or_insert_with(|| QueuedBlob {
+                path: blob.path.clone(),
+                attempts: 0,
+                last_error: String::new(),
+            });
+            entry.attempts += 1;
+            entry.last_error = error.to_string();
+        }
+    }
+
+    /// 移除已上传成功的 blob
+    pub fn remove(&mut self, hashes: &[String]) {
+        for hash in hashes {
+            self.0.remove(hash);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blob(path: &str, len: usize) -> HashedBlob {
+        HashedBlob { hash: path.to_string(), path: path.to_string(), content: Some("x".repeat(len)) }
+    }
+
+    fn paths(batches: &[Vec<HashedBlob>]) -> Vec<Vec<&str>> {
+        batches.iter().map(|batch| batch.iter().map(|b| b.path.as_str()).collect()).collect()
+    }
+
+    #[test]
+    fn test_plan_batches_by_count_and_bytes() {
+        let blobs = vec![blob("a", 10), blob("b", 10), blob("c", 10), blob("d", 10), blob("e", 10)];
+        assert_eq!(paths(&plan_batches(blobs.clone(), 2, 1000)), [vec!["a", "b"], vec!["c", "d"], vec!["e"]]);
+        // 每个 blob 占 11 字节
+        assert_eq!(paths(&plan_batches(blobs, 10, 30)), [vec!["a", "b"], vec!["c", "d"], vec!["e"]]);
+
+        // 超出字节上限的单个 blob 独占一批
+        let blobs = vec![blob("a", 5), blob("big", 100), blob("c", 5)];
+        assert_eq!(paths(&plan_batches(blobs, 10, 50)), [vec!["a"], vec!["big"], vec!["c"]]);
+    }
+
+    #[test]
+    fn test_queue_record_and_retain() {
+        let mut queue = UploadQueue::default();
+        queue.record_failure(&[blob("a", 1), blob("b", 1)], "HTTP 500");
+        queue.record_failure(&[blob("a", 1)], "timeout");
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.0["a"].attempts, 2);
+        assert_eq!(queue.0["a"].last_error, "timeout");
+
+        queue.retain_current(&["a".to_string()].into_iter().collect());
+        assert!(queue.contains("a"));
+        assert!(!queue.contains("b"));
+
+        queue.remove(&["a".to_string()]);
+        assert!(queue.is_empty());
+    }
+}
@@ -0,0 +1,299 @@
+// 后台增量索引
+// 首次检索某个项目后开始监听其目录，在内存中按文件维护 blob 与哈希；
+// 之后的检索直接使用内存结果，只需计算上传差异并发起查询，无需重新遍历、读取和哈希整个项目
+
+use anyhow::Result;
+use notify::event::ModifyKind;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use super::ignore_rules::is_ignore_file;
+use super::chunker::{blob_file_path, ChunkOptions};
+use super::index::{collect_blobs, FileFilter, HashedBlob};
+use super::roots::IndexRoot;
+use super::types::AcemcpConfig;
+use crate::constants::mcp::{ACEMCP_SECRETS_FILENAME, ACEMCP_WATCH_DEBOUNCE_MS, ACEMCP_WATCH_MAX_DELAY_MS, ACEMCP_WATCH_MAX_PROJECTS};
+use crate::{log_debug, log_important};
+
+/// 影响索引结果的配置，变化后需要重新建立监听
+#[derive(Clone, PartialEq)]
+struct IndexSettings {
+    text_exts: Vec<String>,
+    exclude_patterns: Vec<String>,
+    chunk_options: ChunkOptions,
+    secret_mode: String,
+}
+
+impl IndexSettings {
+    fn file_filter(&self, root: &Path) -> FileFilter {
+        FileFilter::new(root, &self.text_exts, &self.exclude_patterns, &self.secret_mode)
+    }
+}
+
+/// 项目的内存索引
+#[derive(Default)]
+struct ProjectState {
+    /// 相对路径 → 该文件的所有 blob
+    files: HashMap<String, Vec<HashedBlob>>,
+    /// 初次扫描是否完成
+    ready: bool,
+}
+
+impl ProjectState {
+    fn snapshot(&self) -> Vec<HashedBlob> {
+        self.files.values().flatten().cloned().collect()
+    }
+
+    /// 内存中只保留哈希，内容在需要时再读取
+    fn replace_all(&mut self, blobs: Vec<HashedBlob>) {
+        self.files = group_by_file(blobs.into_iter().map(HashedBlob::without_content).collect());
+    }
+}
+
+/// 正在监听的项目，丢弃时监听随之停止，后台线程在事件通道关闭后退出
+struct WatchedProject {
+    settings: IndexSettings,
+    state: Arc<Mutex<ProjectState>>,
+    _watcher: RecommendedWatcher,
+}
+
+fn watched_projects() -> &'static Mutex<HashMap<String, WatchedProject>> {
+    static WATCHED: OnceLock<Mutex<HashMap<String, WatchedProject>>> = OnceLock::new();
+    WATCHED.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// 获取项目当前所有 blob 及其哈希
+///
+/// 项目已在后台监听时直接返回内存中的结果；否则遍历一次项目，并在启用监听时开始后台监听
+pub fn current_blobs(config: &AcemcpConfig, root: &IndexRoot) -> Result<Vec<HashedBlob>> {
+    let settings = IndexSettings {
+        text_exts: config.text_extensions_or_default(),
+        exclude_patterns: config.exclude_patterns_or_default(),
+        chunk_options: config.chunk_options(),
+        secret_mode: config.secret_mode_or_default(),
+    };
+    let project_root_path = root.path.as_str();
+    let normalized_root = root.normalized.as_str();
+
+    if !config.watch_enabled() {
+        return scan(project_root_path, &settings);
+    }
+
+    if let Some(state) = watched_state(normalized_root, &settings) {
+        let state = state.lock().map_err(|e| anyhow::anyhow!("读取后台索引失败: {}", e))?;
+        if state.ready {
+            let blobs = state.snapshot();
+            log_important!(info, "使用后台索引: 项目={}, 文件数={}, blobs数={}", normalized_root, state.files.len(), blobs.len());
+            return Ok(blobs);
+        }
+    }
+
+    // 先开始监听再遍历，遍历期间发生的变化会在遍历完成后补上
+    let state = start_watching(normalized_root, &settings);
+    let blobs = scan(project_root_path, &settings)?;
+    if let Some(state) = state {
+        if let Ok(mut state) = state.lock() {
+            state.replace_all(blobs.clone());
+            state.ready = true;
+        }
+    }
+
+    Ok(blobs)
+}
+
+/// 已在监听且配置一致的项目状态
+fn watched_state(normalized_root: &str, settings: &IndexSettings) -> Option<Arc<Mutex<ProjectState>>> {
+    let watched = watched_projects().lock().ok()?;
+    watched
+        .get(normalized_root)
+        .filter(|project| project.settings == *settings)
+        .map(|project| project.state.clone())
+}
+
+/// 开始监听项目目录，配置变化时替换原有监听；监听失败或数量达到上限时返回 None
+fn start_watching(normalized_root: &str, settings: &IndexSettings) -> Option<Arc<Mutex<ProjectState>>> {
+    let mut watched = watched_projects().lock().ok()?;
+    if let Some(project) = watched.get(normalized_root) {
+        if project.settings == *settings {
+            return Some(project.state.clone());
+        }
+        log_important!(info, "索引配置已变化，重新监听项目: {}", normalized_root);
+        watched.remove(normalized_root);
+    }
+    if watched.len() >= ACEMCP_WATCH_MAX_PROJECTS {
+        log_important!(info, "后台监听的项目数已达上限 {}，不再监听: {}", ACEMCP_WATCH_MAX_PROJECTS, normalized_root);
+        return None;
+    }
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = match notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let _ = tx.send(event);
+    }) {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            log_important!(warn, "创建文件监听失败: {}", e);
+            return None;
+        }
+    };
+    if let Err(e) = watcher.watch(Path::new(normalized_root), RecursiveMode::Recursive) {
+        log_important!(warn, "监听项目目录失败: {}, 错误: {}", normalized_root, e);
+        return None;
+    }
+
+    let state = Arc::new(Mutex::new(ProjectState::default()));
+    let root = PathBuf::from(normalized_root);
+    let thread_state = state.clone();
+    let thread_settings = settings.clone();
+    let spawned = std::thread::Builder::new()
+        .name("acemcp-watcher".to_string())
+        .spawn(move || run_event_loop(root, thread_settings, thread_state, rx));
+    if let Err(e) = spawned {
+        log_important!(warn, "启动后台索引线程失败: {}", e);
+        return None;
+    }
+
+    log_important!(info, "开始后台监听项目: {}", normalized_root);
+    watched.insert(
+        normalized_root.to_string(),
+        WatchedProject { settings: settings.clone(), state: state.clone(), _watcher: watcher },
+    );
+    Some(state)
+}
+
+/// 处理文件变化事件
+///
+/// 短时间内的连续事件合并处理；持续有事件时最多延迟 `ACEMCP_WATCH_MAX_DELAY_MS` 也会处理一次
+fn run_event_loop(
+    root: PathBuf,
+    settings: IndexSettings,
+    state: Arc<Mutex<ProjectState>>,
+    rx: Receiver<notify::Result<notify::Event>>,
+) {
+    let debounce = Duration::from_millis(ACEMCP_WATCH_DEBOUNCE_MS);
+    let max_delay = Duration::from_millis(ACEMCP_WATCH_MAX_DELAY_MS);
+    let mut filter = settings.file_filter(&root);
+    let mut pending: HashSet<PathBuf> = HashSet::new();
+    let mut rescan = false;
+    let mut first_pending_at: Option<Instant> = None;
+
+    loop {
+        match rx.recv_timeout(debounce) {
+            Ok(Ok(event)) => {
+                if matches!(event.kind, EventKind::Access(_)) {
+                    continue;
+                }
+                for path in event.paths {
+                    if is_ignore_file(&path) || path.ends_with(ACEMCP_SECRETS_FILENAME) {
+                        // 忽略规则或密钥检测配置变化会影响整个项目的过滤结果
+                        rescan = true;
+                    } else if path.is_dir() {
+                        // 新建或移入的目录中已有的文件不一定产生事件
+                        if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(_)))
+                            && filter.accepts_dir(&path)
+                        {
+                            rescan = true;
+                        }
+                    } else {
+                        pending.insert(path);
+                    }
+                }
+                first_pending_at.get_or_insert_with(Instant::now);
+                if first_pending_at.is_some_and(|at| at.elapsed() < max_delay) {
+                    continue;
+                }
+            }
+            Ok(Err(e)) => {
+                // 事件丢失时无法确定变化范围，重新遍历
+                log_important!(warn, "文件监听出错，将重新遍历项目: {}", e);
+                rescan = true;
+                first_pending_at.get_or_insert_with(Instant::now);
+                continue;
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        if pending.is_empty() && !rescan {
+            continue;
+        }
+        // 初次扫描完成前保留事件，完成后再处理，避免被扫描结果覆盖
+        if !state.lock().map(|state| state.ready).unwrap_or(false) {
+            continue;
+        }
+
+        if rescan {
+            filter = settings.file_filter(&root);
+            rescan_project(&root, &settings, &state);
+        } else {
+            apply_changes(&filter, &settings, &state, &pending);
+        }
+        pending.clear();
+        rescan = false;
+        first_pending_at = None;
+    }
+
+    log_debug!("后台索引线程退出: {}", root.display());
+}
+
+/// 重新遍历整个项目
+fn rescan_project(root: &Path, settings: &IndexSettings, state: &Mutex<ProjectState>) {
+    match scan(&root.to_string_lossy(), settings) {
+        Ok(blobs) => {
+            if let Ok(mut state) = state.lock() {
+                state.replace_all(blobs);
+            }
+        }
+        Err(e) => log_important!(warn, "后台重新遍历项目失败: {}", e),
+    }
+}
+
+/// 按变化的文件更新内存索引
+fn apply_changes(filter: &FileFilter, settings: &IndexSettings, state: &Mutex<ProjectState>, paths: &HashSet<PathBuf>) {
+    let mut updated: Vec<(String, Option<Vec<HashedBlob>>)> = Vec::new();
+    for path in paths {
+        // 不存在的路径是被删除或移出的文件或目录
+        let blobs = filter
+            .read_blobs(path, settings.chunk_options)
+            .map(|blobs| blobs.into_iter().map(|blob| HashedBlob::new(blob).without_content()).collect());
+        updated.push((filter.relative_path(path), blobs));
+    }
+
+    let Ok(mut state) = state.lock() else {
+        return;
+    };
+    for (rel, blobs) in updated {
+        match blobs {
+            Some(blobs) => {
+                state.files.insert(rel, blobs);
+            }
+            None => {
+                let dir_prefix = format!("{}/", rel);
+                state.files.retain(|file, _| file != &rel && !file.starts_with(&dir_prefix));
+            }
+        }
+    }
+    log_debug!("后台索引已更新: 变化路径数={}, 当前文件数={}", paths.len(), state.files.len());
+}
+
+/// 遍历项目并计算哈希，未变化的文件使用哈希缓存
+fn scan(project_root_path: &str, settings: &IndexSettings) -> Result<Vec<HashedBlob>> {
+    collect_blobs(
+        project_root_path,
+        &settings.text_exts,
+        &settings.exclude_patterns,
+        settings.chunk_options,
+        &settings.secret_mode,
+    )
+}
+
+fn group_by_file(blobs: Vec<HashedBlob>) -> HashMap<String, Vec<HashedBlob>> {
+    let mut files: HashMap<String, Vec<HashedBlob>> = HashMap::new();
+    for blob in blobs {
+        files.entry(blob_file_path(&blob.path).to_string()).or_default().push(blob);
+    }
+    files
+}
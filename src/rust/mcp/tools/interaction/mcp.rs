@@ -2,8 +2,10 @@ use anyhow::Result;
 use rmcp::{Error as McpError, model::*};
 
 use crate::mcp::{ZhiRequest, PopupRequest};
-use crate::mcp::handlers::{create_tauri_popup, parse_mcp_response};
+use crate::mcp::handlers::parse_mcp_response;
 use crate::mcp::utils::{generate_request_id, popup_error};
+use crate::config::load_standalone_config;
+use crate::interaction::{run_with_fallback, select_backends};
 
 /// 🚛 擎天柱 - 领袖级交互核心
 ///
@@ -16,6 +18,8 @@ impl InteractionTool {
     pub async fn zhi(
         request: ZhiRequest,
     ) -> Result<CallToolResult, McpError> {
+        let config = load_standalone_config().unwrap_or_default();
+
         let popup_request = PopupRequest {
             id: generate_request_id(),
             message: request.message,
@@ -25,9 +29,21 @@ impl InteractionTool {
                 Some(request.predefined_options)
             },
             is_markdown: request.is_markdown,
+            timeout_secs: request.timeout_secs.or(Some(config.reply_config.popup_timeout_secs)),
+            timeout_default_option: request.timeout_default_option,
+            // 未指定项目路径时使用服务器的工作目录，MCP 客户端不一定在项目目录中启动寸止
+            project_path: request.project_path.or_else(|| {
+                std::env::current_dir()
+                    .ok()
+                    .map(|dir| dir.to_string_lossy().to_string())
+            }),
+            skip_telegram_sync: false,
         };
 
-        match create_tauri_popup(&popup_request) {
+        // 按路由规则选择交互后端，失败时依次回退
+        let backends = select_backends(&config, &popup_request);
+
+        match run_with_fallback(&popup_request, &backends) {
             Ok(response) => {
                 // 解析响应内容，支持文本和图像情报
                 let content = parse_mcp_response(&response)?;
@@ -0,0 +1,316 @@
+use anyhow::Result;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use super::types::{MemoryCategory, MemoryEntry, MemoryScope};
+use crate::constants::mcp::{MEMORY_BUNDLE_FORMAT, MEMORY_FORMAT_VERSION};
+
+/// 记忆导出包
+///
+/// 不包含导出时间和机器相关路径，条目按分类、创建时间和ID排序，
+/// 相同内容多次导出结果一致，适合提交到仓库并对比差异
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryBundle {
+    pub format: String,
+    pub version: String,
+    pub scope: MemoryScope,
+    pub entries: Vec<MemoryEntry>,
+}
+
+/// 导出包文件格式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BundleFormat {
+    Json,
+    Markdown,
+}
+
+impl BundleFormat {
+    /// 从工具参数解析格式
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "json" => Some(BundleFormat::Json),
+            "markdown" | "md" => Some(BundleFormat::Markdown),
+            _ => None,
+        }
+    }
+
+    /// 根据文件扩展名推断格式，默认 JSON
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("md") || ext.eq_ignore_ascii_case("markdown") => {
+                BundleFormat::Markdown
+            }
+            _ => BundleFormat::Json,
+        }
+    }
+}
+
+/// 导入时ID冲突的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ConflictStrategy {
+    /// 保留更新时间较新的一方
+    #[default]
+    Newer,
+    /// 始终保留本地条目
+    Local,
+    /// 始终使用导入的条目
+    Incoming,
+}
+
+impl ConflictStrategy {
+    /// 从工具参数解析冲突处理方式
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "newer" => Some(ConflictStrategy::Newer),
+            "local" | "skip" => Some(ConflictStrategy::Local),
+            "incoming" | "overwrite" => Some(ConflictStrategy::Incoming),
+            _ => None,
+        }
+    }
+}
+
+/// 导入结果
+#[derive(Debug, Clone, Default)]
+pub struct ImportReport {
+    /// 新增的条目
+    pub added: usize,
+    /// ID冲突时被导入内容覆盖的条目
+    pub updated: usize,
+    /// ID冲突时保留本地的条目
+    pub kept_local: usize,
+    /// 内容与本地其他条目重复而跳过的条目
+    pub duplicates: usize,
+}
+
+/// 检查格式版本是否可以读取，主版本号高于当前版本时无法兼容
+pub fn check_format_version(version: &str) -> Result<()> {
+    let major = |version: &str| version.split('.').next().and_then(|major| major.trim().parse::<u32>().ok());
+
+    match (major(version), major(MEMORY_FORMAT_VERSION)) {
+        (Some(found), Some(supported)) if found <= supported => Ok(()),
+        (Some(_), Some(_)) => anyhow::bail!(
+            "记忆格式版本 {} 高于当前支持的 {}，请升级寸止后再试",
+            version,
+            MEMORY_FORMAT_VERSION
+        ),
+        _ => anyhow::bail!("无法识别的记忆格式版本: {}", version),
+    }
+}
+
+impl MemoryBundle {
+    /// 由记忆条目创建导出包，条目按稳定顺序排列
+    pub fn new(scope: MemoryScope, mut entries: Vec<MemoryEntry>) -> Self {
+        entries.sort_by(|a, b| {
+            category_order(a.category)
+                .cmp(&category_order(b.category))
+                .then_with(|| a.created_at.cmp(&b.created_at))
+                .then_with(|| a.id.cmp(&b.id))
+        });
+
+        Self {
+            format: MEMORY_BUNDLE_FORMAT.to_string(),
+            version: MEMORY_FORMAT_VERSION.to_string(),
+            scope,
+            entries,
+        }
+    }
+
+    /// 按指定格式序列化
+    pub fn render(&self, format: BundleFormat) -> Result<String> {
+        match format {
+            BundleFormat::Json => Ok(format!("{}\n", serde_json::to_string_pretty(self)?)),
+            BundleFormat::Markdown => Ok(self.to_markdown()),
+        }
+    }
+
+    /// 按指定格式解析并检查版本
+    pub fn parse(content: &str, format: BundleFormat) -> Result<Self> {
+        let bundle = match format {
+            BundleFormat::Json => serde_json::from_str::<MemoryBundle>(content)
+                .map_err(|e| anyhow::anyhow!("记忆导出包格式错误: {}", e))?,
+            BundleFormat::Markdown => Self::from_markdown(content)?,
+        };
+
+        if bundle.format != MEMORY_BUNDLE_FORMAT {
+            anyhow::bail!("不是寸止记忆导出包: format = {}", bundle.format);
+        }
+        check_format_version(&bundle.version)?;
+
+        Ok(bundle)
+    }
+
+    /// 生成 Markdown 格式
+    ///
+    /// 每条记忆前的注释保存ID和时间，多行内容缩进两个空格
+    fn to_markdown(&self) -> String {
+        let mut output = format!(
+            "---\nformat: {}\nversion: {}\nscope: {}\n---\n",
+            self.format,
+            self.version,
+            self.scope.key()
+        );
+
+        for category in MemoryCategory::ALL {
+            let entries: Vec<&MemoryEntry> = self.entries.iter().filter(|entry| entry.category == category).collect();
+            if entries.is_empty() {
+                continue;
+            }
+
+            output.push_str(&format!("\n## {}\n", category.key()));
+            for entry in entries {
+                output.push_str(&format!(
+                    "\n<!-- id: {} | created: {} | updated: {} -->\n",
+                    entry.id,
+                    entry.created_at.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+                    entry.updated_at.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+                ));
+                let mut lines = entry.content.lines();
+                output.push_str(&format!("- {}\n", lines.next().unwrap_or_default()));
+                for line in lines {
+                    output.push_str(&format!("  {}\n", line));
+                }
+            }
+        }
+
+        output
+    }
+
+    /// 解析 Markdown 格式
+    fn from_markdown(content: &str) -> Result<Self> {
+        let mut lines = content.lines().peekable();
+        if lines.next().map(str::trim) != Some("---") {
+            anyhow::bail!("记忆导出包缺少头部信息");
+        }
+
+        let mut format = None;
+        let mut version = None;
+        let mut scope = MemoryScope::default();
+        for line in lines.by_ref() {
+            let line = line.trim();
+            if line == "---" {
+                break;
+            }
+            match line.split_once(':').map(|(key, value)| (key.trim(), value.trim())) {
+                Some(("format", value)) => format = Some(value.to_string()),
+                Some(("version", value)) => version = Some(value.to_string()),
+                Some(("scope", value)) => {
+                    scope = MemoryScope::parse(value).ok_or_else(|| anyhow::anyhow!("未知的记忆范围: {}", value))?
+                }
+                _ => {}
+            }
+        }
+
+        let mut entries: Vec<MemoryEntry> = Vec::new();
+        let mut category = None;
+        let mut header: Option<(String, DateTime<Utc>, DateTime<Utc>)> = None;
+
+        while let Some(line) = lines.next() {
+            if let Some(key) = line.strip_prefix("## ") {
+                category = Some(
+                    MemoryCategory::parse(key).ok_or_else(|| anyhow::anyhow!("未知的记忆分类: {}", key.trim()))?,
+                );
+            } else if let Some(comment) = line.trim().strip_prefix("<!--").and_then(|c| c.strip_suffix("-->")) {
+                header = Some(parse_entry_header(comment)?);
+            } else if let Some(first_line) = line.strip_prefix("- ") {
+                let (Some(category), Some((id, created_at, updated_at))) = (category, header.take()) else {
+                    anyhow::bail!("记忆条目缺少分类或ID信息: {}", line);
+                };
+
+                let mut content = first_line.to_string();
+                while let Some(continuation) = lines.peek().and_then(|next| next.strip_prefix("  ")) {
+                    content.push('\n');
+                    content.push_str(continuation);
+                    lines.next();
+                }
+
+                entries.push(MemoryEntry { id, content, category, created_at, updated_at });
+            }
+        }
+
+        Ok(Self {
+            format: format.ok_or_else(|| anyhow::anyhow!("记忆导出包缺少 format"))?,
+            version: version.ok_or_else(|| anyhow::anyhow!("记忆导出包缺少 version"))?,
+            scope,
+            entries,
+        })
+    }
+}
+
+/// 解析条目注释中的ID和时间
+fn parse_entry_header(comment: &str) -> Result<(String, DateTime<Utc>, DateTime<Utc>)> {
+    let mut id = None;
+    let mut created_at = None;
+    let mut updated_at = None;
+
+    for field in comment.split('|') {
+        let Some((key, value)) = field.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim() {
+            "id" => id = Some(value.to_string()),
+            "created" => created_at = Some(DateTime::parse_from_rfc3339(value)?.with_timezone(&Utc)),
+            "updated" => updated_at = Some(DateTime::parse_from_rfc3339(value)?.with_timezone(&Utc)),
+            _ => {}
+        }
+    }
+
+    match (id, created_at, updated_at) {
+        (Some(id), Some(created_at), Some(updated_at)) if !id.is_empty() => Ok((id, created_at, updated_at)),
+        _ => anyhow::bail!("记忆条目信息不完整: {}", comment.trim()),
+    }
+}
+
+fn category_order(category: MemoryCategory) -> usize {
+    MemoryCategory::ALL.iter().position(|c| *c == category).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(content: &str, category: MemoryCategory) -> MemoryEntry {
+        MemoryEntry {
+            id: uuid::Uuid::new_v4().to_string(),
+            content: content.to_string(),
+            category,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_markdown_round_trip() {
+        let bundle = MemoryBundle::new(
+            MemoryScope::Repo,
+            vec![
+                entry("项目使用 Tauri 2", MemoryCategory::Context),
+                entry("不要生成测试脚本\n也不要编译", MemoryCategory::Rule),
+            ],
+        );
+
+        let markdown = bundle.render(BundleFormat::Markdown).unwrap();
+        let parsed = MemoryBundle::parse(&markdown, BundleFormat::Markdown).unwrap();
+
+        assert_eq!(parsed.scope, MemoryScope::Repo);
+        assert_eq!(parsed.entries.len(), 2);
+        for (original, parsed) in bundle.entries.iter().zip(&parsed.entries) {
+            assert_eq!(original.id, parsed.id);
+            assert_eq!(original.content, parsed.content);
+            assert_eq!(original.category, parsed.category);
+            assert_eq!(original.updated_at, parsed.updated_at);
+        }
+
+        // 再次导出结果一致
+        assert_eq!(parsed.render(BundleFormat::Markdown).unwrap(), markdown);
+    }
+
+    #[test]
+    fn test_check_format_version() {
+        assert!(check_format_version("1.0.0").is_ok());
+        assert!(check_format_version(MEMORY_FORMAT_VERSION).is_ok());
+        assert!(check_format_version("99.0.0").is_err());
+        assert!(check_format_version("latest").is_err());
+    }
+}
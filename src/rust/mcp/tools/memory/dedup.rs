@@ -0,0 +1,105 @@
+use std::collections::HashSet;
+
+use super::types::{MemoryCategory, MemoryEntry};
+
+/// 判断两条记忆是否重复的结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Duplicate {
+    /// 规范化后内容完全相同
+    Exact,
+    /// 相似度达到阈值
+    Near(f64),
+}
+
+/// 规范化记忆内容：转小写并去掉空白和标点，只保留文字和数字
+pub fn normalize_content(content: &str) -> String {
+    content
+        .chars()
+        .filter(|ch| ch.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// 基于字符二元组的 Dice 系数计算相似度，取值 0~1
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let a = bigrams(&normalize_content(a));
+    let b = bigrams(&normalize_content(b));
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+
+    let common = a.intersection(&b).count();
+    2.0 * common as f64 / (a.len() + b.len()) as f64
+}
+
+fn bigrams(text: &str) -> HashSet<(char, char)> {
+    let chars: Vec<char> = text.chars().collect();
+    match chars.len() {
+        0 => HashSet::new(),
+        // 单字内容用自身作为二元组，避免无法比较
+        1 => HashSet::from([(chars[0], chars[0])]),
+        _ => chars.windows(2).map(|pair| (pair[0], pair[1])).collect(),
+    }
+}
+
+/// 检查新内容是否与已有记忆重复
+pub fn check_duplicate(existing: &str, content: &str, threshold: f64) -> Option<Duplicate> {
+    let normalized = normalize_content(content);
+    if normalized.is_empty() {
+        return None;
+    }
+    if normalize_content(existing) == normalized {
+        return Some(Duplicate::Exact);
+    }
+
+    let score = similarity(existing, content);
+    (score >= threshold).then_some(Duplicate::Near(score))
+}
+
+/// 在同一分类的记忆中查找与新内容最相似的重复条目，完全相同的优先
+pub fn find_duplicate(
+    entries: &[MemoryEntry],
+    content: &str,
+    category: MemoryCategory,
+    threshold: f64,
+) -> Option<(usize, Duplicate)> {
+    let mut best: Option<(usize, Duplicate)> = None;
+
+    for (index, entry) in entries.iter().enumerate() {
+        if entry.category != category {
+            continue;
+        }
+        match check_duplicate(&entry.content, content, threshold) {
+            Some(Duplicate::Exact) => return Some((index, Duplicate::Exact)),
+            Some(Duplicate::Near(score))
+                if !matches!(best, Some((_, Duplicate::Near(best_score))) if best_score >= score) =>
+            {
+                best = Some((index, Duplicate::Near(score)));
+            }
+            _ => {}
+        }
+    }
+
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_duplicate() {
+        assert_eq!(check_duplicate("不要生成测试脚本", "不要生成测试脚本。", 0.8), Some(Duplicate::Exact));
+        assert_eq!(check_duplicate("Use pnpm", "use  PNPM!", 0.8), Some(Duplicate::Exact));
+
+        assert!(matches!(
+            check_duplicate("不要生成测试脚本", "请不要生成测试脚本", 0.8),
+            Some(Duplicate::Near(_))
+        ));
+        assert_eq!(check_duplicate("不要生成测试脚本", "使用 pnpm 管理依赖", 0.8), None);
+        assert_eq!(check_duplicate("不要生成测试脚本", "   ", 0.8), None);
+
+        // 相似度高不代表含义相同，否定与肯定的表述只算相近
+        assert_eq!(check_duplicate("不要编译", "要编译", 0.8), Some(Duplicate::Near(0.8)));
+    }
+}
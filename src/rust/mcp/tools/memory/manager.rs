@@ -1,34 +1,60 @@
 use anyhow::Result;
-use chrono::Utc;
-use std::fs;
+use chrono::{DateTime, Local, Utc};
+use std::fs::{self, File};
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use super::types::{MemoryEntry, MemoryCategory, MemoryMetadata};
+use super::bundle::{check_format_version, ConflictStrategy, ImportReport, MemoryBundle};
+use super::dedup::{find_duplicate, normalize_content, Duplicate};
+use super::types::{
+    AddMemoryResult, ConsolidationReport, MemoryEntry, MemoryCategory, MemoryMetadata, MemoryScope, MemoryStore,
+};
+use crate::constants::mcp::{MEMORY_FORMAT_VERSION, MEMORY_SIMILARITY_THRESHOLD};
+use crate::utils::{lock_file, write_atomic};
+use crate::{log_debug, log_important};
 
-/// 记忆管理器
+/// 结构化记忆存储文件名
+const STORE_FILE: &str = "memories.json";
+
+/// 存储文件锁，多个进程读改写同一存储时依次进行
+const STORE_LOCK_FILE: &str = "memories.lock";
+
+/// 等待存储文件锁的最长时间
+const STORE_LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Markdown 视图文件顶部的提示
+const VIEW_NOTICE: &str = "<!-- 由 memories.json 生成，请通过记忆工具修改 -->";
+
+/// 记忆管理器，管理单个作用域的记忆
 pub struct MemoryManager {
     memory_dir: PathBuf,
     project_path: String,
+    scope: MemoryScope,
 }
 
 impl MemoryManager {
-    /// 创建新的记忆管理器
+    /// 创建仓库作用域的记忆管理器
     pub fn new(project_path: &str) -> Result<Self> {
-        // 规范化项目路径
-        let normalized_path = Self::normalize_project_path(project_path)?;
-        let memory_dir = normalized_path.join(".cunzhi-memory");
+        Self::with_scope(project_path, MemoryScope::Repo)
+    }
+
+    /// 创建指定作用域的记忆管理器，记忆目录不存在时创建
+    pub fn with_scope(project_path: &str, scope: MemoryScope) -> Result<Self> {
+        let (root, memory_dir) = Self::resolve_scope_dir(project_path, scope)?;
 
-        // 创建记忆目录，如果失败则说明项目不适合使用记忆功能
+        // 创建记忆目录，如果失败则说明目录不适合使用记忆功能
         fs::create_dir_all(&memory_dir)
             .map_err(|e| anyhow::anyhow!(
-                "无法在git项目中创建记忆目录: {}\n错误: {}\n这可能是因为项目目录没有写入权限。",
+                "无法创建{}记忆目录: {}\n错误: {}\n这可能是因为目录没有写入权限。",
+                scope.label(),
                 memory_dir.display(),
                 e
             ))?;
 
         let manager = Self {
             memory_dir,
-            project_path: normalized_path.to_string_lossy().to_string(),
+            project_path: root.to_string_lossy().to_string(),
+            scope,
         };
 
         // 初始化记忆文件结构
@@ -37,6 +63,79 @@ impl MemoryManager {
         Ok(manager)
     }
 
+    /// 打开已存在的作用域，记忆目录不存在时返回 None 且不创建目录
+    pub fn open_existing(project_path: &str, scope: MemoryScope) -> Result<Option<Self>> {
+        let (_, memory_dir) = match Self::resolve_scope_dir(project_path, scope) {
+            Ok(dirs) => dirs,
+            // 目录作用域要求路径位于仓库子目录，不满足时视为不存在
+            Err(_) if scope == MemoryScope::Directory => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        if !memory_dir.is_dir() {
+            return Ok(None);
+        }
+        Self::with_scope(project_path, scope).map(Some)
+    }
+
+    /// 当前作用域
+    pub fn scope(&self) -> MemoryScope {
+        self.scope
+    }
+
+    /// 作用域根目录
+    pub fn root_path(&self) -> &str {
+        &self.project_path
+    }
+
+    /// 计算作用域的根目录和记忆目录
+    fn resolve_scope_dir(project_path: &str, scope: MemoryScope) -> Result<(PathBuf, PathBuf)> {
+        if scope == MemoryScope::User {
+            let root = dirs::home_dir()
+                .ok_or_else(|| anyhow::anyhow!("无法获取用户主目录"))?
+                .join(".cunzhi");
+            let memory_dir = root.join("memory");
+            return Ok((root, memory_dir));
+        }
+
+        let dir = Self::normalize_project_path(project_path)?;
+        // 不在 git 仓库中时以目录本身作为仓库根目录
+        let repo_root = Self::find_git_root(&dir).unwrap_or_else(|| dir.clone());
+
+        let root = match scope {
+            MemoryScope::Directory => {
+                if dir == repo_root {
+                    anyhow::bail!("目录作用域需要仓库内的子目录，当前路径就是仓库根目录: {}", dir.display());
+                }
+                dir
+            }
+            MemoryScope::Workspace => Self::find_workspace_root(&repo_root)?,
+            _ => repo_root,
+        };
+
+        let memory_dir = root.join(".cunzhi-memory");
+        Ok((root, memory_dir))
+    }
+
+    /// 查找工作区根目录：仓库上层最近的已有记忆目录的目录，没有时使用仓库的上级目录
+    fn find_workspace_root(repo_root: &Path) -> Result<PathBuf> {
+        let home = dirs::home_dir();
+
+        for ancestor in repo_root.ancestors().skip(1) {
+            // 主目录及以上不作为工作区，避免与用户作用域混淆
+            if home.as_deref().is_some_and(|home| home.starts_with(ancestor)) {
+                break;
+            }
+            if ancestor.join(".cunzhi-memory").is_dir() {
+                return Ok(ancestor.to_path_buf());
+            }
+        }
+
+        match repo_root.parent() {
+            Some(parent) if !home.as_deref().is_some_and(|home| home.starts_with(parent)) => Ok(parent.to_path_buf()),
+            _ => anyhow::bail!("仓库位于主目录或根目录下，无法确定工作区: {}", repo_root.display()),
+        }
+    }
+
     /// 规范化项目路径
     fn normalize_project_path(project_path: &str) -> Result<PathBuf> {
         // 使用增强的路径解码和规范化功能
@@ -73,16 +172,7 @@ impl MemoryManager {
             return Err(anyhow::anyhow!("项目路径不是目录: {}", canonical_path.display()));
         }
 
-        // 验证是否为 git 根目录或其子目录
-        if let Some(git_root) = Self::find_git_root(&canonical_path) {
-            // 如果找到了 git 根目录，使用 git 根目录作为项目路径
-            Ok(git_root)
-        } else {
-            Err(anyhow::anyhow!(
-                "错误：提供的项目路径不在 git 仓库中。\n路径: {}\n请确保在 git 根目录（包含 .git 文件夹的目录）中调用此功能。",
-                canonical_path.display()
-            ))
-        }
+        Ok(canonical_path)
     }
 
     /// 手动规范化路径
@@ -138,77 +228,285 @@ impl MemoryManager {
     }
 
     /// 初始化记忆文件结构
+    ///
+    /// 首次使用结构化存储时，从旧版 Markdown 文件迁移已有记忆
     fn initialize_memory_structure(&self) -> Result<()> {
-        // 创建各类记忆文件，使用新的结构化格式
-        let categories = [
-            MemoryCategory::Rule,
-            MemoryCategory::Preference,
-            MemoryCategory::Pattern,
-            MemoryCategory::Context,
-        ];
-
-        for category in categories.iter() {
-            let filename = match category {
-                MemoryCategory::Rule => "rules.md",
-                MemoryCategory::Preference => "preferences.md",
-                MemoryCategory::Pattern => "patterns.md",
-                MemoryCategory::Context => "context.md",
-            };
-
-            let file_path = self.memory_dir.join(filename);
-            if !file_path.exists() {
-                let header_content = self.get_category_header(category);
-                fs::write(&file_path, header_content)?;
+        if self.store_path().exists() {
+            return Ok(());
+        }
+
+        // 其他进程可能在等待锁期间完成了迁移
+        let _lock = self.lock_store();
+        if self.store_path().exists() {
+            return Ok(());
+        }
+
+        let mut store = MemoryStore::default();
+        for category in MemoryCategory::ALL {
+            let file_path = self.memory_dir.join(category.filename());
+            if file_path.exists() {
+                store.entries.extend(Self::parse_legacy_memory_file(&file_path, category)?);
             }
         }
 
-        // 创建或更新元数据
-        self.update_metadata()?;
+        if !store.entries.is_empty() {
+            log_important!(info, "已从Markdown迁移 {} 条记忆到 {}", store.entries.len(), STORE_FILE);
+        }
 
-        Ok(())
+        self.save_store(&store, false)
+    }
+
+    /// 结构化存储文件路径
+    fn store_path(&self) -> PathBuf {
+        self.memory_dir.join(STORE_FILE)
+    }
+
+    /// 获取存储文件锁，返回值丢弃前其他进程无法修改存储；超时或不支持文件锁时不加锁继续
+    fn lock_store(&self) -> Option<File> {
+        lock_file(&self.memory_dir.join(STORE_LOCK_FILE), STORE_LOCK_TIMEOUT)
+    }
+
+    /// 读取结构化存储
+    fn load_store(&self) -> Result<MemoryStore> {
+        let store_path = self.store_path();
+        if !store_path.exists() {
+            return Ok(MemoryStore::default());
+        }
+
+        let content = fs::read_to_string(&store_path)?;
+        let store: MemoryStore = serde_json::from_str(&content)
+            .map_err(|e| anyhow::anyhow!("记忆存储文件损坏: {}\n错误: {}", store_path.display(), e))?;
+        check_format_version(&store.version)?;
+
+        Ok(store)
+    }
+
+    /// 写入结构化存储，并重新生成 Markdown 视图和元数据
+    ///
+    /// `organized` 为真时表示本次写入来自整理，会刷新整理时间
+    fn save_store(&self, store: &MemoryStore, organized: bool) -> Result<()> {
+        write_atomic(&self.store_path(), &serde_json::to_string_pretty(store)?)?;
+
+        for category in MemoryCategory::ALL {
+            let view = Self::render_category_view(category, &store.entries);
+            fs::write(self.memory_dir.join(category.filename()), view)?;
+        }
+
+        self.update_metadata(store.entries.len(), organized)
     }
 
     /// 添加记忆条目
-    pub fn add_memory(&self, content: &str, category: MemoryCategory) -> Result<String> {
-        let id = uuid::Uuid::new_v4().to_string();
+    ///
+    /// 只与同一分类的记忆比较：完全相同时不写入；相近时仍然新增，并返回相近的已有条目。
+    /// 相近的表述可能含义相反（如"要编译"与"不要编译"），因此不自动合并
+    pub fn add_memory(&self, content: &str, category: MemoryCategory) -> Result<AddMemoryResult> {
+        let content = content.trim();
+        let _lock = self.lock_store();
+        let mut store = self.load_store()?;
         let now = Utc::now();
 
+        let similar = match find_duplicate(&store.entries, content, category, MEMORY_SIMILARITY_THRESHOLD) {
+            Some((index, Duplicate::Exact)) => return Ok(AddMemoryResult::Duplicate(store.entries[index].clone())),
+            Some((index, Duplicate::Near(score))) => {
+                log_debug!("记忆与 {} 相似度 {:.2}，仍然新增", store.entries[index].id, score);
+                Some((store.entries[index].clone(), score))
+            }
+            None => None,
+        };
+
         let entry = MemoryEntry {
-            id: id.clone(),
+            id: uuid::Uuid::new_v4().to_string(),
             content: content.to_string(),
             category,
             created_at: now,
             updated_at: now,
         };
 
-        // 将记忆添加到对应的文件中
-        self.append_to_category_file(&entry)?;
+        store.entries.push(entry.clone());
+        self.save_store(&store, false)?;
+
+        Ok(match similar {
+            Some((similar, score)) => AddMemoryResult::Similar { entry, similar, score },
+            None => AddMemoryResult::Added(entry),
+        })
+    }
+
+    /// 整理记忆：合并同一分类中内容完全相同的条目、按分类和创建时间排序并重写各分类文件
+    ///
+    /// 相近的条目不合并，只在报告中列出
+    pub fn consolidate(&self) -> Result<ConsolidationReport> {
+        let _lock = self.lock_store();
+        let store = self.load_store()?;
+        let before = store.entries.len();
+
+        let mut entries = store.entries;
+        entries.sort_by_key(|entry| entry.created_at);
+
+        // 保留最早的条目及其ID，后出现的重复条目合并进来
+        let mut kept: Vec<MemoryEntry> = Vec::new();
+        let mut merged = Vec::new();
+        let mut similar = Vec::new();
+        for entry in entries {
+            if entry.content.trim().is_empty() {
+                merged.push(entry);
+                continue;
+            }
+
+            match find_duplicate(&kept, &entry.content, entry.category, MEMORY_SIMILARITY_THRESHOLD) {
+                Some((index, Duplicate::Exact)) => {
+                    let target = &mut kept[index];
+                    target.updated_at = target.updated_at.max(entry.updated_at);
+                    merged.push(entry);
+                }
+                Some((index, Duplicate::Near(_))) => {
+                    similar.push((kept[index].id.clone(), entry.id.clone()));
+                    kept.push(entry);
+                }
+                None => kept.push(entry),
+            }
+        }
+
+        kept.sort_by_key(|entry| {
+            let category_order = MemoryCategory::ALL.iter().position(|c| *c == entry.category).unwrap_or(0);
+            (category_order, entry.created_at)
+        });
 
-        // 更新元数据
-        self.update_metadata()?;
+        let store = MemoryStore { entries: kept, ..MemoryStore::default() };
+        self.save_store(&store, true)?;
 
-        Ok(id)
+        Ok(ConsolidationReport {
+            before,
+            after: store.entries.len(),
+            merged,
+            similar,
+        })
     }
 
-    /// 获取所有记忆
-    pub fn get_all_memories(&self) -> Result<Vec<MemoryEntry>> {
-        let mut memories = Vec::new();
+    /// 导出当前作用域的所有记忆
+    pub fn export_bundle(&self) -> Result<MemoryBundle> {
+        Ok(MemoryBundle::new(self.scope, self.load_store()?.entries))
+    }
 
-        let categories = [
-            (MemoryCategory::Rule, "rules.md"),
-            (MemoryCategory::Preference, "preferences.md"),
-            (MemoryCategory::Pattern, "patterns.md"),
-            (MemoryCategory::Context, "context.md"),
-        ];
+    /// 导入导出包，ID相同的条目按冲突策略处理，与本地同一分类中内容完全相同的新条目跳过
+    pub fn import_bundle(&self, bundle: MemoryBundle, strategy: ConflictStrategy) -> Result<ImportReport> {
+        let _lock = self.lock_store();
+        let mut store = self.load_store()?;
+        let mut report = ImportReport::default();
+
+        for incoming in bundle.entries {
+            if let Some(local) = store.entries.iter_mut().find(|entry| entry.id == incoming.id) {
+                let unchanged = local.content == incoming.content && local.category == incoming.category;
+                let take_incoming = !unchanged
+                    && match strategy {
+                        ConflictStrategy::Newer => incoming.updated_at > local.updated_at,
+                        ConflictStrategy::Local => false,
+                        ConflictStrategy::Incoming => true,
+                    };
 
-        for (category, filename) in categories.iter() {
-            let file_path = self.memory_dir.join(filename);
-            if file_path.exists() {
-                let content = fs::read_to_string(&file_path)?;
-                let entries = self.parse_memory_file(&content, *category)?;
-                memories.extend(entries);
+                if take_incoming {
+                    *local = incoming;
+                    report.updated += 1;
+                } else {
+                    report.kept_local += 1;
+                }
+                continue;
+            }
+
+            let normalized = normalize_content(&incoming.content);
+            if store
+                .entries
+                .iter()
+                .any(|entry| entry.category == incoming.category && normalize_content(&entry.content) == normalized)
+            {
+                report.duplicates += 1;
+                continue;
             }
+
+            store.entries.push(incoming);
+            report.added += 1;
+        }
+
+        if report.added + report.updated > 0 {
+            self.save_store(&store, false)?;
+        }
+
+        Ok(report)
+    }
+
+    /// 更新记忆条目的内容和/或分类
+    pub fn update_memory(
+        &self,
+        id: &str,
+        content: Option<&str>,
+        category: Option<MemoryCategory>,
+    ) -> Result<MemoryEntry> {
+        let _lock = self.lock_store();
+        let mut store = self.load_store()?;
+        let index = Self::find_entry_index(&store, id)?;
+
+        let entry = &mut store.entries[index];
+        if let Some(content) = content {
+            entry.content = content.trim().to_string();
+        }
+        if let Some(category) = category {
+            entry.category = category;
         }
+        entry.updated_at = Utc::now();
+
+        let updated = entry.clone();
+        self.save_store(&store, false)?;
+
+        Ok(updated)
+    }
+
+    /// 删除记忆条目，返回被删除的条目
+    pub fn delete_memory(&self, id: &str) -> Result<MemoryEntry> {
+        let _lock = self.lock_store();
+        let mut store = self.load_store()?;
+        let index = Self::find_entry_index(&store, id)?;
+
+        let removed = store.entries.remove(index);
+        self.save_store(&store, false)?;
+
+        Ok(removed)
+    }
+
+    /// 按ID获取记忆条目
+    pub fn get_memory(&self, id: &str) -> Result<MemoryEntry> {
+        let store = self.load_store()?;
+        let index = Self::find_entry_index(&store, id)?;
+        Ok(store.entries[index].clone())
+    }
+
+    /// 查找条目位置，支持完整ID或唯一的ID前缀
+    fn find_entry_index(store: &MemoryStore, id: &str) -> Result<usize> {
+        let id = id.trim();
+        if id.is_empty() {
+            anyhow::bail!("记忆ID不能为空");
+        }
+
+        if let Some(index) = store.entries.iter().position(|entry| entry.id == id) {
+            return Ok(index);
+        }
+
+        let matches: Vec<usize> = store
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.id.starts_with(id))
+            .map(|(index, _)| index)
+            .collect();
+
+        match matches.as_slice() {
+            [index] => Ok(*index),
+            [] => anyhow::bail!("未找到ID为 {} 的记忆", id),
+            _ => anyhow::bail!("ID前缀 {} 匹配到 {} 条记忆，请提供更长的ID", id, matches.len()),
+        }
+    }
+
+    /// 获取所有记忆
+    pub fn get_all_memories(&self) -> Result<Vec<MemoryEntry>> {
+        let mut memories = self.load_store()?.entries;
 
         // 按更新时间排序
         memories.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
@@ -216,66 +514,74 @@ impl MemoryManager {
         Ok(memories)
     }
 
-    /// 获取指定分类的记忆
+    /// 获取指定分类的记忆，按添加顺序排列
     pub fn get_memories_by_category(&self, category: MemoryCategory) -> Result<Vec<MemoryEntry>> {
-        let filename = match category {
-            MemoryCategory::Rule => "rules.md",
-            MemoryCategory::Preference => "preferences.md",
-            MemoryCategory::Pattern => "patterns.md",
-            MemoryCategory::Context => "context.md",
-        };
-
-        let file_path = self.memory_dir.join(filename);
-        if !file_path.exists() {
-            return Ok(Vec::new());
-        }
-
-        let content = fs::read_to_string(&file_path)?;
-        self.parse_memory_file(&content, category)
+        Ok(self
+            .load_store()?
+            .entries
+            .into_iter()
+            .filter(|entry| entry.category == category)
+            .collect())
     }
 
-    /// 将记忆条目添加到对应分类文件
-    fn append_to_category_file(&self, entry: &MemoryEntry) -> Result<()> {
-        let filename = match entry.category {
-            MemoryCategory::Rule => "rules.md",
-            MemoryCategory::Preference => "preferences.md",
-            MemoryCategory::Pattern => "patterns.md",
-            MemoryCategory::Context => "context.md",
-        };
+    /// 列出记忆条目及其ID，可按分类过滤
+    pub fn list_memories(&self, category: Option<MemoryCategory>) -> Result<String> {
+        let store = self.load_store()?;
+        let mut sections = Vec::new();
 
-        let file_path = self.memory_dir.join(filename);
-        let mut content = if file_path.exists() {
-            fs::read_to_string(&file_path)?
-        } else {
-            format!("# {}\n\n", self.get_category_title(&entry.category))
-        };
+        for current in MemoryCategory::ALL {
+            if category.is_some_and(|category| category != current) {
+                continue;
+            }
 
-        // 简化格式：一行一个记忆
-        content.push_str(&format!("- {}\n", entry.content));
+            let lines: Vec<String> = store
+                .entries
+                .iter()
+                .filter(|entry| entry.category == current)
+                .map(|entry| {
+                    format!(
+                        "- [{}] {} （更新于 {}）",
+                        short_id(&entry.id),
+                        entry.content.split_whitespace().collect::<Vec<&str>>().join(" "),
+                        entry.updated_at.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+                    )
+                })
+                .collect();
+
+            if !lines.is_empty() {
+                sections.push(format!("**{}** ({})\n{}", current.short_name(), lines.len(), lines.join("\n")));
+            }
+        }
 
-        fs::write(&file_path, content)?;
-        Ok(())
+        if sections.is_empty() {
+            return Ok("📭 暂无项目记忆".to_string());
+        }
+        Ok(sections.join("\n\n"))
     }
 
-    /// 解析记忆文件内容 - 简化版本
-    fn parse_memory_file(&self, content: &str, category: MemoryCategory) -> Result<Vec<MemoryEntry>> {
-        let mut memories = Vec::new();
+    /// 解析旧版记忆文件，每个 "- " 开头的行是一个记忆条目
+    ///
+    /// 旧格式不保存时间，使用文件修改时间作为创建时间
+    fn parse_legacy_memory_file(file_path: &Path, category: MemoryCategory) -> Result<Vec<MemoryEntry>> {
+        let content = fs::read_to_string(file_path)?;
+        let timestamp: DateTime<Utc> = fs::metadata(file_path)
+            .and_then(|metadata| metadata.modified())
+            .map(DateTime::from)
+            .unwrap_or_else(|_| Utc::now());
 
-        // 按列表项解析，每个 "- " 开头的行是一个记忆条目
+        let mut memories = Vec::new();
         for line in content.lines() {
             let line = line.trim();
-            if line.starts_with("- ") && line.len() > 2 {
-                let content = line[2..].trim(); // 去掉 "- " 前缀
+            if let Some(content) = line.strip_prefix("- ") {
+                let content = content.trim();
                 if !content.is_empty() {
-                    let entry = MemoryEntry {
+                    memories.push(MemoryEntry {
                         id: uuid::Uuid::new_v4().to_string(),
                         content: content.to_string(),
                         category,
-                        created_at: Utc::now(),
-                        updated_at: Utc::now(),
-                    };
-
-                    memories.push(entry);
+                        created_at: timestamp,
+                        updated_at: timestamp,
+                    });
                 }
             }
         }
@@ -283,28 +589,41 @@ impl MemoryManager {
         Ok(memories)
     }
 
-    /// 获取分类标题
-    fn get_category_title(&self, category: &MemoryCategory) -> &str {
-        match category {
-            MemoryCategory::Rule => "开发规范和规则",
-            MemoryCategory::Preference => "用户偏好设置",
-            MemoryCategory::Pattern => "常用模式和最佳实践",
-            MemoryCategory::Context => "项目上下文信息",
+    /// 生成分类的 Markdown 视图，多行内容缩进到同一列表项下
+    fn render_category_view(category: MemoryCategory, entries: &[MemoryEntry]) -> String {
+        let mut view = format!("# {}\n\n{}\n\n", category.title(), VIEW_NOTICE);
+
+        for entry in entries.iter().filter(|entry| entry.category == category) {
+            let mut lines = entry.content.lines();
+            view.push_str(&format!("- {}\n", lines.next().unwrap_or_default()));
+            for line in lines {
+                view.push_str(&format!("  {}\n", line));
+            }
         }
+
+        view
     }
 
-    /// 获取分类文件头部（简化版本）
-    fn get_category_header(&self, category: &MemoryCategory) -> String {
-        format!("# {}\n\n", self.get_category_title(category))
+    /// 读取元数据，不存在或无法解析时返回 None
+    pub fn get_metadata(&self) -> Option<MemoryMetadata> {
+        let content = fs::read_to_string(self.memory_dir.join("metadata.json")).ok()?;
+        serde_json::from_str(&content).ok()
     }
 
-    /// 更新元数据
-    fn update_metadata(&self) -> Result<()> {
+    /// 更新元数据，只有整理时才刷新整理时间
+    fn update_metadata(&self, total_entries: usize, organized: bool) -> Result<()> {
+        let last_organized = if organized {
+            Some(Utc::now())
+        } else {
+            self.get_metadata().and_then(|metadata| metadata.last_organized)
+        };
+
         let metadata = MemoryMetadata {
             project_path: self.project_path.clone(),
-            last_organized: Utc::now(),
-            total_entries: self.get_all_memories()?.len(),
-            version: "1.0.0".to_string(),
+            scope: self.scope,
+            last_organized,
+            total_entries,
+            version: MEMORY_FORMAT_VERSION.to_string(),
         };
 
         let metadata_path = self.memory_dir.join("metadata.json");
@@ -313,50 +632,167 @@ impl MemoryManager {
 
         Ok(())
     }
+}
+
+/// 展示用的短ID
+pub fn short_id(id: &str) -> &str {
+    id.get(..8).unwrap_or(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 创建带 .git 目录的临时项目
+    fn temp_project() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("cunzhi-memory-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(dir.join(".git")).unwrap();
+        dir
+    }
 
-    /// 获取项目信息供MCP调用方分析 - 压缩简化版本
-    pub fn get_project_info(&self) -> Result<String> {
-        // 汇总所有记忆规则并压缩
-        let all_memories = self.get_all_memories()?;
-        if all_memories.is_empty() {
-            return Ok("📭 暂无项目记忆".to_string());
-        }
+    #[test]
+    fn test_entries_keep_id_across_reloads() {
+        let project = temp_project();
+        let manager = MemoryManager::new(project.to_str().unwrap()).unwrap();
 
-        let mut compressed_info = Vec::new();
-
-        // 按分类压缩汇总
-        let categories = [
-            (MemoryCategory::Rule, "规范"),
-            (MemoryCategory::Preference, "偏好"),
-            (MemoryCategory::Pattern, "模式"),
-            (MemoryCategory::Context, "背景"),
-        ];
-
-        for (category, title) in categories.iter() {
-            let memories = self.get_memories_by_category(*category)?;
-            if !memories.is_empty() {
-                let mut items = Vec::new();
-                for memory in memories {
-                    let content = memory.content.trim();
-                    if !content.is_empty() {
-                        // 去除多余空格和换行，压缩内容
-                        let compressed_content = content
-                            .split_whitespace()
-                            .collect::<Vec<&str>>()
-                            .join(" ");
-                        items.push(compressed_content);
-                    }
-                }
-                if !items.is_empty() {
-                    compressed_info.push(format!("**{}**: {}", title, items.join("; ")));
-                }
-            }
-        }
+        let AddMemoryResult::Added(created) = manager.add_memory("使用 pnpm 管理依赖", MemoryCategory::Rule).unwrap() else {
+            panic!("应新增记忆");
+        };
+        let id = created.id.clone();
 
-        if compressed_info.is_empty() {
-            Ok("📭 暂无有效项目记忆".to_string())
-        } else {
-            Ok(format!("📚 项目记忆总览: {}", compressed_info.join(" | ")))
-        }
+        let manager = MemoryManager::new(project.to_str().unwrap()).unwrap();
+        let updated = manager
+            .update_memory(short_id(&id), Some("使用 pnpm 管理前端依赖"), Some(MemoryCategory::Preference))
+            .unwrap();
+        assert_eq!(updated.id, id);
+        assert_eq!(updated.created_at, created.created_at);
+        assert!(updated.updated_at >= created.updated_at);
+
+        let view = fs::read_to_string(project.join(".cunzhi-memory/preferences.md")).unwrap();
+        assert!(view.contains("- 使用 pnpm 管理前端依赖"));
+
+        manager.delete_memory(&id).unwrap();
+        assert!(manager.get_all_memories().unwrap().is_empty());
+        assert!(manager.get_memory(&id).is_err());
+
+        fs::remove_dir_all(project).unwrap();
+    }
+
+    #[test]
+    fn test_dedup_and_consolidate() {
+        let project = temp_project();
+        let manager = MemoryManager::new(project.to_str().unwrap()).unwrap();
+
+        let AddMemoryResult::Added(first) = manager.add_memory("不要生成测试脚本", MemoryCategory::Rule).unwrap() else {
+            panic!("应新增记忆");
+        };
+        assert!(matches!(
+            manager.add_memory("不要生成测试脚本。", MemoryCategory::Rule).unwrap(),
+            AddMemoryResult::Duplicate(entry) if entry.id == first.id
+        ));
+        let AddMemoryResult::Similar { entry: similar, .. } =
+            manager.add_memory("请不要生成测试脚本", MemoryCategory::Rule).unwrap()
+        else {
+            panic!("相近的记忆应单独新增");
+        };
+        manager.delete_memory(&similar.id).unwrap();
+
+        // 其他分类中的相同内容不算重复
+        let AddMemoryResult::Added(preference) =
+            manager.add_memory("不要生成测试脚本", MemoryCategory::Preference).unwrap()
+        else {
+            panic!("不同分类应新增记忆");
+        };
+        manager.delete_memory(&preference.id).unwrap();
+
+        assert!(manager.get_metadata().unwrap().last_organized.is_none());
+
+        // 绕过写入去重，模拟旧数据中的重复条目
+        let mut store = manager.load_store().unwrap();
+        let mut copy = store.entries[0].clone();
+        copy.id = uuid::Uuid::new_v4().to_string();
+        copy.created_at = Utc::now();
+        store.entries.push(copy);
+        manager.save_store(&store, false).unwrap();
+
+        let report = manager.consolidate().unwrap();
+        assert_eq!((report.before, report.after, report.merged.len()), (2, 1, 1));
+        assert_eq!(manager.get_all_memories().unwrap()[0].id, first.id);
+        assert!(manager.get_metadata().unwrap().last_organized.is_some());
+
+        fs::remove_dir_all(project).unwrap();
+    }
+
+    #[test]
+    fn test_similar_memories_not_merged() {
+        let project = temp_project();
+        let manager = MemoryManager::new(project.to_str().unwrap()).unwrap();
+
+        // 含义相反的相近表述不能覆盖已有记忆
+        let AddMemoryResult::Added(negative) = manager.add_memory("不要编译", MemoryCategory::Rule).unwrap() else {
+            panic!("应新增记忆");
+        };
+        assert!(matches!(
+            manager.add_memory("要编译", MemoryCategory::Rule).unwrap(),
+            AddMemoryResult::Similar { entry, similar, .. } if entry.content == "要编译" && similar.id == negative.id
+        ));
+        let contents: Vec<String> = manager.get_all_memories().unwrap().into_iter().map(|e| e.content).collect();
+        assert!(contents.contains(&"不要编译".to_string()) && contents.contains(&"要编译".to_string()));
+
+        let report = manager.consolidate().unwrap();
+        assert_eq!((report.before, report.after, report.similar.len()), (2, 2, 1));
+
+        fs::remove_dir_all(project).unwrap();
+    }
+
+    #[test]
+    fn test_import_conflicts() {
+        let source = temp_project();
+        let target = temp_project();
+        let source_manager = MemoryManager::new(source.to_str().unwrap()).unwrap();
+        let target_manager = MemoryManager::new(target.to_str().unwrap()).unwrap();
+
+        let AddMemoryResult::Added(shared) = source_manager.add_memory("使用 pnpm", MemoryCategory::Rule).unwrap() else {
+            panic!("应新增记忆");
+        };
+        source_manager.add_memory("项目使用 Tauri", MemoryCategory::Context).unwrap();
+        target_manager.add_memory("项目使用 Tauri", MemoryCategory::Context).unwrap();
+        // 内容相同但分类不同的记忆不算重复
+        target_manager.add_memory("使用 pnpm", MemoryCategory::Context).unwrap();
+
+        let report = target_manager.import_bundle(source_manager.export_bundle().unwrap(), ConflictStrategy::Newer).unwrap();
+        assert_eq!((report.added, report.duplicates), (1, 1));
+
+        // 源端修改后再次导入，较新的内容覆盖本地
+        source_manager.update_memory(&shared.id, Some("使用 pnpm 管理依赖"), None).unwrap();
+        let bundle = source_manager.export_bundle().unwrap();
+
+        let report = target_manager.import_bundle(bundle.clone(), ConflictStrategy::Local).unwrap();
+        assert_eq!((report.updated, report.kept_local), (0, 1));
+        let report = target_manager.import_bundle(bundle, ConflictStrategy::Newer).unwrap();
+        assert_eq!(report.updated, 1);
+        assert_eq!(target_manager.get_memory(&shared.id).unwrap().content, "使用 pnpm 管理依赖");
+
+        fs::remove_dir_all(source).unwrap();
+        fs::remove_dir_all(target).unwrap();
+    }
+
+    #[test]
+    fn test_migrate_legacy_markdown() {
+        let project = temp_project();
+        let memory_dir = project.join(".cunzhi-memory");
+        fs::create_dir_all(&memory_dir).unwrap();
+        fs::write(memory_dir.join("rules.md"), "# 开发规范和规则\n\n- 不要生成测试脚本\n- 不要编译\n").unwrap();
+
+        let manager = MemoryManager::new(project.to_str().unwrap()).unwrap();
+        let rules = manager.get_memories_by_category(MemoryCategory::Rule).unwrap();
+        assert_eq!(rules.len(), 2);
+
+        // 再次加载时ID保持不变
+        let manager = MemoryManager::new(project.to_str().unwrap()).unwrap();
+        let reloaded = manager.get_memories_by_category(MemoryCategory::Rule).unwrap();
+        assert_eq!(rules[0].id, reloaded[0].id);
+
+        fs::remove_dir_all(project).unwrap();
     }
 }
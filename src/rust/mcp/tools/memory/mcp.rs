@@ -1,8 +1,11 @@
 use anyhow::Result;
 use rmcp::{Error as McpError, model::*};
 
-use super::{MemoryManager, MemoryCategory};
-use crate::mcp::{JiyiRequest, utils::{validate_project_path, project_path_error}};
+use std::path::{Path, PathBuf};
+
+use super::{scopes, AddMemoryResult, BundleFormat, ConflictStrategy, MemoryBundle, MemoryManager, MemoryCategory, MemoryScope};
+use crate::constants::mcp::DEFAULT_MEMORY_RECALL_LIMIT;
+use crate::mcp::{JiyiRequest, utils::{decode_and_normalize_path, validate_project_path, project_path_error}};
 
 /// 🚗 大黄蜂 - 忠诚的记忆守护者
 ///
@@ -24,8 +27,20 @@ impl MemoryTool {
             )).into());
         }
 
-        let manager = MemoryManager::new(&request.project_path)
-            .map_err(|e| McpError::internal_error(format!("大黄蜂记忆系统初始化失败: {}", e), None))?;
+        let scope = match request.scope.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+            Some(value) => Some(MemoryScope::parse(value).ok_or_else(|| {
+                McpError::invalid_params(format!("大黄蜂不认识这个情报范围: {}", value), None)
+            })?),
+            None => None,
+        };
+
+        // 分类参数无法识别时报错，避免把更新或过滤静默地应用到错误的分类
+        let category = match request.category.as_deref().map(str::trim).filter(|c| !c.is_empty()) {
+            Some(value) => Some(MemoryCategory::parse(value).ok_or_else(|| {
+                McpError::invalid_params(format!("大黄蜂不认识这个情报分类: {}", value), None)
+            })?),
+            None => None,
+        };
 
         let result = match request.action.as_str() {
             "记忆" => {
@@ -33,23 +48,127 @@ impl MemoryTool {
                     return Err(McpError::invalid_params("大黄蜂需要情报内容才能存储！".to_string(), None));
                 }
 
-                let category = match request.category.as_str() {
-                    "rule" => MemoryCategory::Rule,
-                    "preference" => MemoryCategory::Preference,
-                    "pattern" => MemoryCategory::Pattern,
-                    "context" => MemoryCategory::Context,
-                    _ => MemoryCategory::Context,
-                };
+                let category = category.unwrap_or(MemoryCategory::Context);
 
-                let id = manager.add_memory(&request.content, category)
+                let manager = Self::open_manager(&request.project_path, scope)?;
+                let result = manager.add_memory(&request.content, category)
                     .map_err(|e| McpError::internal_error(format!("大黄蜂存储情报失败: {}", e), None))?;
 
-                format!("🚗 大黄蜂已存储情报！\n📋 情报ID: {}\n📝 内容: {}\n📂 分类: {:?}", id, request.content, category)
+                match result {
+                    AddMemoryResult::Added(entry) => format!(
+                        "🚗 大黄蜂已存储情报！\n📋 情报ID: {}\n📝 内容: {}\n📂 分类: {:?}\n🗺️ 范围: {}",
+                        entry.id, entry.content, entry.category, manager.scope().label()
+                    ),
+                    AddMemoryResult::Duplicate(entry) => format!(
+                        "🚗 大黄蜂已掌握这条情报，无需重复存储\n📋 情报ID: {}\n📝 内容: {}",
+                        entry.id, entry.content
+                    ),
+                    AddMemoryResult::Similar { entry, similar, score } => format!(
+                        "🚗 大黄蜂已存储情报，但发现相似情报（相似度 {:.2}），如重复请删除其一\n📋 情报ID: {}\n📝 内容: {}\n📂 分类: {:?}\n🔍 相似情报ID: {}\n📝 相似内容: {}",
+                        score, entry.id, entry.content, entry.category, similar.id, similar.content
+                    ),
+                }
             }
             "回忆" => {
-                manager.get_project_info()
+                let limit = request.limit.unwrap_or(DEFAULT_MEMORY_RECALL_LIMIT);
+                scopes::recall(&request.project_path, scope, request.query.as_deref(), limit)
                     .map_err(|e| McpError::internal_error(format!("大黄蜂提取情报失败: {}", e), None))?
             }
+            "列表" => {
+                scopes::list(&request.project_path, scope, category)
+                    .map_err(|e| McpError::internal_error(format!("大黄蜂清点情报失败: {}", e), None))?
+            }
+            "更新" => {
+                let id = Self::require_id(&request)?;
+                let content = Some(request.content.as_str()).filter(|c| !c.trim().is_empty());
+                if content.is_none() && category.is_none() {
+                    return Err(McpError::invalid_params("大黄蜂需要新的情报内容或分类才能更新！".to_string(), None));
+                }
+
+                let manager = Self::locate_manager(&request.project_path, scope, id)?;
+                let entry = manager.update_memory(id, content, category)
+                    .map_err(|e| McpError::internal_error(format!("大黄蜂更新情报失败: {}", e), None))?;
+
+                format!("🚗 大黄蜂已更新情报！\n📋 情报ID: {}\n📝 内容: {}\n📂 分类: {:?}", entry.id, entry.content, entry.category)
+            }
+            "删除" => {
+                let id = Self::require_id(&request)?;
+
+                let manager = Self::locate_manager(&request.project_path, scope, id)?;
+                let entry = manager.delete_memory(id)
+                    .map_err(|e| McpError::internal_error(format!("大黄蜂销毁情报失败: {}", e), None))?;
+
+                format!("🚗 大黄蜂已销毁情报！\n📋 情报ID: {}\n📝 内容: {}", entry.id, entry.content)
+            }
+            "整理" => {
+                let manager = Self::open_manager(&request.project_path, scope)?;
+                let report = manager.consolidate()
+                    .map_err(|e| McpError::internal_error(format!("大黄蜂整理情报失败: {}", e), None))?;
+
+                let mut message = format!("🚗 大黄蜂已整理情报！\n📊 {} 条 → {} 条", report.before, report.after);
+                for entry in &report.merged {
+                    message.push_str(&format!("\n🔀 已合并: [{}] {}", entry.id, entry.content));
+                }
+                for (kept, similar) in &report.similar {
+                    message.push_str(&format!("\n🔍 内容相近，未合并: [{}] 与 [{}]", kept, similar));
+                }
+                message
+            }
+            "导出" => {
+                let manager = Self::open_manager(&request.project_path, scope)?;
+                let bundle = manager.export_bundle()
+                    .map_err(|e| McpError::internal_error(format!("大黄蜂导出情报失败: {}", e), None))?;
+
+                match Self::resolve_file_path(&request)? {
+                    Some(path) => {
+                        let format = Self::bundle_format(&request, &path)?;
+                        let content = bundle.render(format)
+                            .map_err(|e| McpError::internal_error(format!("大黄蜂导出情报失败: {}", e), None))?;
+                        std::fs::write(&path, content)
+                            .map_err(|e| McpError::internal_error(format!("大黄蜂无法写入情报包 {}: {}", path.display(), e), None))?;
+
+                        format!("🚗 大黄蜂已导出 {} 条情报\n📦 文件: {}", bundle.entries.len(), path.display())
+                    }
+                    // 未指定文件时直接返回导出内容
+                    None => {
+                        let format = match request.format.as_deref() {
+                            Some(value) => Self::parse_format(value)?,
+                            None => BundleFormat::Json,
+                        };
+                        bundle.render(format)
+                            .map_err(|e| McpError::internal_error(format!("大黄蜂导出情报失败: {}", e), None))?
+                    }
+                }
+            }
+            "导入" => {
+                let path = Self::resolve_file_path(&request)?
+                    .ok_or_else(|| McpError::invalid_params("大黄蜂需要情报包文件路径才能导入！".to_string(), None))?;
+                let format = Self::bundle_format(&request, &path)?;
+                let strategy = match request.conflict.as_deref().map(str::trim).filter(|c| !c.is_empty()) {
+                    Some(value) => ConflictStrategy::parse(value).ok_or_else(|| {
+                        McpError::invalid_params(format!("大黄蜂不认识这个冲突处理方式: {}", value), None)
+                    })?,
+                    None => ConflictStrategy::default(),
+                };
+
+                let content = std::fs::read_to_string(&path)
+                    .map_err(|e| McpError::invalid_params(format!("大黄蜂无法读取情报包 {}: {}", path.display(), e), None))?;
+                let bundle = MemoryBundle::parse(&content, format)
+                    .map_err(|e| McpError::invalid_params(format!("大黄蜂无法解析情报包: {}", e), None))?;
+
+                let manager = Self::open_manager(&request.project_path, scope.or(Some(bundle.scope)))?;
+                let report = manager.import_bundle(bundle, strategy)
+                    .map_err(|e| McpError::internal_error(format!("大黄蜂导入情报失败: {}", e), None))?;
+
+                format!(
+                    "🚗 大黄蜂已导入情报！\n🗺️ 范围: {}\n➕ 新增: {}\n🔄 覆盖: {}\n📌 保留本地: {}\n♻️ 重复跳过: {}",
+                    manager.scope().label(),
+                    report.added,
+                    report.updated,
+                    report.kept_local,
+                    report.duplicates
+                )
+            }
             _ => {
                 return Err(McpError::invalid_params(
                     format!("大黄蜂不理解这个指令: {}", request.action),
@@ -60,4 +179,60 @@ impl MemoryTool {
 
         Ok(CallToolResult::success(vec![Content::text(result)]))
     }
+
+    /// 打开写入目标作用域，默认为仓库作用域
+    fn open_manager(project_path: &str, scope: Option<MemoryScope>) -> Result<MemoryManager, McpError> {
+        MemoryManager::with_scope(project_path, scope.unwrap_or_default())
+            .map_err(|e| McpError::internal_error(format!("大黄蜂记忆系统初始化失败: {}", e), None))
+    }
+
+    /// 定位情报所在的作用域，未指定范围时按优先级在所有作用域中查找
+    fn locate_manager(project_path: &str, scope: Option<MemoryScope>, id: &str) -> Result<MemoryManager, McpError> {
+        match scope {
+            Some(scope) => Self::open_manager(project_path, Some(scope)),
+            None => scopes::find_scope_of(project_path, id)
+                .map_err(|e| McpError::invalid_params(format!("大黄蜂找不到这条情报: {}", e), None)),
+        }
+    }
+
+    /// 解析情报包文件路径，相对路径基于项目路径
+    fn resolve_file_path(request: &JiyiRequest) -> Result<Option<PathBuf>, McpError> {
+        let Some(file_path) = request.file_path.as_deref().map(str::trim).filter(|p| !p.is_empty()) else {
+            return Ok(None);
+        };
+
+        let file_path = decode_and_normalize_path(file_path)
+            .map_err(|e| McpError::invalid_params(format!("大黄蜂无法识别情报包路径: {}", e), None))?;
+        let path = PathBuf::from(file_path);
+        if path.is_absolute() {
+            return Ok(Some(path));
+        }
+
+        let project_path = decode_and_normalize_path(&request.project_path)
+            .map_err(|e| McpError::invalid_params(format!("大黄蜂无法定位作战基地: {}", e), None))?;
+        Ok(Some(Path::new(&project_path).join(path)))
+    }
+
+    /// 情报包格式：优先使用参数，否则按扩展名推断
+    fn bundle_format(request: &JiyiRequest, path: &Path) -> Result<BundleFormat, McpError> {
+        match request.format.as_deref() {
+            Some(value) => Self::parse_format(value),
+            None => Ok(BundleFormat::from_path(path)),
+        }
+    }
+
+    fn parse_format(value: &str) -> Result<BundleFormat, McpError> {
+        BundleFormat::parse(value)
+            .ok_or_else(|| McpError::invalid_params(format!("大黄蜂不认识这个情报包格式: {}", value), None))
+    }
+
+    /// 更新和删除操作需要指定情报ID
+    fn require_id(request: &JiyiRequest) -> Result<&str, McpError> {
+        request
+            .id
+            .as_deref()
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .ok_or_else(|| McpError::invalid_params("大黄蜂需要情报ID才能执行这个指令！".to_string(), None))
+    }
 }
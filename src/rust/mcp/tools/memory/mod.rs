@@ -3,11 +3,16 @@
 //! 负责存储和管理重要的作战规范、盟友偏好和最佳战术
 //! 虽然声带受损，但记忆永不磨灭！
 
+pub mod bundle;
+pub mod dedup;
 pub mod manager;
+pub mod scopes;
+pub mod search;
 pub mod types;
 pub mod mcp;
 
 // 重新导出主要类型和功能
+pub use bundle::{BundleFormat, ConflictStrategy, ImportReport, MemoryBundle};
 pub use manager::MemoryManager;
-pub use types::{MemoryEntry, MemoryCategory, MemoryMetadata};
+pub use types::{AddMemoryResult, ConsolidationReport, MemoryEntry, MemoryCategory, MemoryMetadata, MemoryScope, MemoryStore, ScopedEntry};
 pub use mcp::MemoryTool;
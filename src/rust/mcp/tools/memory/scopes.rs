@@ -0,0 +1,217 @@
+use anyhow::Result;
+
+use super::dedup::{find_duplicate, Duplicate};
+use super::manager::{short_id, MemoryManager};
+use super::search::rank_memories;
+use super::types::{MemoryCategory, MemoryEntry, MemoryScope, ScopedEntry};
+use crate::constants::mcp::MEMORY_SIMILARITY_THRESHOLD;
+
+/// 按优先级从高到低打开路径相关的所有已存在作用域
+pub fn open_scopes(project_path: &str) -> Result<Vec<MemoryManager>> {
+    let mut managers = Vec::new();
+    for scope in MemoryScope::PRECEDENCE {
+        match MemoryManager::open_existing(project_path, scope) {
+            Ok(Some(manager)) => managers.push(manager),
+            Ok(None) => {}
+            // 工作区无法确定时不影响其他作用域
+            Err(_) if scope == MemoryScope::Workspace => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(managers)
+}
+
+/// 合并多个作用域的记忆
+///
+/// `managers` 需按优先级从高到低排列，低优先级作用域中与高优先级同分类内容完全相同的记忆会被覆盖；
+/// 相近但不相同的记忆（如「要编译」与「不要编译」）都会保留
+pub fn merge_scoped_entries(managers: &[MemoryManager]) -> Result<Vec<ScopedEntry>> {
+    let mut merged: Vec<ScopedEntry> = Vec::new();
+    let mut higher: Vec<MemoryEntry> = Vec::new();
+
+    for manager in managers {
+        let entries = manager.get_all_memories()?;
+        for entry in &entries {
+            let overridden = matches!(
+                find_duplicate(&higher, &entry.content, entry.category, MEMORY_SIMILARITY_THRESHOLD),
+                Some((_, Duplicate::Exact))
+            );
+            if !overridden {
+                merged.push(ScopedEntry { scope: manager.scope(), entry: entry.clone() });
+            }
+        }
+        higher.extend(entries);
+    }
+
+    Ok(merged)
+}
+
+/// 按ID在各作用域中查找记忆所在的作用域，按优先级返回第一个匹配的
+pub fn find_scope_of(project_path: &str, id: &str) -> Result<MemoryManager> {
+    open_scopes(project_path)?
+        .into_iter()
+        .find(|manager| manager.get_memory(id).is_ok())
+        .ok_or_else(|| anyhow::anyhow!("未在任何作用域中找到ID为 {} 的记忆", id))
+}
+
+/// 回忆记忆，按分类分组返回
+///
+/// 未指定作用域时合并所有作用域；提供查询时按 BM25 相关度选取前 `limit` 条，否则选取最近更新的 `limit` 条
+pub fn recall(project_path: &str, scope: Option<MemoryScope>, query: Option<&str>, limit: usize) -> Result<String> {
+    let managers = match scope {
+        Some(scope) => MemoryManager::open_existing(project_path, scope)?.into_iter().collect(),
+        None => open_scopes(project_path)?,
+    };
+    let scoped = merge_scoped_entries(&managers)?;
+    if scoped.is_empty() {
+        return Ok("📭 暂无项目记忆".to_string());
+    }
+
+    let total = scoped.len();
+    let limit = limit.max(1);
+    let query = query.map(str::trim).filter(|query| !query.is_empty());
+
+    let selected: Vec<&ScopedEntry> = match query {
+        Some(query) => {
+            let entries: Vec<MemoryEntry> = scoped.iter().map(|scoped| scoped.entry.clone()).collect();
+            rank_memories(&entries, query)
+                .into_iter()
+                .take(limit)
+                .map(|(index, _)| &scoped[index])
+                .collect()
+        }
+        None => {
+            let mut recent: Vec<&ScopedEntry> = scoped.iter().collect();
+            recent.sort_by_key(|scoped| std::cmp::Reverse(scoped.entry.updated_at));
+            recent.truncate(limit);
+            recent
+        }
+    };
+
+    if selected.is_empty() {
+        return Ok(format!("📭 没有与「{}」相关的项目记忆", query.unwrap_or_default()));
+    }
+
+    // 只有一个作用域时不标注来源
+    let show_scope = managers.len() > 1;
+
+    // 按分类分组，组内保持相关度或时间顺序
+    let mut sections = Vec::new();
+    for category in MemoryCategory::ALL {
+        let items: Vec<String> = selected
+            .iter()
+            .filter(|scoped| scoped.entry.category == category)
+            .map(|scoped| {
+                // 去除多余空格和换行，压缩内容
+                let entry = &scoped.entry;
+                let compressed_content = entry.content.split_whitespace().collect::<Vec<&str>>().join(" ");
+                if show_scope {
+                    format!("[{}:{}] {}", scoped.scope.label(), short_id(&entry.id), compressed_content)
+                } else {
+                    format!("[{}] {}", short_id(&entry.id), compressed_content)
+                }
+            })
+            .collect();
+        if !items.is_empty() {
+            sections.push(format!("**{}**: {}", category.short_name(), items.join("; ")));
+        }
+    }
+
+    let header = match query {
+        Some(query) => format!("🔍 与「{}」相关的项目记忆（{}/{} 条）", query, selected.len(), total),
+        None if selected.len() < total => {
+            format!("📚 项目记忆总览（最近更新的 {}/{} 条，可传入 query 检索其他记忆）", selected.len(), total)
+        }
+        None => "📚 项目记忆总览".to_string(),
+    };
+
+    Ok(format!("{}\n{}", header, sections.join("\n")))
+}
+
+/// 列出各作用域的记忆及ID，未指定作用域时列出所有已存在的作用域
+pub fn list(project_path: &str, scope: Option<MemoryScope>, category: Option<MemoryCategory>) -> Result<String> {
+    let managers = match scope {
+        Some(scope) => MemoryManager::open_existing(project_path, scope)?.into_iter().collect(),
+        None => open_scopes(project_path)?,
+    };
+
+    let mut sections = Vec::new();
+    for manager in &managers {
+        let has_entries = manager
+            .get_all_memories()?
+            .iter()
+            .any(|entry| !matches!(category, Some(category) if category != entry.category));
+        if !has_entries {
+            continue;
+        }
+        let listing = manager.list_memories(category)?;
+        sections.push(format!("## {}作用域（{}）\n{}", manager.scope().label(), manager.root_path(), listing));
+    }
+
+    if sections.is_empty() {
+        return Ok("📭 暂无项目记忆".to_string());
+    }
+    Ok(sections.join("\n\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_directory_scope_overrides_repo() {
+        let repo = std::env::temp_dir().join(format!("cunzhi-scope-test-{}", uuid::Uuid::new_v4()));
+        let subdir = repo.join("frontend");
+        fs::create_dir_all(repo.join(".git")).unwrap();
+        fs::create_dir_all(&subdir).unwrap();
+        let subdir_path = subdir.to_str().unwrap();
+
+        let repo_manager = MemoryManager::with_scope(subdir_path, MemoryScope::Repo).unwrap();
+        repo_manager.add_memory("使用 cargo 构建", MemoryCategory::Rule).unwrap();
+        repo_manager.add_memory("提交前运行测试", MemoryCategory::Rule).unwrap();
+        let dir_manager = MemoryManager::with_scope(subdir_path, MemoryScope::Directory).unwrap();
+        dir_manager.add_memory("使用 pnpm 构建", MemoryCategory::Rule).unwrap();
+        dir_manager.add_memory("提交前运行测试！", MemoryCategory::Rule).unwrap();
+
+        assert!(subdir.join(".cunzhi-memory").is_dir());
+        assert!(MemoryManager::with_scope(repo.to_str().unwrap(), MemoryScope::Directory).is_err());
+
+        let merged = merge_scoped_entries(&[dir_manager, repo_manager]).unwrap();
+        assert_eq!(merged.len(), 3);
+        let duplicate: Vec<&ScopedEntry> = merged.iter().filter(|s| s.entry.content.contains("测试")).collect();
+        assert_eq!(duplicate.len(), 1);
+        assert_eq!(duplicate[0].scope, MemoryScope::Directory);
+
+        fs::remove_dir_all(repo).unwrap();
+    }
+
+    #[test]
+    fn test_recall_keeps_similar_memories() {
+        let dir = std::env::temp_dir().join(format!("cunzhi-scope-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.to_str().unwrap();
+
+        let manager = MemoryManager::new(path).unwrap();
+        manager.add_memory("不要编译", MemoryCategory::Rule).unwrap();
+        manager.add_memory("要编译", MemoryCategory::Rule).unwrap();
+
+        let output = recall(path, Some(manager.scope()), None, 10).unwrap();
+        assert!(output.contains("] 不要编译"));
+        assert!(output.contains("] 要编译"));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_non_git_directory() {
+        let dir = std::env::temp_dir().join(format!("cunzhi-scope-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let manager = MemoryManager::new(dir.to_str().unwrap()).unwrap();
+        manager.add_memory("这是普通目录", MemoryCategory::Context).unwrap();
+        assert!(dir.join(".cunzhi-memory/memories.json").is_file());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+
+use super::types::MemoryEntry;
+
+/// BM25 词频饱和参数
+const BM25_K1: f64 = 1.2;
+
+/// BM25 文档长度归一化参数
+const BM25_B: f64 = 0.75;
+
+/// 将文本切分为检索词
+///
+/// 英文和数字按单词切分并转小写，中日韩文字按相邻两字切分（单字成段时保留单字）
+pub fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    let mut cjk_run: Vec<char> = Vec::new();
+
+    for ch in text.chars() {
+        if is_cjk(ch) {
+            flush_word(&mut word, &mut tokens);
+            cjk_run.push(ch);
+        } else if ch.is_alphanumeric() || ch == '_' {
+            flush_cjk(&mut cjk_run, &mut tokens);
+            word.extend(ch.to_lowercase());
+        } else {
+            flush_word(&mut word, &mut tokens);
+            flush_cjk(&mut cjk_run, &mut tokens);
+        }
+    }
+    flush_word(&mut word, &mut tokens);
+    flush_cjk(&mut cjk_run, &mut tokens);
+
+    tokens
+}
+
+fn flush_word(word: &mut String, tokens: &mut Vec<String>) {
+    if !word.is_empty() {
+        tokens.push(std::mem::take(word));
+    }
+}
+
+fn flush_cjk(run: &mut Vec<char>, tokens: &mut Vec<String>) {
+    match run.len() {
+        0 => {}
+        1 => tokens.push(run[0].to_string()),
+        _ => tokens.extend(run.windows(2).map(|pair| pair.iter().collect())),
+    }
+    run.clear();
+}
+
+fn is_cjk(ch: char) -> bool {
+    matches!(ch as u32,
+        0x4E00..=0x9FFF      // 中日韩统一表意文字
+        | 0x3400..=0x4DBF    // 扩展A
+        | 0x3040..=0x30FF    // 平假名、片假名
+        | 0xAC00..=0xD7AF    // 韩文音节
+        | 0xF900..=0xFAFF    // 兼容表意文字
+        | 0x20000..=0x2A6DF  // 扩展B
+    )
+}
+
+/// 用 BM25 对记忆打分，返回得分大于零的条目下标，按得分从高到低排列
+pub fn rank_memories(entries: &[MemoryEntry], query: &str) -> Vec<(usize, f64)> {
+    let mut query_terms = tokenize(query);
+    query_terms.sort();
+    query_terms.dedup();
+    if query_terms.is_empty() || entries.is_empty() {
+        return Vec::new();
+    }
+
+    let documents: Vec<Vec<String>> = entries.iter().map(|entry| tokenize(&entry.content)).collect();
+    let total_docs = documents.len() as f64;
+    let avg_len = (documents.iter().map(Vec::len).sum::<usize>() as f64 / total_docs).max(1.0);
+
+    // 每个查询词出现在多少条记忆中
+    let mut doc_freq: HashMap<&str, usize> = HashMap::new();
+    for document in &documents {
+        for term in &query_terms {
+            if document.contains(term) {
+                *doc_freq.entry(term.as_str()).or_default() += 1;
+            }
+        }
+    }
+
+    let mut ranked: Vec<(usize, f64)> = documents
+        .iter()
+        .enumerate()
+        .map(|(index, document)| {
+            let doc_len = document.len() as f64;
+            let score = query_terms
+                .iter()
+                .map(|term| {
+                    let tf = document.iter().filter(|token| *token == term).count() as f64;
+                    if tf == 0.0 {
+                        return 0.0;
+                    }
+                    let df = doc_freq.get(term.as_str()).copied().unwrap_or(0) as f64;
+                    let idf = (1.0 + (total_docs - df + 0.5) / (df + 0.5)).ln();
+                    idf * tf * (BM25_K1 + 1.0) / (tf + BM25_K1 * (1.0 - BM25_B + BM25_B * doc_len / avg_len))
+                })
+                .sum::<f64>();
+            (index, score)
+        })
+        .filter(|(_, score)| *score > 0.0)
+        .collect();
+
+    // 同分时较新的记忆优先
+    ranked.sort_by(|a, b| {
+        b.1.total_cmp(&a.1)
+            .then_with(|| entries[b.0].updated_at.cmp(&entries[a.0].updated_at))
+    });
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::tools::memory::types::MemoryCategory;
+    use chrono::Utc;
+
+    fn entry(content: &str) -> MemoryEntry {
+        MemoryEntry {
+            id: uuid::Uuid::new_v4().to_string(),
+            content: content.to_string(),
+            category: MemoryCategory::Rule,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(tokenize("使用 PNPM 管理依赖"), vec!["使用", "pnpm", "管理", "理依", "依赖"]);
+        assert_eq!(tokenize("用Vue3写"), vec!["用", "vue3", "写"]);
+        assert!(tokenize("，。!?").is_empty());
+    }
+
+    #[test]
+    fn test_rank_memories() {
+        let entries = vec![
+            entry("不要生成测试脚本"),
+            entry("前端使用 pnpm 管理依赖"),
+            entry("后端依赖通过 cargo 管理，不要随意升级依赖版本"),
+        ];
+
+        let ranked = rank_memories(&entries, "依赖");
+        assert_eq!(ranked.len(), 2);
+        // 词频更高的条目排在前面
+        assert!(entries[ranked[0].0].content.contains("cargo"));
+
+        let ranked = rank_memories(&entries, "pnpm");
+        assert_eq!(ranked.len(), 1);
+        assert!(entries[ranked[0].0].content.contains("pnpm"));
+
+        assert!(rank_memories(&entries, "数据库").is_empty());
+    }
+}
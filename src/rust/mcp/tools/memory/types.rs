@@ -1,6 +1,8 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::constants::mcp::MEMORY_FORMAT_VERSION;
+
 /// 记忆条目结构
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MemoryEntry {
@@ -20,11 +22,185 @@ pub enum MemoryCategory {
     Context,     // 项目上下文信息
 }
 
+impl MemoryCategory {
+    /// 所有分类，按展示顺序排列
+    pub const ALL: [MemoryCategory; 4] = [
+        MemoryCategory::Rule,
+        MemoryCategory::Preference,
+        MemoryCategory::Pattern,
+        MemoryCategory::Context,
+    ];
+
+    /// 从工具参数解析分类
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "rule" => Some(MemoryCategory::Rule),
+            "preference" => Some(MemoryCategory::Preference),
+            "pattern" => Some(MemoryCategory::Pattern),
+            "context" => Some(MemoryCategory::Context),
+            _ => None,
+        }
+    }
+
+    /// 分类标识，与工具参数一致
+    pub fn key(&self) -> &'static str {
+        match self {
+            MemoryCategory::Rule => "rule",
+            MemoryCategory::Preference => "preference",
+            MemoryCategory::Pattern => "pattern",
+            MemoryCategory::Context => "context",
+        }
+    }
+
+    /// 对应的 Markdown 视图文件名
+    pub fn filename(&self) -> &'static str {
+        match self {
+            MemoryCategory::Rule => "rules.md",
+            MemoryCategory::Preference => "preferences.md",
+            MemoryCategory::Pattern => "patterns.md",
+            MemoryCategory::Context => "context.md",
+        }
+    }
+
+    /// 分类标题
+    pub fn title(&self) -> &'static str {
+        match self {
+            MemoryCategory::Rule => "开发规范和规则",
+            MemoryCategory::Preference => "用户偏好设置",
+            MemoryCategory::Pattern => "常用模式和最佳实践",
+            MemoryCategory::Context => "项目上下文信息",
+        }
+    }
+
+    /// 分类简称，用于汇总展示
+    pub fn short_name(&self) -> &'static str {
+        match self {
+            MemoryCategory::Rule => "规范",
+            MemoryCategory::Preference => "偏好",
+            MemoryCategory::Pattern => "模式",
+            MemoryCategory::Context => "背景",
+        }
+    }
+}
+
+/// 记忆作用域
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MemoryScope {
+    User,       // 用户全局，存放在 ~/.cunzhi/memory
+    Workspace,  // 工作区，仓库上层目录，多个仓库共享
+    #[default]
+    Repo,       // 仓库（git 根目录，非 git 目录时为目录本身）
+    Directory,  // 仓库内的子目录
+}
+
+impl MemoryScope {
+    /// 按优先级从高到低排列，回忆时越具体的作用域越优先
+    pub const PRECEDENCE: [MemoryScope; 4] = [
+        MemoryScope::Directory,
+        MemoryScope::Repo,
+        MemoryScope::Workspace,
+        MemoryScope::User,
+    ];
+
+    /// 作用域标识，与工具参数一致
+    pub fn key(&self) -> &'static str {
+        match self {
+            MemoryScope::User => "user",
+            MemoryScope::Workspace => "workspace",
+            MemoryScope::Repo => "repo",
+            MemoryScope::Directory => "directory",
+        }
+    }
+
+    /// 从工具参数解析作用域
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "user" | "global" => Some(MemoryScope::User),
+            "workspace" => Some(MemoryScope::Workspace),
+            "repo" | "project" => Some(MemoryScope::Repo),
+            "directory" | "subdir" => Some(MemoryScope::Directory),
+            _ => None,
+        }
+    }
+
+    /// 作用域名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            MemoryScope::User => "用户",
+            MemoryScope::Workspace => "工作区",
+            MemoryScope::Repo => "仓库",
+            MemoryScope::Directory => "目录",
+        }
+    }
+}
+
+/// 带作用域的记忆条目，用于跨作用域合并
+#[derive(Debug, Clone)]
+pub struct ScopedEntry {
+    pub scope: MemoryScope,
+    pub entry: MemoryEntry,
+}
+
+/// 记忆存储文件内容，是所有记忆的唯一数据来源
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MemoryStore {
+    /// 存储格式版本
+    #[serde(default = "current_format_version")]
+    pub version: String,
+    #[serde(default)]
+    pub entries: Vec<MemoryEntry>,
+}
+
+impl Default for MemoryStore {
+    fn default() -> Self {
+        Self {
+            version: current_format_version(),
+            entries: Vec::new(),
+        }
+    }
+}
+
+fn current_format_version() -> String {
+    MEMORY_FORMAT_VERSION.to_string()
+}
+
+/// 添加记忆的结果
+#[derive(Debug, Clone)]
+pub enum AddMemoryResult {
+    /// 新增了记忆
+    Added(MemoryEntry),
+    /// 与已有记忆完全相同，未写入
+    Duplicate(MemoryEntry),
+    /// 已新增记忆，但与同分类的已有记忆相近，由调用方判断是否需要删除其一
+    Similar {
+        entry: MemoryEntry,
+        similar: MemoryEntry,
+        score: f64,
+    },
+}
+
+/// 整理记忆的结果
+#[derive(Debug, Clone)]
+pub struct ConsolidationReport {
+    pub before: usize,
+    pub after: usize,
+    /// 被合并掉的重复条目
+    pub merged: Vec<MemoryEntry>,
+    /// 内容相近但未合并的条目ID对（保留的条目, 相近的条目）
+    pub similar: Vec<(String, String)>,
+}
+
 /// 记忆元数据
 #[derive(Debug, Serialize, Deserialize)]
 pub struct MemoryMetadata {
     pub project_path: String,
-    pub last_organized: DateTime<Utc>,
+    #[serde(default)]
+    pub scope: MemoryScope,
+    /// 最近一次整理时间，从未整理过时为空
+    #[serde(default)]
+    pub last_organized: Option<DateTime<Utc>>,
     pub total_entries: usize,
+    /// 记忆存储格式版本，与导出包版本一致
     pub version: String,
 }
@@ -11,6 +11,15 @@ pub struct ZhiRequest {
     #[schemars(description = "消息是否为Markdown格式，默认为true")]
     #[serde(default = "default_is_markdown")]
     pub is_markdown: bool,
+    #[schemars(description = "等待用户响应的超时时间（秒），不填使用全局默认值，0 表示一直等待")]
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    #[schemars(description = "超时后自动选择的预定义选项（可选）")]
+    #[serde(default)]
+    pub timeout_default_option: Option<String>,
+    #[schemars(description = "当前项目的绝对路径（可选），用于按项目选择交互方式，不填使用寸止的工作目录")]
+    #[serde(default)]
+    pub project_path: Option<String>,
 }
 
 fn default_is_markdown() -> bool {
@@ -19,18 +28,43 @@ fn default_is_markdown() -> bool {
 
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
 pub struct JiyiRequest {
-    #[schemars(description = "操作类型：记忆(添加记忆), 回忆(获取项目信息)")]
+    #[schemars(
+        description = "操作类型：记忆(添加记忆), 回忆(获取项目信息), 列表(列出记忆及ID), 更新(按ID修改记忆), 删除(按ID删除记忆), 整理(合并重复记忆), 导出(导出为情报包), 导入(从情报包导入)"
+    )]
     pub action: String,
-    #[schemars(description = "项目路径（必需）")]
+    #[schemars(description = "项目路径（必需），可以是 git 仓库、仓库子目录或普通目录")]
     pub project_path: String,
-    #[schemars(description = "记忆内容（记忆操作时必需）")]
+    #[schemars(description = "记忆内容（记忆操作时必需，更新操作时可选）")]
     #[serde(default)]
     pub content: String,
     #[schemars(
-        description = "记忆分类：rule(规范规则), preference(用户偏好), pattern(最佳实践), context(项目上下文)"
+        description = "记忆分类：rule(规范规则), preference(用户偏好), pattern(最佳实践), context(项目上下文)。记忆操作默认 context，列表操作用于过滤，更新操作用于修改分类"
     )]
-    #[serde(default = "default_category")]
-    pub category: String,
+    #[serde(default)]
+    pub category: Option<String>,
+    #[schemars(
+        description = "记忆范围：user(用户全局), workspace(工作区), repo(仓库，默认), directory(子目录)。回忆和列表不指定时合并所有范围，越具体的范围优先"
+    )]
+    #[serde(default)]
+    pub scope: Option<String>,
+    #[schemars(description = "记忆ID（更新、删除操作时必需），可使用列表中显示的ID前缀")]
+    #[serde(default)]
+    pub id: Option<String>,
+    #[schemars(description = "回忆时的检索内容，按相关度返回匹配的记忆；不提供时返回最近更新的记忆")]
+    #[serde(default)]
+    pub query: Option<String>,
+    #[schemars(description = "回忆时返回的最大条数，默认20")]
+    #[serde(default)]
+    pub limit: Option<usize>,
+    #[schemars(description = "导出、导入时的情报包文件路径，相对路径基于项目路径；导出时不提供则直接返回内容")]
+    #[serde(default)]
+    pub file_path: Option<String>,
+    #[schemars(description = "情报包格式：json 或 markdown，默认按文件扩展名推断")]
+    #[serde(default)]
+    pub format: Option<String>,
+    #[schemars(description = "导入时ID冲突的处理方式：newer(保留较新的，默认), local(保留本地), incoming(使用导入的)")]
+    #[serde(default)]
+    pub conflict: Option<String>,
 }
 
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
@@ -39,18 +73,52 @@ pub struct AcemcpRequest {
     pub project_root_path: String,
     #[schemars(description = "用于查找相关代码上下文的自然语言搜索查询")]
     pub query: String,
+    #[schemars(description = "为 true 时按代码片段返回包含路径、行号范围、分数和内容的结构化结果")]
+    #[serde(default)]
+    pub structured: Option<bool>,
+    #[schemars(description = "只检索匹配这些 glob 的文件或目录（相对项目根目录）")]
+    #[serde(default)]
+    pub include_globs: Option<Vec<String>>,
+    #[schemars(description = "不检索匹配这些 glob 的文件或目录（相对项目根目录）")]
+    #[serde(default)]
+    pub exclude_globs: Option<Vec<String>>,
+    #[schemars(description = "只检索这些语言的文件，如 rust、typescript 或扩展名 .vue")]
+    #[serde(default)]
+    pub languages: Option<Vec<String>>,
+    #[schemars(description = "最多返回的结果数")]
+    #[serde(default)]
+    pub max_results: Option<usize>,
+    #[schemars(description = "与项目根目录一起检索的其他根目录（绝对路径），每个根目录单独索引")]
+    #[serde(default)]
+    pub additional_roots: Option<Vec<String>>,
+    #[schemars(description = "为 true 时自动发现项目中的嵌套 git 仓库、子模块和 Cargo 工作区成员并分别索引")]
+    #[serde(default)]
+    pub discover_workspace: Option<bool>,
 }
 
-fn default_category() -> String {
-    "context".to_string()
-}
-
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PopupRequest {
     pub id: String,
     pub message: String,
     pub predefined_options: Option<Vec<String>>,
     pub is_markdown: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_default_option: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project_path: Option<String>, // 发起请求的项目路径，用于选择交互后端
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub skip_telegram_sync: bool, // 广播模式下Telegram独立询问，弹窗不再同步到Telegram
+}
+
+impl PopupRequest {
+    /// 获取等待用户响应的时长，未设置或为 0 时表示一直等待
+    pub fn timeout(&self) -> Option<std::time::Duration> {
+        self.timeout_secs
+            .filter(|secs| *secs > 0)
+            .map(std::time::Duration::from_secs)
+    }
 }
 
 /// 新的结构化响应数据格式
@@ -139,3 +207,40 @@ pub fn build_continue_response(request_id: Option<String>, source: &str) -> Stri
     let response = build_mcp_response(Some(continue_prompt), vec![], vec![], request_id, source);
     response.to_string()
 }
+
+/// 构建超时操作的响应
+///
+/// 请求指定了超时选项时直接选中该选项，否则按 `ReplyConfig.timeout_action` 处理
+pub fn build_timeout_response(request: &PopupRequest) -> String {
+    let timeout_action = crate::config::load_standalone_config()
+        .map(|config| config.reply_config.timeout_action)
+        .unwrap_or_else(|_| crate::constants::mcp::DEFAULT_TIMEOUT_ACTION.to_string());
+    let request_id = Some(request.id.clone());
+
+    let timeout_option = request
+        .timeout_default_option
+        .clone()
+        .filter(|option| !option.trim().is_empty())
+        .or_else(|| {
+            if timeout_action == "option" {
+                request.predefined_options.as_ref().and_then(|options| options.first().cloned())
+            } else {
+                None
+            }
+        });
+
+    if let Some(option) = timeout_option {
+        return build_send_response(None, vec![option], vec![], request_id, "timeout");
+    }
+
+    match timeout_action.as_str() {
+        "no_answer" => {
+            let message = format!(
+                "⏰ 用户在 {} 秒内未响应，本次没有答复",
+                request.timeout_secs.unwrap_or_default()
+            );
+            build_send_response(Some(message), vec![], vec![], request_id, "timeout")
+        }
+        _ => build_continue_response(request_id, "timeout"),
+    }
+}
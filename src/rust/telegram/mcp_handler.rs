@@ -1,9 +1,11 @@
 use anyhow::Result;
 use std::collections::HashSet;
+use std::time::Instant;
 use teloxide::prelude::*;
 
 use crate::config::load_standalone_config;
-use crate::mcp::types::{build_continue_response, build_send_response, PopupRequest};
+use crate::interaction::CancelSignal;
+use crate::mcp::types::{build_continue_response, build_send_response, build_timeout_response, PopupRequest};
 use crate::telegram::{handle_callback_query, handle_text_message, TelegramCore, TelegramEvent};
 use crate::log_important;
 
@@ -13,18 +15,27 @@ pub async fn handle_telegram_only_mcp_request(request_file: &str) -> Result<()>
     let request_json = std::fs::read_to_string(request_file)?;
     let request: PopupRequest = serde_json::from_str(&request_json)?;
 
+    let response = run_telegram_interaction(&request, &CancelSignal::new()).await?;
+
+    // 输出JSON响应到stdout（MCP协议要求）
+    println!("{}", response);
+    Ok(())
+}
+
+/// 通过Telegram完成一次交互，返回MCP响应
+///
+/// `cancel` 被触发（如已在其他渠道答复）时告知用户并返回 "CANCELLED"
+pub async fn run_telegram_interaction(request: &PopupRequest, cancel: &CancelSignal) -> Result<String> {
     // 加载完整配置
     let app_config = load_standalone_config()?;
     let telegram_config = &app_config.telegram_config;
 
     if !telegram_config.enabled {
-        log_important!(warn, "Telegram未启用，无法处理请求");
-        return Ok(());
+        anyhow::bail!("Telegram未启用，无法处理请求");
     }
 
     if telegram_config.bot_token.trim().is_empty() || telegram_config.chat_id.trim().is_empty() {
-        log_important!(warn, "Telegram配置不完整");
-        return Ok(());
+        anyhow::bail!("Telegram配置不完整");
     }
 
     // 创建Telegram核心实例，使用配置中的API URL
@@ -54,20 +65,24 @@ pub async fn handle_telegram_only_mcp_request(request_file: &str) -> Result<()>
     core.send_operation_message(true).await?;
 
     // 启动消息监听循环
-    start_telegram_mcp_listener(core, request, predefined_options).await
+    start_telegram_mcp_listener(core, request, predefined_options, cancel).await
 }
 
 /// 启动Telegram MCP消息监听循环
 async fn start_telegram_mcp_listener(
     core: TelegramCore,
-    request: PopupRequest,
+    request: &PopupRequest,
     predefined_options: Vec<String>,
-) -> Result<()> {
+    cancel: &CancelSignal,
+) -> Result<String> {
     let mut offset = 0i32;
     let mut selected_options: HashSet<String> = HashSet::new();
     let mut user_input = String::new();
     let mut options_message_id: Option<i32> = None;
 
+    // 设置了超时时间时，到期后返回默认答复
+    let deadline = request.timeout().map(|timeout| Instant::now() + timeout);
+
     // 获取当前最新的消息ID作为基准
     if let Ok(updates) = core.bot.get_updates().limit(10).await {
         if let Some(update) = updates.last() {
@@ -77,7 +92,22 @@ async fn start_telegram_mcp_listener(
 
     // 监听循环（简化版本，只等待发送或继续操作）
     loop {
-        match core.bot.get_updates().offset(offset).timeout(10).await {
+        // 长轮询时间不超过剩余等待时间，至少 1 秒，避免最后一秒内连续发起请求
+        let mut poll_timeout = 10u32;
+        if let Some(deadline) = deadline {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return handle_timeout(&core, request).await;
+            }
+            poll_timeout = poll_timeout.min(remaining.as_secs() as u32).max(1);
+        }
+
+        let updates = tokio::select! {
+            _ = cancel.cancelled() => return handle_answered_elsewhere(&core, request).await,
+            updates = async { core.bot.get_updates().offset(offset).timeout(poll_timeout).await } => updates,
+        };
+
+        match updates {
             Ok(updates) => {
                 for update in updates {
                     offset = update.id.0 as i32 + 1;
@@ -96,19 +126,18 @@ async fn start_telegram_mcp_listener(
                         }
                         teloxide::types::UpdateKind::Message(message) => {
                             // 处理选项消息ID识别
-                            if let Err(e) = handle_message_update(
+                            match handle_message_update(
                                 &core,
                                 &message,
                                 &predefined_options,
                                 &mut options_message_id,
                                 &mut user_input,
                                 &selected_options,
-                                &request,
+                                request,
                             ).await {
-                                if let Some(_result) = e.downcast_ref::<ProcessingComplete>() {
-                                    return Ok(());
-                                }
-                                log_important!(warn, "处理消息失败: {}", e);
+                                Ok(Some(response)) => return Ok(response),
+                                Ok(None) => {}
+                                Err(e) => log_important!(warn, "处理消息失败: {}", e),
                             }
                         }
                         _ => {}
@@ -165,7 +194,7 @@ async fn handle_callback_query_update(
     Ok(())
 }
 
-/// 处理消息更新
+/// 处理消息更新，用户发送或继续时返回MCP响应
 async fn handle_message_update(
     core: &TelegramCore,
     message: &teloxide::types::Message,
@@ -174,7 +203,7 @@ async fn handle_message_update(
     user_input: &mut String,
     selected_options: &HashSet<String>,
     request: &PopupRequest,
-) -> Result<()> {
+) -> Result<Option<String>> {
     // 识别选项消息ID
     identify_options_message_id(message, predefined_options, options_message_id);
 
@@ -182,12 +211,12 @@ async fn handle_message_update(
     if let Ok(Some(event)) = handle_text_message(message, core.chat_id, None).await {
         match event {
             TelegramEvent::SendPressed => {
-                handle_send_pressed(core, selected_options, user_input, request).await?;
-                return Err(ProcessingComplete.into());
+                let response = handle_send_pressed(core, selected_options, user_input, request).await;
+                return Ok(Some(response));
             }
             TelegramEvent::ContinuePressed => {
-                handle_continue_pressed(core, request).await?;
-                return Err(ProcessingComplete.into());
+                let response = handle_continue_pressed(core, request).await;
+                return Ok(Some(response));
             }
             TelegramEvent::TextUpdated { text } => {
                 *user_input = text;
@@ -196,7 +225,7 @@ async fn handle_message_update(
         }
     }
 
-    Ok(())
+    Ok(None)
 }
 
 /// 识别选项消息ID
@@ -240,7 +269,7 @@ async fn handle_send_pressed(
     selected_options: &HashSet<String>,
     user_input: &str,
     request: &PopupRequest,
-) -> Result<()> {
+) -> String {
     // 使用统一的响应构建函数
     let selected_list: Vec<String> = selected_options.iter().cloned().collect();
 
@@ -258,9 +287,6 @@ async fn handle_send_pressed(
         "telegram",
     );
 
-    // 输出JSON响应到stdout（MCP协议要求）
-    println!("{}", response);
-
     // 发送确认消息（使用统一的反馈消息生成函数）
     let feedback_message = crate::telegram::core::build_feedback_message(
         &selected_list,
@@ -269,23 +295,20 @@ async fn handle_send_pressed(
     );
     let _ = core.send_message(&feedback_message).await;
 
-    Ok(())
+    response
 }
 
 /// 处理继续按钮按下
 async fn handle_continue_pressed(
     core: &TelegramCore,
     request: &PopupRequest,
-) -> Result<()> {
+) -> String {
     // 使用统一的继续响应构建函数
     let response = build_continue_response(
         Some(request.id.clone()),
         "telegram_continue",
     );
 
-    // 输出JSON响应到stdout（MCP协议要求）
-    println!("{}", response);
-
     // 发送确认消息（使用统一的反馈消息生成函数）
     let feedback_message = crate::telegram::core::build_feedback_message(
         &[],  // 继续操作没有选项
@@ -294,17 +317,23 @@ async fn handle_continue_pressed(
     );
     let _ = core.send_message(&feedback_message).await;
 
-    Ok(())
+    response
 }
 
-/// 处理完成标记（用于从监听循环中退出）
-#[derive(Debug)]
-struct ProcessingComplete;
+/// 处理已在其他渠道答复的情况
+async fn handle_answered_elsewhere(core: &TelegramCore, request: &PopupRequest) -> Result<String> {
+    log_important!(info, "请求 {} 已在其他渠道答复，停止Telegram等待", request.id);
+    let _ = core.send_message("✅ 已在其他渠道答复，本次请求已结束").await;
 
-impl std::fmt::Display for ProcessingComplete {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Processing complete")
-    }
+    Ok("CANCELLED".to_string())
 }
 
-impl std::error::Error for ProcessingComplete {}
+/// 处理等待超时
+async fn handle_timeout(core: &TelegramCore, request: &PopupRequest) -> Result<String> {
+    let response = build_timeout_response(request);
+
+    log_important!(warn, "Telegram请求 {} 等待超时，已返回默认答复", request.id);
+    let _ = core.send_message("⏰ 等待超时，已自动返回默认答复").await;
+
+    Ok(response)
+}
@@ -11,4 +11,4 @@ pub use core::{
 };
 pub use integration::TelegramIntegration;
 pub use markdown::process_telegram_markdown;
-pub use mcp_handler::handle_telegram_only_mcp_request;
+pub use mcp_handler::{handle_telegram_only_mcp_request, run_telegram_interaction};
@@ -2,6 +2,7 @@ use crate::config::{save_config, load_config, AppState, ReplyConfig, WindowConfi
 use crate::constants::{window, ui, validation};
 use crate::mcp::types::{build_continue_response, build_send_response, ImageAttachment, PopupRequest};
 use crate::mcp::handlers::create_tauri_popup;
+use crate::interaction::CancelSignal;
 use tauri::{AppHandle, Manager, State};
 
 #[tauri::command]
@@ -382,14 +383,11 @@ pub async fn send_mcp_response(
     }
 
     // 检查是否为MCP模式
-    let args: Vec<String> = std::env::args().collect();
-    let is_mcp_mode = args.len() >= 3 && args[1] == "--mcp-request";
+    let is_mcp_mode = crate::app::mcp_request().is_some();
 
     if is_mcp_mode {
-        // MCP模式：直接输出到stdout（MCP协议要求）
-        println!("{}", response_str);
-        std::io::Write::flush(&mut std::io::stdout())
-            .map_err(|e| format!("刷新stdout失败: {}", e))?;
+        // MCP模式：直接输出到stdout（MCP协议要求），已超时返回默认答复时忽略
+        crate::app::respond_mcp_request(&response_str);
     } else {
         // 通过channel发送响应（如果有的话）
         let sender = {
@@ -410,15 +408,14 @@ pub async fn send_mcp_response(
 
 #[tauri::command]
 pub fn get_cli_args() -> Result<serde_json::Value, String> {
-    let args: Vec<String> = std::env::args().collect();
     let mut result = serde_json::Map::new();
 
-    // 检查是否有 --mcp-request 参数
-    if args.len() >= 3 && args[1] == "--mcp-request" {
-        result.insert(
-            "mcp_request".to_string(),
-            serde_json::Value::String(args[2].clone()),
-        );
+    // 检查是否在处理 MCP 请求（--mcp-request 参数或 popup 子命令），请求不来自文件时为 "-"
+    if crate::app::mcp_request().is_some() {
+        let source = crate::app::mcp_request_file()
+            .map(|file| file.to_string_lossy().to_string())
+            .unwrap_or_else(|| "-".to_string());
+        result.insert("mcp_request".to_string(), serde_json::Value::String(source));
     }
 
     Ok(serde_json::Value::Object(result))
@@ -426,6 +423,11 @@ pub fn get_cli_args() -> Result<serde_json::Value, String> {
 
 #[tauri::command]
 pub fn read_mcp_request(file_path: String) -> Result<serde_json::Value, String> {
+    // 当前进程处理的请求已在启动时读取，popup 子命令构造的请求不写入文件
+    if let Some(request) = crate::app::mcp_request() {
+        return serde_json::to_value(request).map_err(|e| format!("序列化请求失败: {}", e));
+    }
+
     if !std::path::Path::new(&file_path).exists() {
         return Err(format!("文件不存在: {}", file_path));
     }
@@ -483,6 +485,14 @@ pub async fn open_external_url(url: String) -> Result<(), String> {
 
 #[tauri::command]
 pub async fn exit_app(app: AppHandle) -> Result<(), String> {
+    // 常驻模式下只隐藏窗口，等待下一个弹窗请求
+    if crate::daemon::is_daemon_mode() {
+        if let Some(window) = app.get_webview_window("main") {
+            window.hide().map_err(|e| format!("隐藏窗口失败: {}", e))?;
+        }
+        return Ok(());
+    }
+
     // 直接调用强制退出，用于程序内部的退出操作（如MCP响应后退出）
     crate::ui::exit::force_exit_app(app).await
 }
@@ -530,7 +540,7 @@ pub async fn create_test_popup(request: serde_json::Value) -> Result<String, Str
         .map_err(|e| format!("解析请求参数失败: {}", e))?;
 
     // 调用现有的popup创建函数
-    match create_tauri_popup(&popup_request) {
+    match create_tauri_popup(&popup_request, &CancelSignal::new()) {
         Ok(response) => Ok(response),
         Err(e) => Err(format!("创建测试popup失败: {}", e))
     }
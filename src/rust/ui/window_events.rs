@@ -13,6 +13,12 @@ pub fn setup_window_event_listeners(app_handle: &AppHandle) {
                 api.prevent_close();
                 
                 let app_handle = app_handle_clone.clone();
+
+                // 常驻模式下关闭窗口等同于取消当前弹窗，进程继续运行
+                if crate::daemon::is_daemon_mode() {
+                    cancel_pending_daemon_popup(&app_handle);
+                    return;
+                }
                 
                 // 异步处理退出请求
                 tauri::async_runtime::spawn(async move {
@@ -42,3 +48,16 @@ pub fn setup_window_event_listeners(app_handle: &AppHandle) {
         });
     }
 }
+
+/// 取消常驻模式下正在显示的弹窗并隐藏窗口
+fn cancel_pending_daemon_popup(app_handle: &AppHandle) {
+    let state = app_handle.state::<AppState>();
+    let sender = state.response_channel.lock().ok().and_then(|mut channel| channel.take());
+    if let Some(sender) = sender {
+        let _ = sender.send("CANCELLED".to_string());
+    }
+
+    if let Some(window) = app_handle.get_webview_window("main") {
+        let _ = window.hide();
+    }
+}
@@ -0,0 +1,64 @@
+// 多个进程共用的数据文件的写入工具
+// 写入临时文件并刷盘后重命名替换，中断时不会留下写了一半的文件；需要读改写时配合文件锁使用
+
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::log_important;
+
+/// 同一进程内临时文件的序号，避免并发写入同一文件时共用临时文件
+static TMP_SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+/// 等待文件锁时的轮询间隔
+const LOCK_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// 写入同目录下的临时文件并刷盘，再重命名替换目标文件
+///
+/// 临时文件名包含进程号和进程内序号，多个进程或线程同时写入同一文件时互不覆盖临时文件
+pub fn write_atomic(path: &Path, data: &str) -> std::io::Result<()> {
+    let file_name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    let sequence = TMP_SEQUENCE.fetch_add(1, Ordering::Relaxed);
+    let tmp_path = path.with_file_name(format!(".{}.{}.{}.tmp", file_name, std::process::id(), sequence));
+    let result = (|| {
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(data.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result
+}
+
+/// 获取文件锁，其他进程持有锁时等待，超时或不支持文件锁时返回 None
+///
+/// 返回的文件丢弃时释放锁；持有锁的进程退出时系统自动释放
+pub fn lock_file(lock_path: &Path, timeout: Duration) -> Option<File> {
+    let file = match OpenOptions::new().create(true).truncate(false).write(true).open(lock_path) {
+        Ok(file) => file,
+        Err(e) => {
+            log_important!(warn, "打开锁文件失败，不加锁继续: {}, 错误: {}", lock_path.display(), e);
+            return None;
+        }
+    };
+
+    let started = Instant::now();
+    loop {
+        match file.try_lock() {
+            Ok(()) => return Some(file),
+            Err(TryLockError::WouldBlock) if started.elapsed() < timeout => std::thread::sleep(LOCK_POLL_INTERVAL),
+            Err(TryLockError::WouldBlock) => {
+                log_important!(warn, "等待文件锁超时，不加锁继续: {}", lock_path.display());
+                return None;
+            }
+            Err(TryLockError::Error(e)) => {
+                log_important!(warn, "获取文件锁失败，不加锁继续: {}, 错误: {}", lock_path.display(), e);
+                return None;
+            }
+        }
+    }
+}
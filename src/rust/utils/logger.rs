@@ -115,7 +115,7 @@ pub fn init_logger(config: LogConfig) -> Result<(), Box<dyn std::error::Error>>
 /// 自动检测模式并初始化日志系统
 pub fn auto_init_logger() -> Result<(), Box<dyn std::error::Error>> {
     let args: Vec<String> = env::args().collect();
-    let is_mcp_mode = args.len() >= 3 && args[1] == "--mcp-request";
+    let is_mcp_mode = crate::app::is_request_invocation(&args);
     
     let config = if is_mcp_mode {
         // MCP 模式：输出到文件
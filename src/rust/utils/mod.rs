@@ -1,3 +1,5 @@
+pub mod fs;
 pub mod logger;
 
+pub use fs::{lock_file, write_atomic};
 pub use logger::{LogConfig, init_logger, auto_init_logger};